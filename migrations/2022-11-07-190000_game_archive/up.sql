@@ -0,0 +1,16 @@
+create table games
+(
+    id              varchar not null primary key,
+    chat_id         varchar not null,
+    game_type       varchar not null,
+    finished_at     varchar not null
+);
+
+create table game_results
+(
+    id              varchar not null primary key,
+    game_id         varchar not null,
+    player_id       varchar not null,
+    total           integer not null,
+    placement       integer not null
+);
@@ -0,0 +1,16 @@
+create table settlements
+(
+    chat_id         varchar not null primary key,
+    mode            varchar not null,
+    rate            integer not null
+);
+
+create table ledger_entries
+(
+    id              varchar not null primary key,
+    chat_id         varchar not null,
+    game_id         varchar not null,
+    player_id       varchar not null,
+    amount          integer not null,
+    created_at      varchar not null
+);
@@ -0,0 +1,11 @@
+create table seasons
+(
+    id              varchar not null primary key,
+    chat_id         varchar not null,
+    name            varchar not null,
+    scoring         varchar not null,
+    started_at      varchar not null,
+    ended_at        varchar
+);
+
+alter table games add column season_id varchar;
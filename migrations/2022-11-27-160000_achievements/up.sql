@@ -0,0 +1,10 @@
+create table achievements
+(
+    id              varchar not null primary key,
+    chat_id         varchar not null,
+    player_id       varchar not null,
+    game_id         varchar not null,
+    code            varchar not null,
+    description     varchar not null,
+    created_at      varchar not null
+);
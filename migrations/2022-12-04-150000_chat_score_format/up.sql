@@ -0,0 +1 @@
+alter table chats add column score_format varchar not null default 'image';
@@ -0,0 +1 @@
+alter table chats add column theme varchar not null default 'light';
@@ -0,0 +1,5 @@
+create table chat_rules
+(
+    chat_id         varchar not null primary key,
+    data            varchar not null
+);
@@ -0,0 +1,14 @@
+use teloxide::{Bot, types::Message};
+
+use crate::core::settlement::balance_report;
+
+pub fn balance(
+    _: &Bot,
+    message: Message,
+) -> String {
+    let chat_id = message.chat.id.to_string();
+    match balance_report(chat_id) {
+        Ok(message) => message,
+        Err(e) => format!("Error collecting balance: {}", e),
+    }
+}
\ No newline at end of file
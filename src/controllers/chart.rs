@@ -0,0 +1,44 @@
+use std::io::Error;
+
+use teloxide::{Bot, types::{Message, InputFile}};
+
+use crate::core::{game_handler::RUNNING_GAMES, message_helper::extract_message_text, chart::{ChartFormat, render_chart}, storage::store_document};
+
+use super::game_state::ScoreOutput;
+
+// /chart sends the running totals as an image, /chart svg as a document
+pub async fn chart(
+    _: &Bot,
+    message: Message,
+) -> Result<ScoreOutput, Error> {
+    let chat_id = message.chat.id.to_string();
+    let format = match extract_message_text(&message).and_then(|t| t.split_whitespace().nth(1).map(|f| f.to_lowercase())) {
+        None => ChartFormat::Png,
+        Some(format) if format == "png" => ChartFormat::Png,
+        Some(format) if format == "svg" => ChartFormat::Svg,
+        Some(_) => return Err(Error::other("Use /chart [png|svg]".to_string())),
+    };
+    let mut games = RUNNING_GAMES.lock().await;
+    let game = match games.get_mut(&chat_id) {
+        Some(game) => game,
+        None => return Err(Error::other(
+            "No game currently running..".to_string())
+        ),
+    };
+    let sheet = game.score_sheet()?;
+    match format {
+        ChartFormat::Png => {
+            let image = render_chart(&sheet, &game.round_groups(), format)?;
+            Ok(ScoreOutput::Photo(InputFile::memory(image).file_name("chart.png")))
+        },
+        ChartFormat::Svg => {
+            let svg = render_chart(&sheet, &game.round_groups(), format)?;
+            match store_document(&chat_id, "chart.svg", svg) {
+                Ok(file) => Ok(ScoreOutput::Document(file)),
+                Err(e) => Err(Error::other(
+                    format!("Error saving chart file: {}", e))
+                )
+            }
+        },
+    }
+}
\ No newline at end of file
@@ -1,72 +1,50 @@
-use std::{fs::File, io::{Error, ErrorKind, Write}};
+use std::io::Error;
 
 use teloxide::{Bot, types::{Message, InputFile}};
 
-use crate::core::game_handler::RUNNING_GAMES;
+use crate::core::{game_handler::RUNNING_GAMES, archive::finish_game, html_template::chat_theme, storage::store_document};
 
 pub async fn end_game(
     _: &Bot,
     message: Message,
-) -> Result<InputFile, Error> {
+) -> Result<(Option<InputFile>, InputFile, Vec<String>), Error> {
     let chat_id = message.chat.id.to_string();
     let mut games = RUNNING_GAMES.lock().await;
     // if no game struct -> return and notify invalid state
     if !games.contains_key(&chat_id) {
-        return Err(Error::new(
-            ErrorKind::Other, 
+        return Err(Error::other(
             "No game currently running..".to_string())
         );
     }
 
     // find game struct of the chat (should always be found due to previous step)
-    let game_to_play = match games.remove(&chat_id) {
+    let mut game_to_play = match games.remove(&chat_id) {
         Some(game) => game,
-        None => return Err(Error::new(
-            ErrorKind::Other, 
+        None => return Err(Error::other(
             "Error finding a running game! Invalid state on game fetch".to_string())
         )
     };
 
-    let file_name = append_file_to_path(game_to_play.generate_file_name());
-
-
-    // try to end game
-    let game_result = game_to_play.end_game();
-    let html = match game_result {
-        Ok(message) => message,
-        Err(e) => return Err(Error::new(
-            ErrorKind::Other, 
-            format!("Error ending game: {}", e))
-        )
+    // final sheet shown inline, the html document is kept as the full record
+    let image = match game_to_play.get_state_image() {
+        Ok(image) => Some(InputFile::memory(image).file_name("score.png")),
+        Err(e) => {
+            log::error!("Failed to draw final score: {}", e);
+            None
+        },
     };
-    match save_file(html, file_name.clone()) {
-        Ok(_) => Ok(send_file(file_name)),
-        Err(e) => Err(Error::new(
-            ErrorKind::Other, 
-            format!("Error saving game fle: {}", e))
-        )
-    }
-}
-
-fn append_file_to_path(generated_file_name: String) -> String {
-    format!("./res/games/{}", generated_file_name)
-        .replace(' ', "_")
-        .replace('-', "_")
-        .replace(':', "_")
-}
-
 
-fn send_file(file_name: String) -> InputFile {
-    InputFile::file(file_name)
-}
+    let file_name = game_to_play.generate_file_name();
 
-fn save_file(contents: String, file_name: String) -> Result<(), Error> {
-    let mut file = match File::create(file_name) {
-        Ok(file) => file,
+    // archive, highlights, settlement and the end-of-game rows
+    let (html, summary) = match finish_game(chat_id.clone(), game_to_play, &chat_theme(chat_id.clone())) {
+        Ok(finished) => finished,
         Err(e) => return Err(e),
     };
-    match file.write_all(contents.as_bytes()) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
+    match store_document(&chat_id, &file_name, html.into_bytes()) {
+        Ok(file) => Ok((image, file, summary)),
+        Err(e) => Err(Error::other(
+            format!("Error saving game fle: {}", e))
+        )
     }
 }
\ No newline at end of file
@@ -0,0 +1,41 @@
+use std::io::Error;
+
+use teloxide::{Bot, types::{Message, InputFile}};
+
+use crate::core::{game_handler::RUNNING_GAMES, message_helper::extract_message_text, export::{ExportFormat, archived_export}, storage::store_document};
+
+const USAGE: &str = "Use /export csv|json|xlsx|ods|pdf [game id]";
+
+// /export csv|json|xlsx|ods|pdf [game id], without an id the running game or else the last archived one
+pub async fn export(
+    _: &Bot,
+    message: Message,
+) -> Result<InputFile, Error> {
+    let chat_id = message.chat.id.to_string();
+    let text = match extract_message_text(&message) {
+        Some(text) => text,
+        None => return Err(Error::other("Failed to extract message text".to_string())),
+    };
+    let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+    let format = match args.first().and_then(|f| ExportFormat::from_arg(&f.to_lowercase())) {
+        Some(format) => format,
+        None => return Err(Error::other(USAGE.to_string())),
+    };
+    let game_export = match args.get(1) {
+        Some(game_id) => archived_export(chat_id.clone(), Some(game_id))?,
+        None => {
+            let mut games = RUNNING_GAMES.lock().await;
+            match games.get_mut(&chat_id) {
+                Some(game) => game.export()?,
+                None => archived_export(chat_id.clone(), None)?,
+            }
+        },
+    };
+    let contents = format.render(&game_export)?;
+    match store_document(&chat_id, &game_export.file_name(&format), contents) {
+        Ok(file) => Ok(file),
+        Err(e) => Err(Error::other(
+            format!("Error saving export file: {}", e))
+        )
+    }
+}
\ No newline at end of file
@@ -1,19 +1,63 @@
-use std::{fs::File, io::{Error, ErrorKind, Write}};
+use std::io::Error;
 
 use teloxide::{Bot, types::{Message, InputFile}};
 
-use crate::core::game_handler::RUNNING_GAMES;
+use crate::{core::{game_handler::RUNNING_GAMES, message_helper::extract_message_text, database::chat_operations::{get_chat, save_chat}, html_template::{Theme, THEMES}, storage::store_document}, models::chat::Chat};
+
+const SCORE_FORMATS: [&str; 3] = ["image", "text", "html"];
+
+pub enum ScoreOutput {
+    Photo(InputFile),
+    Document(InputFile),
+    Text(String),   // html formatted message
+}
 
 pub async fn game_state(
     _: &Bot,
     message: Message,
-) -> Result<InputFile, Error> {
+) -> Result<ScoreOutput, Error> {
     let chat_id = message.chat.id.to_string();
+    // /score image|text|html, without a format the chat's default is used
+    let args: Vec<String> = match extract_message_text(&message) {
+        Some(text) => text.split_whitespace().skip(1).map(|a| a.to_lowercase()).collect(),
+        None => vec![],
+    };
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    let chat = match get_chat(chat_id.clone()) {
+        Ok(Some(chat)) => chat,
+        Ok(None) => Chat::from(chat_id.clone()),
+        Err(e) => return Err(Error::other(
+            format!("Error fetching chat from DB: {}", e))
+        )
+    };
+    let format = match args.as_slice() {
+        ["default", format] if SCORE_FORMATS.contains(format) => {
+            return match save_chat(Chat { score_format: format.to_string(), ..chat }) {
+                Ok(_) => Ok(ScoreOutput::Text(format!("/score now sends the {} by default", format))),
+                Err(e) => Err(Error::other(
+                    format!("Error saving chat to DB: {}", e))
+                )
+            };
+        },
+        ["theme", theme] if THEMES.contains(theme) => {
+            return match save_chat(Chat { theme: theme.to_string(), ..chat }) {
+                Ok(_) => Ok(ScoreOutput::Text(format!("Html score sheets now use the {} theme", theme))),
+                Err(e) => Err(Error::other(
+                    format!("Error saving chat to DB: {}", e))
+                )
+            };
+        },
+        [format] if SCORE_FORMATS.contains(format) => format.to_string(),
+        [] => chat.score_format.clone(),
+        _ => return Err(Error::other(
+            "Use /score [image|text|html], /score default <image|text|html> or /score theme <light|dark|print>".to_string())
+        ),
+    };
+    let theme = Theme::from_name(&chat.theme).unwrap_or(Theme::Light);
     let mut games = RUNNING_GAMES.lock().await;
     // if no game struct -> return and notify invalid state
     if !games.contains_key(&chat_id) {
-        return Err(Error::new(
-            ErrorKind::Other, 
+        return Err(Error::other(
             "No game currently running..".to_string())
         );
     }
@@ -21,52 +65,95 @@ pub async fn game_state(
     // find game struct of the chat (should always be found due to previous step)
     let game_to_play = match games.get_mut(&chat_id) {
         Some(game) => game,
-        None => return Err(Error::new(
-            ErrorKind::Other, 
+        None => return Err(Error::other(
             "Error finding a running game! Invalid state on game fetch".to_string())
         )
     };
 
-    let file_name = append_file_to_path(game_to_play.generate_file_name());
-
+    match format.as_str() {
+        "image" => return match game_to_play.get_state_image() {
+            Ok(image) => Ok(ScoreOutput::Photo(InputFile::memory(image).file_name("score.png"))),
+            Err(e) => Err(Error::other(
+                format!("Error drawing score: {}", e))
+            )
+        },
+        "text" => return match game_to_play.get_state_text() {
+            Ok(text) => Ok(ScoreOutput::Text(text)),
+            Err(e) => Err(Error::other(
+                format!("Error writing score: {}", e))
+            )
+        },
+        _ => (),
+    };
 
     // try to end game
-    let game_result = game_to_play.get_state();
+    let game_result = game_to_play.get_state(&theme);
     let html = match game_result {
         Ok(message) => message,
-        Err(e) => return Err(Error::new(
-            ErrorKind::Other, 
+        Err(e) => return Err(Error::other(
             format!("Error ending game: {}", e))
         )
     };
-    match save_file(html, file_name.clone()) {
-        Ok(_) => Ok(send_file(file_name)),
-        Err(e) => Err(Error::new(
-            ErrorKind::Other, 
+    match store_document(&chat_id, &game_to_play.generate_file_name(), html.into_bytes()) {
+        Ok(file) => Ok(ScoreOutput::Document(file)),
+        Err(e) => Err(Error::other(
             format!("Error saving game fle: {}", e))
         )
     }
 }
 
-fn append_file_to_path(generated_file_name: String) -> String {
-    format!("./res/games/{}", generated_file_name)
-        .replace(' ', "_")
-        .replace('-', "_")
-        .replace(':', "_")
-}
+#[cfg(test)]
+mod tests {
+    use teloxide::Bot;
 
+    use crate::{controllers::{register::register, new_game::new_game, score_round::score_round}, core::{game_handler::RUNNING_GAMES, html_template::Theme, database::test_database::{setup_database, message}}};
 
-fn send_file(file_name: String) -> InputFile {
-    InputFile::file(file_name)
-}
+    use super::{game_state, ScoreOutput};
 
-fn save_file(contents: String, file_name: String) -> Result<(), Error> {
-    let mut file = match File::create(file_name) {
-        Ok(file) => file,
-        Err(e) => return Err(e),
-    };
-    match file.write_all(contents.as_bytes()) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
+    const CHAT_ID: i64 = -1048;
+    const SCRIPT: &str = "<SCRIPT>ALERT(1)</SCRIPT>";
+    const IMAGE: &str = "\"><IMG/SRC=X/ONERROR=ALERT(1)>";
+    const QUOTED: &str = "'ONMOUSEOVER='ALERT(1)";
+    const AMPERSAND: &str = "TOM&JERRY";
+
+    fn assert_no_markup(document: &str) {
+        for raw in ["<SCRIPT", "<IMG", "\"><", "'ONMOUSEOVER", "TOM&JERRY", "&JER"] {
+            assert!(!document.contains(raw), "{} is not escaped in:\n{}", raw, document);
+        }
+    }
+
+    fn assert_escaped(document: &str) {
+        assert_no_markup(document);
+        assert!(document.contains("&lt;SCRIPT&gt;ALERT(1)&lt;/SCRIPT&gt;"));
+        assert!(document.contains("&quot;&gt;&lt;IMG/SRC=X/ONERROR=ALERT(1)&gt;"));
+        assert!(document.contains("&#39;ONMOUSEOVER=&#39;ALERT(1)"));
+        assert!(document.contains("TOM&amp;JERRY"));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn hostile_names_are_escaped_on_every_score_sheet() {
+        setup_database();
+        let bot = Bot::new("test");
+        let registered = register(&bot, message(CHAT_ID, &format!("/register {} {} {} {}", SCRIPT, IMAGE, QUOTED, AMPERSAND)));
+        assert_eq!(registered.matches("created!").count(), 4, "{}", registered);
+        new_game(&bot, message(CHAT_ID, "/newgame")).await;
+        for round in [
+            format!("/round I3,15 {},M:{} {},T", SCRIPT, QUOTED, IMAGE),
+            format!("/round I2,-10 {},R {}", QUOTED, AMPERSAND),
+        ] {
+            let result = score_round(&bot, message(CHAT_ID, &round)).await;
+            assert!(!result.starts_with("Error"), "{}", result);
+        }
+
+        match game_state(&bot, message(CHAT_ID, "/score text")).await {
+            // the text table shortens names, so only the markup is checked
+            Ok(ScoreOutput::Text(text)) => assert_no_markup(&text),
+            _ => panic!("Expected the text scoreboard"),
+        };
+        let mut games = RUNNING_GAMES.lock().await;
+        let game = games.get_mut(&CHAT_ID.to_string()).expect("Game is not running");
+        for theme in [Theme::Light, Theme::Dark, Theme::Print] {
+            assert_escaped(&game.get_state(&theme).expect("Error rendering score sheet"));
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use teloxide::{Bot, types::Message};
+
+use crate::core::{message_helper::extract_message_text, head_to_head::head_to_head};
+
+pub fn h2h(
+    _: &Bot,
+    message: Message,
+) -> String {
+    let chat_id = message.chat.id.to_string();
+    let text = match extract_message_text(&message) {
+        Some(text) => text,
+        None => return "Failed to extract message text".to_string(),
+    };
+    match head_to_head(chat_id, &text) {
+        Ok(message) => message,
+        Err(e) => format!("Error comparing players: {}", e),
+    }
+}
\ No newline at end of file
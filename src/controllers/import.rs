@@ -0,0 +1,87 @@
+use std::io::Error;
+
+use teloxide::{Bot, net::Download, prelude::Requester, types::Message};
+
+use crate::core::{game_handler::RUNNING_GAMES, message_helper::extract_message_text, archive::archive_game, import::{parse_import, resolve_players, build_game, register_players}};
+
+const USAGE: &str = "Reply to an exported csv or json document with /import archive|play [create]";
+
+// /import archive|play [create] as a reply to a document in the /export format
+pub async fn import(
+    bot: &Bot,
+    message: Message,
+) -> Result<String, Error> {
+    let chat_id = message.chat.id.to_string();
+    let text = match extract_message_text(&message) {
+        Some(text) => text,
+        None => return Err(Error::other(USAGE.to_string())),
+    };
+    let args: Vec<String> = text.split_whitespace().skip(1).map(|a| a.to_lowercase()).collect();
+    let (play, create) = match args.iter().map(|a| a.as_str()).collect::<Vec<&str>>().as_slice() {
+        ["archive"] => (false, false),
+        ["archive", "create"] => (false, true),
+        ["play"] => (true, false),
+        ["play", "create"] => (true, true),
+        _ => return Err(Error::other(USAGE.to_string())),
+    };
+    let document = match message.reply_to_message().and_then(|m| m.document()) {
+        Some(document) => document.clone(),
+        None => return Err(Error::other(USAGE.to_string())),
+    };
+
+    let file = match bot.get_file(document.file.id.clone()).await {
+        Ok(file) => file,
+        Err(e) => return Err(Error::other(format!("Error fetching the document: {}", e))),
+    };
+    let mut contents = vec![];
+    if let Err(e) = bot.download_file(&file.path, &mut contents).await {
+        return Err(Error::other(format!("Error downloading the document: {}", e)));
+    }
+    let contents = match String::from_utf8(contents) {
+        Ok(contents) => contents,
+        Err(_) => return Err(Error::other("The document is not a text file".to_string())),
+    };
+
+    let mut game_export = parse_import(&contents)?;
+    let (players, new_players) = resolve_players(chat_id.clone(), &mut game_export, create)?;
+    // the game is built (and its tags checked) before anyone is registered
+    let mut game = build_game(chat_id.clone(), &game_export, players)?;
+    let mut lines = vec![];
+    if play {
+        // lock only once the document is read, so a slow download doesn't hold up other chats
+        let mut games = RUNNING_GAMES.lock().await;
+        if games.contains_key(&chat_id) {
+            return Err(Error::other(
+                "A game is already running. End it with /endgame first".to_string())
+            );
+        }
+        let registered = register_players(new_players)?;
+        if !registered.is_empty() {
+            lines.push(format!("Registered: {}", registered.join(", ")));
+        }
+        games.insert(chat_id, game);
+        lines.push(format!("Imported {} rounds of {}. Continue with /round", game_export.rounds, game_export.game_type));
+        if game_export.game_type == "tarok" && game_export.seating.is_none() {
+            lines.push("The seating is not in the document, use /seat <players> to track the dealer".to_string());
+        }
+        return Ok(lines.join("\n"));
+    }
+
+    if game_export.rounds == 0 {
+        return Err(Error::other("The document has no rounds to archive".to_string()));
+    }
+    let sheet = game.score_sheet()?;
+    let export = game.export()?;
+    let registered = register_players(new_players)?;
+    if !registered.is_empty() {
+        lines.push(format!("Registered: {}", registered.join(", ")));
+    }
+    let archived = archive_game(chat_id, &sheet, &export)?;
+    lines.push(format!(
+        "Archived {} rounds of {} as game {}",
+        sheet.rounds,
+        archived.game_type,
+        archived.id.split('-').next().unwrap_or(""),
+    ));
+    Ok(lines.join("\n"))
+}
\ No newline at end of file
@@ -0,0 +1,41 @@
+use std::io::Error;
+
+use teloxide::{Bot, types::{Message, InputFile}};
+
+use crate::core::{message_helper::extract_message_text, leaderboard::{parse_leaderboard_args, build_leaderboard, leaderboard_to_text, leaderboard_to_html, leaderboard_to_grid}, html_template::chat_theme, score_image::render_png, storage::store_document};
+
+use super::game_state::ScoreOutput;
+
+pub fn leaderboard(
+    _: &Bot,
+    message: Message,
+) -> Result<(String, ScoreOutput), Error> {
+    let chat_id = message.chat.id.to_string();
+    let text = match extract_message_text(&message) {
+        Some(text) => text,
+        None => return Err(Error::other("Failed to extract message text".to_string())),
+    };
+    let (window, game_type, image) = parse_leaderboard_args(&text)?;
+    let board = match build_leaderboard(chat_id.clone(), window, game_type) {
+        Ok(board) => board,
+        Err(e) => return Err(Error::other(
+            format!("Error building leaderboard: {}", e))
+        ),
+    };
+
+    if image {
+        return match render_png(&leaderboard_to_grid(&board)) {
+            Ok(png) => Ok((leaderboard_to_text(&board), ScoreOutput::Photo(InputFile::memory(png).file_name("leaderboard.png")))),
+            Err(e) => Err(Error::other(
+                format!("Error drawing leaderboard: {}", e))
+            )
+        };
+    }
+    let html = leaderboard_to_html(&board, &chat_theme(chat_id.clone()));
+    match store_document(&chat_id, "leaderboard.html", html.into_bytes()) {
+        Ok(file) => Ok((leaderboard_to_text(&board), ScoreOutput::Document(file))),
+        Err(e) => Err(Error::other(
+            format!("Error saving leaderboard file: {}", e))
+        )
+    }
+}
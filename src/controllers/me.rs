@@ -0,0 +1,19 @@
+use teloxide::{Bot, types::Message};
+
+use crate::core::{message_helper::extract_message_text, achievements::player_achievements};
+
+pub fn me(
+    _: &Bot,
+    message: Message,
+) -> String {
+    let text = match extract_message_text(&message) {
+        Some(text) => text,
+        None => return "Failed to extract message text".to_string(),
+    };
+    // players are usually registered under their first name
+    let sender_name = message.from().map(|user| user.first_name.clone());
+    match player_achievements(&text, sender_name) {
+        Ok(message) => message,
+        Err(e) => format!("Error listing achievements: {}", e),
+    }
+}
\ No newline at end of file
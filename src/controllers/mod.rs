@@ -2,4 +2,20 @@ pub mod register;
 pub mod new_game;
 pub mod score_round;
 pub mod end_game;
-pub mod game_state;
\ No newline at end of file
+pub mod game_state;
+pub mod rules;
+pub mod seat;
+pub mod radlc;
+pub mod explain;
+pub mod stats;
+pub mod leaderboard;
+pub mod rating;
+pub mod settle;
+pub mod balance;
+pub mod season;
+pub mod tournament;
+pub mod h2h;
+pub mod me;
+pub mod export;
+pub mod import;
+pub mod chart;
\ No newline at end of file
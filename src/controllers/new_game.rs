@@ -9,21 +9,18 @@ pub async fn new_game(
     let chat_id = message.chat.id.to_string();
     let mut games = RUNNING_GAMES.lock().await;
     
-    // if no game struct -> create a game struct
-    if !games.contains_key(&chat_id) {
-        games.insert(chat_id.clone(), get_chat_default_game());
+    // a running game has to be ended first
+    if games.contains_key(&chat_id) {
+        return "A game is already running. End it with /endgame first".to_string();
     }
 
-    // find game struct of the chat (should always be created due to previous step)
-    let game_to_play = match games.get_mut(&chat_id) {
-        Some(game) => game,
-        None => return "Error starting a game! Invalid state on game fetch".to_string()
-    };
-
-    // try to start game
-    let game_result = game_to_play.start_game();
-    match game_result {
-        Ok(message) => message,
+    // the game is only kept once it started (seating parsed and checked)
+    let mut game_to_play = get_chat_default_game();
+    match game_to_play.start_game(message) {
+        Ok(message) => {
+            games.insert(chat_id, game_to_play);
+            message
+        },
         Err(e) => format!("Error starting game: {}", e) 
     }
 }
@@ -0,0 +1,18 @@
+use teloxide::{Bot, types::Message};
+
+use crate::core::{message_helper::extract_message_text, rating::rating_report};
+
+pub fn rating(
+    _: &Bot,
+    message: Message,
+) -> String {
+    let chat_id = message.chat.id.to_string();
+    let text = match extract_message_text(&message) {
+        Some(text) => text,
+        None => return "Failed to extract message text".to_string(),
+    };
+    match rating_report(chat_id, &text) {
+        Ok(message) => message,
+        Err(e) => format!("Error calculating ratings: {}", e),
+    }
+}
\ No newline at end of file
@@ -1,6 +1,6 @@
 use teloxide::{Bot, types::Message};
 
-use crate::core::game_handler::RUNNING_GAMES;
+use crate::core::{game_handler::RUNNING_GAMES, archive::save_round_rows};
 
 pub async fn score_round(
     _: &Bot,
@@ -23,7 +23,10 @@ pub async fn score_round(
     // try to handle message
     let game_result = game_to_play.handle_round(message);
     match game_result {
-        Ok(message) => message,
+        Ok(message) => match save_round_rows(game_to_play.as_mut()) {
+            Ok(_) => message,
+            Err(e) => format!("{}\n{}", message, e),
+        },
         Err(e) => format!("Error handling round: {}", e) 
     }
 }
\ No newline at end of file
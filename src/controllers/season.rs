@@ -0,0 +1,33 @@
+use std::io::Error;
+
+use teloxide::{Bot, types::{Message, InputFile}};
+
+use crate::core::{message_helper::extract_message_text, season::season_command, storage::store_document};
+
+pub fn season(
+    _: &Bot,
+    message: Message,
+) -> Result<(String, Option<InputFile>), Error> {
+    let chat_id = message.chat.id.to_string();
+    let text = match extract_message_text(&message) {
+        Some(text) => text,
+        None => return Err(Error::other("Failed to extract message text".to_string())),
+    };
+    let (reply, report) = match season_command(chat_id.clone(), &text) {
+        Ok(resp) => resp,
+        Err(e) => return Err(Error::other(
+            format!("Error managing season: {}", e))
+        ),
+    };
+    let html = match report {
+        Some(html) => html,
+        None => return Ok((reply, None)),
+    };
+
+    match store_document(&chat_id, "season.html", html.into_bytes()) {
+        Ok(file) => Ok((reply, Some(file))),
+        Err(e) => Err(Error::other(
+            format!("Error saving season file: {}", e))
+        )
+    }
+}
\ No newline at end of file
@@ -0,0 +1,18 @@
+use teloxide::{Bot, types::Message};
+
+use crate::core::{message_helper::extract_message_text, settlement::settlement_command};
+
+pub fn settle(
+    _: &Bot,
+    message: Message,
+) -> String {
+    let chat_id = message.chat.id.to_string();
+    let text = match extract_message_text(&message) {
+        Some(text) => text,
+        None => return "Failed to extract message text".to_string(),
+    };
+    match settlement_command(chat_id, &text) {
+        Ok(message) => message,
+        Err(e) => format!("Error changing settlement: {}", e),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,18 @@
+use teloxide::{Bot, types::Message};
+
+use crate::{core::message_helper::extract_message_text, games::tarok::stats::tarok_stats};
+
+pub fn stats(
+    _: &Bot,
+    message: Message,
+) -> String {
+    let chat_id = message.chat.id.to_string();
+    let player_name = match extract_message_text(&message) {
+        Some(text) => text.split_whitespace().nth(1).map(|name| name.to_string()),
+        None => None,
+    };
+    match tarok_stats(chat_id, player_name) {
+        Ok(message) => message,
+        Err(e) => format!("Error collecting stats: {}", e),
+    }
+}
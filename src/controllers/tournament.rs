@@ -0,0 +1,75 @@
+use std::io::Error;
+
+use teloxide::{Bot, types::{Message, InputFile}};
+
+use crate::core::{game_handler::TOURNAMENTS, message_helper::extract_message_text, tournament::Tournament, html_template::chat_theme, storage::store_document};
+
+const USAGE: &str = "Use /tournament join|leave <players>, next, round <table> <round>, score <table>, finish <table>, standings, end";
+
+pub async fn tournament(
+    _: &Bot,
+    message: Message,
+) -> Result<(String, Option<InputFile>), Error> {
+    let chat_id = message.chat.id.to_string();
+    let text = match extract_message_text(&message) {
+        Some(text) => text,
+        None => return Err(Error::other("Failed to extract message text".to_string())),
+    };
+    let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+    let mut tournaments = TOURNAMENTS.lock().await;
+
+    // joining is the only way to start a tournament
+    if args.first() == Some(&"join") && !tournaments.contains_key(&chat_id) {
+        tournaments.insert(chat_id.clone(), Tournament::new());
+    }
+    let current = match tournaments.get_mut(&chat_id) {
+        Some(tournament) => tournament,
+        None => return Err(Error::other(
+            "No tournament running.. Start one with /tournament join <players>".to_string())
+        ),
+    };
+
+    let reply = match (args.first(), args.get(1)) {
+        (Some(&"join"), _) => current.join(&args[1..]),
+        (Some(&"leave"), _) => current.leave(&args[1..]),
+        (Some(&"next"), _) => current.next_round(),
+        (Some(&"round"), Some(table)) => current.play_round(chat_id.clone(), table, &args[2..].join(" ")),
+        (Some(&"standings"), _) | (None, _) => Ok(current.standings()),
+        (Some(&"end"), _) => {
+            let ended = current.end();
+            if ended.is_ok() {
+                tournaments.remove(&chat_id);
+            }
+            ended
+        },
+        (Some(&"finish"), Some(table)) => {
+            let (reply, html) = match current.finish_table(chat_id.clone(), table, &chat_theme(chat_id.clone())) {
+                Ok(finished) => finished,
+                Err(e) => return Err(e),
+            };
+            return match store_document(&chat_id, &format!("table_{}_tarok.html", table), html.into_bytes()) {
+                Ok(file) => Ok((reply, Some(file))),
+                Err(e) => Err(Error::other(
+                    format!("Error saving game fle: {}", e))
+                )
+            };
+        },
+        (Some(&"score"), Some(table)) => {
+            let html = match current.table_state(table, &chat_theme(chat_id.clone())) {
+                Ok(html) => html,
+                Err(e) => return Err(e),
+            };
+            return match store_document(&chat_id, &format!("table_{}_tarok.html", table), html.into_bytes()) {
+                Ok(file) => Ok((format!("Table {}", table), Some(file))),
+                Err(e) => Err(Error::other(
+                    format!("Error saving game fle: {}", e))
+                )
+            };
+        },
+        _ => Err(Error::other(USAGE.to_string())),
+    };
+    match reply {
+        Ok(reply) => Ok((reply, None)),
+        Err(e) => Err(e),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,247 @@
+use std::io::Error;
+
+use crate::models::{achievement::PlayerAchievement, user::User};
+
+use super::{traits::AchievementRule, score_sheet::ScoreSheet, database::{achievement_operations::{insert_achievements, get_achievements_by_player}, user_operations::get_user_by_name}};
+
+pub struct Achievement {
+    pub code: &'static str,
+    pub player_id: String,
+    pub description: String,
+    pub once: bool,     // awarded only the first time a player earns it
+}
+
+pub fn generic_achievement_rules() -> Vec<Box<dyn AchievementRule>> {
+    vec![
+        Box::new(BiggestSwing),
+        Box::new(LongestWinStreak),
+        Box::new(NeverLostRound),
+    ]
+}
+
+// Runs the rules over a finished game, persists what was earned and returns the highlights
+pub fn award_achievements(
+    chat_id: String,
+    sheet: &ScoreSheet,
+    rules: Vec<Box<dyn AchievementRule>>,
+) -> Result<Option<String>, Error> {
+    let mut awarded = vec![];
+    for achievement in rules.iter().flat_map(|r| r.detect(sheet)) {
+        if achievement.once {
+            let earned = match get_achievements_by_player(achievement.player_id.clone()) {
+                Ok(earned) => earned,
+                Err(e) => return Err(Error::other(format!("Error fetching achievements from DB: {}", e))),
+            };
+            if earned.iter().any(|a| a.code == achievement.code) {
+                continue;
+            }
+        }
+        awarded.push(achievement);
+    }
+    if awarded.is_empty() {
+        return Ok(None);
+    }
+    let rows = awarded
+        .iter()
+        .map(|a| PlayerAchievement::from(
+            chat_id.clone(),
+            a.player_id.clone(),
+            sheet.game_id.clone(),
+            a.code.to_string(),
+            a.description.clone()
+        ))
+        .collect();
+    if let Err(e) = insert_achievements(rows) {
+        return Err(Error::other(format!("Error saving achievements to DB: {}", e)));
+    }
+    let mut lines = vec!["Highlights:".to_string()];
+    for achievement in awarded.iter() {
+        lines.push(format!("{}: {}", player_name(&sheet.players, &achievement.player_id), achievement.description));
+    }
+    Ok(Some(lines.join("\n")))
+}
+
+// /me [player], without a player the sender's telegram name is tried
+pub fn player_achievements(text: &str, sender_name: Option<String>) -> Result<String, Error> {
+    let name = match text.split_whitespace().nth(1).map(|n| n.to_string()).or(sender_name) {
+        Some(name) => name,
+        None => return Err(Error::other("Use /me <player>".to_string())),
+    };
+    let user = match get_user_by_name(name.to_uppercase()) {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(Error::other(format!("Unknown player {}. Use /me <player>", name))),
+        Err(e) => return Err(Error::other(format!("Error fetching user from DB: {}", e))),
+    };
+    let earned = match get_achievements_by_player(user.id.clone()) {
+        Ok(earned) => earned,
+        Err(e) => return Err(Error::other(format!("Error fetching achievements from DB: {}", e))),
+    };
+    if earned.is_empty() {
+        return Ok(format!("{} has no achievements yet!", user.name));
+    }
+    let mut lines = vec![format!("Achievements of {}:", user.name)];
+    for achievement in earned.iter() {
+        lines.push(format!(
+            "{} {}",
+            achievement.created_at.split('T').next().unwrap_or(""),
+            achievement.description
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+struct BiggestSwing;
+struct LongestWinStreak;
+struct NeverLostRound;
+
+impl AchievementRule for BiggestSwing {
+    fn detect(&self, sheet: &ScoreSheet) -> Vec<Achievement> {
+        let mut biggest: Option<(&String, usize, i32)> = None;
+        for player in sheet.players.iter() {
+            let scores = match sheet.score.get(&player.id) {
+                Some(scores) => scores,
+                None => continue,
+            };
+            for (round, score) in scores.iter().enumerate() {
+                if let Some(score) = score {
+                    if biggest.is_none_or(|(_, _, b)| score.abs() > b.abs()) {
+                        biggest = Some((&player.id, round, *score));
+                    }
+                }
+            }
+        }
+        match biggest {
+            Some((player_id, round, score)) if score != 0 => vec![Achievement {
+                code: "biggest_swing",
+                player_id: player_id.clone(),
+                description: format!("Biggest swing: {:+} in round {}", score, round + 1),
+                once: false,
+            }],
+            _ => vec![],
+        }
+    }
+}
+
+impl AchievementRule for LongestWinStreak {
+    fn detect(&self, sheet: &ScoreSheet) -> Vec<Achievement> {
+        let mut streaks = vec![];
+        for player in sheet.players.iter() {
+            let scores = match sheet.score.get(&player.id) {
+                Some(scores) => scores,
+                None => continue,
+            };
+            let (mut longest, mut current) = (0, 0);
+            for score in scores.iter() {
+                match score {
+                    Some(score) if *score > 0 => current += 1,
+                    Some(_) => current = 0,
+                    None => (), // sitting out a round doesn't break the streak
+                };
+                longest = longest.max(current);
+            }
+            streaks.push((&player.id, longest));
+        }
+        let longest = streaks.iter().map(|(_, s)| *s).max().unwrap_or(0);
+        if longest < 3 {
+            return vec![];
+        }
+        streaks
+            .into_iter()
+            .filter(|(_, s)| *s == longest)
+            .map(|(player_id, _)| Achievement {
+                code: "win_streak",
+                player_id: player_id.clone(),
+                description: format!("Longest win streak: {} rounds", longest),
+                once: false,
+            })
+            .collect()
+    }
+}
+
+impl AchievementRule for NeverLostRound {
+    fn detect(&self, sheet: &ScoreSheet) -> Vec<Achievement> {
+        if sheet.rounds < 3 {
+            return vec![];
+        }
+        sheet.players
+            .iter()
+            .filter(|p| match sheet.score.get(&p.id) {
+                Some(scores) => scores.iter().any(|s| s.is_some()) && scores.iter().flatten().all(|s| *s >= 0),
+                None => false,
+            })
+            .map(|p| Achievement {
+                code: "never_lost",
+                player_id: p.id.clone(),
+                description: "Never lost a round".to_string(),
+                once: false,
+            })
+            .collect()
+    }
+}
+
+fn player_name(players: &[User], id: &String) -> String {
+    match players.iter().find(|p| &p.id == id) {
+        Some(player) => player.name.clone(),
+        None => id.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        core::{score_sheet::ScoreSheet, traits::AchievementRule, database::test_database::user},
+        models::user::User,
+    };
+
+    use super::{LongestWinStreak, NeverLostRound};
+
+    const CHAT_ID: i64 = -1040;
+
+    fn sheet(players: &[User], scores: &[Vec<Option<i32>>]) -> ScoreSheet {
+        ScoreSheet {
+            game_id: uuid::Uuid::new_v4().to_string(),
+            game_type: "tarok".to_string(),
+            players: players.to_vec(),
+            score: players.iter().zip(scores.iter()).map(|(p, s)| (p.id.clone(), s.clone())).collect(),
+            rounds: scores.iter().map(|s| s.len()).max().unwrap_or(0) as i32,
+            totals: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn win_streak_starts_at_three_rounds() {
+        let (ana, bor) = (user(CHAT_ID, "ANA"), user(CHAT_ID, "BOR"));
+        let two = sheet(&[ana.clone(), bor.clone()], &[
+            vec![Some(10), Some(10), Some(-10), Some(10), Some(10)],
+            vec![Some(-10), Some(10), Some(0), Some(-10), None],
+        ]);
+        assert!(LongestWinStreak.detect(&two).is_empty());
+
+        // sitting out doesn't break a streak, a round without points does
+        let three = sheet(&[ana.clone(), bor.clone()], &[
+            vec![Some(10), None, Some(10), Some(10), Some(0)],
+            vec![Some(10), Some(10), Some(0), Some(10), Some(10)],
+        ]);
+        let streaks = LongestWinStreak.detect(&three);
+        assert_eq!(streaks.len(), 1);
+        assert_eq!(streaks[0].player_id, ana.id);
+        assert_eq!(streaks[0].description, "Longest win streak: 3 rounds");
+    }
+
+    #[test]
+    fn never_lost_needs_three_rounds() {
+        let (ana, bor, cene) = (user(CHAT_ID, "ANA"), user(CHAT_ID, "BOR"), user(CHAT_ID, "CENE"));
+        let short = sheet(&[ana.clone(), bor.clone()], &[vec![Some(10), Some(0)], vec![Some(-10), Some(5)]]);
+        assert!(NeverLostRound.detect(&short).is_empty());
+
+        let long = sheet(&[ana.clone(), bor.clone(), cene.clone()], &[
+            vec![Some(10), Some(0), None],
+            vec![Some(-10), Some(5), Some(5)],
+            vec![None, None, None],
+        ]);
+        let unbeaten = NeverLostRound.detect(&long);
+        assert_eq!(unbeaten.iter().map(|a| a.player_id.clone()).collect::<Vec<String>>(), vec![ana.id.clone()]);
+    }
+}
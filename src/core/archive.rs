@@ -0,0 +1,136 @@
+use std::io::Error;
+
+use chrono::Utc;
+
+use crate::models::game::{ArchivedGame, GameResult};
+
+use super::{score_sheet::ScoreSheet, export::GameExport, database::{game_operations::insert_game, season_operations::get_active_season, round_operations::insert_rounds}, traits::Game, html_template::Theme, achievements::award_achievements, settlement::settle_game};
+
+pub fn archive_game(chat_id: String, sheet: &ScoreSheet, export: &GameExport) -> Result<ArchivedGame, Error> {
+    let placements = sheet.placements();
+    let mut results = vec![];
+    for player in sheet.players.iter() {
+        let total = match sheet.totals.get(&player.id) {
+            Some(total) => *total,
+            None => 0,
+        };
+        let placement = match placements.get(&player.id) {
+            Some(placement) => *placement,
+            None => sheet.players.len() as i32,
+        };
+        results.push(GameResult::from(sheet.game_id.clone(), player.id.clone(), total, placement));
+    }
+    // games finished while a season is running count towards it
+    let season_id = match get_active_season(chat_id.clone()) {
+        Ok(season) => season.map(|s| s.id),
+        Err(e) => return Err(Error::other(format!("Error fetching season: {}", e))),
+    };
+    // the full state is kept so the game can be exported later
+    let data = match serde_json::to_string(export) {
+        Ok(data) => data,
+        Err(e) => return Err(Error::other(format!("Error serializing game: {}", e))),
+    };
+    let game = ArchivedGame::from(
+        sheet.game_id.clone(), 
+        chat_id, 
+        sheet.game_type.clone(), 
+        Utc::now().to_rfc3339(),
+        season_id,
+        Some(data),
+    );
+    match insert_game(game, results) {
+        Ok(game) => Ok(game),
+        Err(e) => Err(Error::other(format!("Error archiving game: {}", e))),
+    }
+}
+
+// Keeps the rounds played since the last call for statistics.
+pub fn save_round_rows(game: &mut dyn Game) -> Result<(), Error> {
+    let rows = game.take_round_rows();
+    if rows.is_empty() {
+        return Ok(());
+    }
+    match insert_rounds(rows) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::other(format!("Failed to persist round: {}", e))),
+    }
+}
+
+// Everything that happens once a game is over: final totals go to the archive, highlights
+// are handed out, the game is settled and the end-of-game rows are written.
+// Returns the html record of the game and the summary lines (highlights, settlement).
+pub fn finish_game(chat_id: String, mut game: Box<dyn Game + Send>, theme: &Theme) -> Result<(String, Vec<String>), Error> {
+    let mut summary = vec![];
+    if let Err(e) = save_round_rows(game.as_mut()) {
+        log::error!("{}", e);
+    }
+    let end_rows = game.end_of_game_rows();
+    if !end_rows.is_empty() {
+        if let Err(e) = insert_rounds(end_rows) {
+            log::error!("Failed to persist end of game: {}", e);
+        }
+    }
+    match game.score_sheet() {
+        Ok(sheet) if sheet.rounds > 0 => {
+            let archived = match game.export() {
+                Ok(export) => archive_game(chat_id.clone(), &sheet, &export).map(|_| ()),
+                Err(e) => Err(Error::other(format!("Error exporting game: {}", e))),
+            };
+            // highlights and money refer to the archived game, so they wait for a game that made it there
+            match archived {
+                Ok(_) => {
+                    match award_achievements(chat_id.clone(), &sheet, game.achievement_rules()) {
+                        Ok(Some(highlights)) => summary.push(highlights),
+                        Ok(None) => (),
+                        Err(e) => log::error!("Failed to award achievements for game {}: {}", sheet.game_id, e),
+                    };
+                    match settle_game(chat_id, &sheet) {
+                        Ok(Some(settlement)) => summary.push(settlement),
+                        Ok(None) => (),
+                        Err(e) => summary.push(format!("Error settling game: {}", e)),
+                    };
+                },
+                Err(e) => {
+                    log::error!("Failed to archive game {}: {}", sheet.game_id, e);
+                    summary.push(format!("{}\nThe game is not archived, so there are no highlights or settlement", e));
+                },
+            };
+        },
+        Ok(_) => (),
+        Err(e) => log::error!("Failed to collect final score for archive: {}", e),
+    };
+    match game.end_game(theme) {
+        Ok(html) => Ok((html, summary)),
+        Err(e) => Err(Error::other(format!("Error ending game: {}", e))),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::{traits::Game, html_template::Theme, settlement::settlement_command, database::test_database::{setup_database, register, message}},
+        games::tarok::game::Tarok,
+    };
+
+    use super::{archive_game, finish_game};
+
+    const CHAT_ID: i64 = -1034;
+
+    #[test]
+    fn failed_archive_is_reported_and_skips_the_rest() {
+        setup_database();
+        register(CHAT_ID, "ARCANA");
+        register(CHAT_ID, "ARCBOR");
+        settlement_command(CHAT_ID.to_string(), "/settle mode zerosum rate 10").expect("Error enabling settlement");
+        let mut game = Tarok::new();
+        game.handle_round(message(CHAT_ID, "/round I3,10 ARCANA ARCBOR")).expect("Error playing round");
+        // the game id is already taken, so archiving it again fails
+        let sheet = game.score_sheet().expect("Error collecting sheet");
+        archive_game(CHAT_ID.to_string(), &sheet, &game.export().expect("Error exporting game")).expect("Error archiving game");
+
+        let (_, summary) = finish_game(CHAT_ID.to_string(), Box::new(game), &Theme::Light).expect("Error finishing game");
+        assert_eq!(summary.len(), 1, "{:?}", summary);
+        assert!(summary[0].starts_with("Error archiving game"), "{}", summary[0]);
+    }
+}
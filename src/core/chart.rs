@@ -0,0 +1,297 @@
+use std::io::Error;
+
+use embedded_graphics::{
+    pixelcolor::Rgb888,
+    prelude::*,
+    primitives::{Polyline, PrimitiveStyle, Rectangle},
+    text::Alignment,
+};
+
+use super::{score_sheet::ScoreSheet, score_image::{Canvas, draw_text, encode_png}, html_template::escape_html};
+
+const WIDTH: i32 = 680;
+const HEIGHT: i32 = 360;
+const PLOT_LEFT: i32 = 56;
+const PLOT_RIGHT: i32 = WIDTH - 150;
+const PLOT_TOP: i32 = 16;
+const PLOT_BOTTOM: i32 = HEIGHT - 56;
+const MAX_NAME: usize = 8;
+
+const PLAYER_COLORS: [(u8, u8, u8); 8] = [
+    (31, 119, 180), (255, 127, 14), (44, 160, 44), (214, 39, 40),
+    (148, 103, 189), (140, 86, 75), (227, 119, 194), (127, 127, 127),
+];
+const GROUP_COLORS: [(u8, u8, u8); 7] = [
+    (222, 235, 247), (254, 230, 206), (229, 245, 224), (252, 224, 222),
+    (239, 237, 245), (255, 247, 188), (235, 235, 235),
+];
+
+pub enum ChartFormat {
+    Svg,
+    Png,
+}
+
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+// the chart is laid out once as shapes and then written out as svg or drawn to a png
+enum Shape {
+    Rect { x: i32, y: i32, width: i32, height: i32, color: (u8, u8, u8) },
+    Line { points: Vec<(i32, i32)>, color: (u8, u8, u8), width: u32 },
+    Label { x: i32, y: i32, text: String, color: (u8, u8, u8), anchor: Anchor },
+}
+
+// Running total of every player over the rounds. Rounds are shaded by their group (e.g. the
+// contract family in tarok) when the game provides one.
+pub fn render_chart(sheet: &ScoreSheet, groups: &[Option<String>], format: ChartFormat) -> Result<Vec<u8>, Error> {
+    if sheet.rounds == 0 {
+        return Err(Error::other("No rounds played yet".to_string()));
+    }
+    let shapes = layout(sheet, groups);
+    match format {
+        ChartFormat::Svg => Ok(to_svg(&shapes).into_bytes()),
+        ChartFormat::Png => to_png(&shapes),
+    }
+}
+
+fn layout(sheet: &ScoreSheet, groups: &[Option<String>]) -> Vec<Shape> {
+    let rounds = sheet.rounds as usize;
+    let running: Vec<Vec<i32>> = sheet.players
+        .iter()
+        .map(|p| {
+            let mut total = 0;
+            let mut line = vec![0];
+            for round in 0..rounds {
+                total += sheet.score.get(&p.id).and_then(|s| s.get(round).copied().flatten()).unwrap_or(0);
+                line.push(total);
+            }
+            // penalties outside the rounds (e.g. unused radlci) land on the last step, so the line ends at the total
+            if let Some(final_total) = sheet.totals.get(&p.id) {
+                if let Some(last) = line.last_mut() {
+                    *last += final_total - total;
+                }
+            }
+            line
+        })
+        .collect();
+    let lowest = running.iter().flatten().copied().min().unwrap_or(0);
+    let highest = running.iter().flatten().copied().max().unwrap_or(0);
+    let step = tick_step(highest - lowest);
+    let low = lowest.div_euclid(step) * step;
+    let high = (highest + step - 1).div_euclid(step) * step;
+    let high = if high == low { low + step } else { high };
+    let x = |round: usize| PLOT_LEFT + (round as i32 * (PLOT_RIGHT - PLOT_LEFT)) / rounds as i32;
+    let y = |value: i32| PLOT_BOTTOM - ((value - low) as i64 * (PLOT_BOTTOM - PLOT_TOP) as i64 / (high - low) as i64) as i32;
+
+    let mut shapes = vec![];
+    // round shading and its legend
+    let mut seen_groups: Vec<&String> = vec![];
+    for (round, group) in groups.iter().enumerate().take(rounds) {
+        if let Some(group) = group {
+            if !seen_groups.contains(&group) {
+                seen_groups.push(group);
+            }
+            let index = seen_groups.iter().position(|g| *g == group).unwrap_or(0);
+            shapes.push(Shape::Rect {
+                x: x(round),
+                y: PLOT_TOP,
+                width: x(round + 1) - x(round),
+                height: PLOT_BOTTOM - PLOT_TOP,
+                color: GROUP_COLORS[index % GROUP_COLORS.len()],
+            });
+        }
+    }
+    let mut legend_x = PLOT_LEFT;
+    for (index, group) in seen_groups.iter().enumerate() {
+        shapes.push(Shape::Rect { x: legend_x, y: HEIGHT - 20, width: 12, height: 12, color: GROUP_COLORS[index % GROUP_COLORS.len()] });
+        shapes.push(Shape::Label { x: legend_x + 16, y: HEIGHT - 14, text: group.to_string(), color: (60, 60, 60), anchor: Anchor::Start });
+        legend_x += 16 + group.chars().count() as i32 * 8 + 16;
+    }
+
+    // value grid
+    let mut tick = low;
+    while tick <= high {
+        let color = if tick == 0 { (120, 120, 120) } else { (215, 215, 215) };
+        shapes.push(Shape::Line { points: vec![(PLOT_LEFT, y(tick)), (PLOT_RIGHT, y(tick))], color, width: 1 });
+        shapes.push(Shape::Label { x: PLOT_LEFT - 6, y: y(tick), text: tick.to_string(), color: (60, 60, 60), anchor: Anchor::End });
+        tick += step;
+    }
+    // round numbers, thinned out so they don't overlap
+    let every = rounds.div_ceil(20);
+    for round in (0..=rounds).step_by(every) {
+        shapes.push(Shape::Label { x: x(round), y: PLOT_BOTTOM + 12, text: round.to_string(), color: (60, 60, 60), anchor: Anchor::Middle });
+    }
+    shapes.push(Shape::Line { points: vec![(PLOT_LEFT, PLOT_TOP), (PLOT_LEFT, PLOT_BOTTOM)], color: (120, 120, 120), width: 1 });
+
+    // players and their final totals
+    for (index, (player, line)) in sheet.players.iter().zip(running.iter()).enumerate() {
+        let color = PLAYER_COLORS[index % PLAYER_COLORS.len()];
+        shapes.push(Shape::Line {
+            points: line.iter().enumerate().map(|(round, value)| (x(round), y(*value))).collect(),
+            color,
+            width: 2,
+        });
+        let legend_y = PLOT_TOP + 8 + index as i32 * 18;
+        shapes.push(Shape::Rect { x: PLOT_RIGHT + 12, y: legend_y - 2, width: 14, height: 4, color });
+        shapes.push(Shape::Label {
+            x: PLOT_RIGHT + 32,
+            y: legend_y,
+            text: format!("{} {}", player.name.chars().take(MAX_NAME).collect::<String>(), line.last().unwrap_or(&0)),
+            color: (0, 0, 0),
+            anchor: Anchor::Start,
+        });
+    }
+    shapes
+}
+
+// 1, 2 or 5 times a power of ten, giving about five grid lines
+fn tick_step(range: i32) -> i32 {
+    let mut magnitude = 1;
+    loop {
+        for step in [1, 2, 5].iter().map(|s| s * magnitude) {
+            if range / step <= 5 {
+                return step;
+            }
+        }
+        magnitude *= 10;
+    }
+}
+
+fn to_svg(shapes: &[Shape]) -> String {
+    let mut svg = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="0 0 {} {}" font-family="monospace" font-size="13"><rect width="100%" height="100%" fill="white"/>"#,
+        WIDTH, HEIGHT, WIDTH, HEIGHT
+    );
+    for shape in shapes.iter() {
+        match shape {
+            Shape::Rect { x, y, width, height, color } => svg.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#, x, y, width, height, hex(*color)
+            )),
+            Shape::Line { points, color, width } => svg.push_str(&format!(
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linejoin="round"/>"#,
+                points.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<String>>().join(" "),
+                hex(*color),
+                width
+            )),
+            Shape::Label { x, y, text, color, anchor } => svg.push_str(&format!(
+                r#"<text x="{}" y="{}" fill="{}" text-anchor="{}" dominant-baseline="middle">{}</text>"#,
+                x,
+                y,
+                hex(*color),
+                match anchor {
+                    Anchor::Start => "start",
+                    Anchor::Middle => "middle",
+                    Anchor::End => "end",
+                },
+                escape_html(text)
+            )),
+        };
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn to_png(shapes: &[Shape]) -> Result<Vec<u8>, Error> {
+    let mut canvas = Canvas::new(WIDTH as u32, HEIGHT as u32);
+    for shape in shapes.iter() {
+        match shape {
+            Shape::Rect { x, y, width, height, color } => {
+                let _ = Rectangle::new(Point::new(*x, *y), Size::new(*width as u32, *height as u32))
+                    .into_styled(PrimitiveStyle::with_fill(rgb(*color)))
+                    .draw(&mut canvas);
+            },
+            Shape::Line { points, color, width } => {
+                let points: Vec<Point> = points.iter().map(|(x, y)| Point::new(*x, *y)).collect();
+                let _ = Polyline::new(&points)
+                    .into_styled(PrimitiveStyle::with_stroke(rgb(*color), *width))
+                    .draw(&mut canvas);
+            },
+            Shape::Label { x, y, text, color, anchor } => {
+                let alignment = match anchor {
+                    Anchor::Start => Alignment::Left,
+                    Anchor::Middle => Alignment::Center,
+                    Anchor::End => Alignment::Right,
+                };
+                draw_text(&mut canvas, text, Point::new(*x, *y), rgb(*color), false, alignment);
+            },
+        };
+    }
+    encode_png(&canvas)
+}
+
+fn rgb((r, g, b): (u8, u8, u8)) -> Rgb888 {
+    Rgb888::new(r, g, b)
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::core::{score_sheet::ScoreSheet, database::test_database::user};
+
+    use super::{render_chart, tick_step, ChartFormat};
+
+    const CHAT_ID: i64 = -1046;
+
+    fn svg(sheet: &ScoreSheet, groups: &[Option<String>]) -> String {
+        String::from_utf8(render_chart(sheet, groups, ChartFormat::Svg).expect("Error rendering chart")).expect("Invalid svg")
+    }
+
+    #[test]
+    fn tick_steps_give_about_five_lines() {
+        assert_eq!(tick_step(0), 1);
+        assert_eq!(tick_step(5), 1);
+        assert_eq!(tick_step(6), 2);
+        assert_eq!(tick_step(10), 2);
+        assert_eq!(tick_step(12), 5);
+        assert_eq!(tick_step(30), 10);
+        assert_eq!(tick_step(240), 50);
+        assert_eq!(tick_step(4000), 1000);
+    }
+
+    #[test]
+    fn svg_ends_lines_at_the_totals_and_escapes_names() {
+        let (ana, bor) = (user(CHAT_ID, "ANA"), user(CHAT_ID, "B<R>&"));
+        let sheet = ScoreSheet {
+            game_id: "chart".to_string(),
+            game_type: "tarok".to_string(),
+            players: vec![ana.clone(), bor.clone()],
+            score: HashMap::from([
+                (ana.id.clone(), vec![Some(20), Some(10)]),
+                (bor.id.clone(), vec![Some(-20), None]),
+            ]),
+            rounds: 2,
+            // bor ends with an unused radlc
+            totals: HashMap::from([(ana.id.clone(), 30), (bor.id.clone(), -70)]),
+        };
+        let chart = svg(&sheet, &[Some("I".to_string()), Some("S".to_string())]);
+        assert!(chart.starts_with("<svg") && chart.ends_with("</svg>"));
+        assert!(chart.contains(">ANA 30</text>"), "{}", chart);
+        assert!(chart.contains(">B&lt;R&gt;&amp; -70</text>"), "{}", chart);
+        assert!(!chart.contains("B<R>"));
+        // the lowest grid line covers the final total, not only the round scores
+        assert!(chart.contains(">-80</text>"), "{}", chart);
+        assert!(chart.contains(">I</text>") && chart.contains(">S</text>"));
+    }
+
+    #[test]
+    fn chart_needs_a_round() {
+        let sheet = ScoreSheet {
+            game_id: "chart".to_string(),
+            game_type: "table".to_string(),
+            players: vec![user(CHAT_ID, "ANA")],
+            score: HashMap::new(),
+            rounds: 0,
+            totals: HashMap::new(),
+        };
+        assert!(render_chart(&sheet, &[], ChartFormat::Svg).is_err());
+    }
+}
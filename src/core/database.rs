@@ -14,6 +14,13 @@ pub mod user_operations {
         Ok(resp.pop())
     }
 
+    pub fn get_users_by_chat(user_chat_id: String) -> Result<Vec<User>, Error> {
+        let conn = establish_connection();
+        users
+            .filter(chat_id.eq(user_chat_id))
+            .load::<User>(&conn)
+    }
+
     pub fn insert_user(user: User) ->  Result<User, Error> {
         let conn = establish_connection();
         let _ = insert_into(users)
@@ -24,11 +31,230 @@ pub mod user_operations {
 }
 
 pub mod chat_operations {
+    use diesel::{prelude::*, insert_into, update};
+    use diesel::result::Error;
+    use crate::models::chat::Chat;
+    use crate::models::schema::chats::dsl::*;
+
+    use super::sqlite_operations::establish_connection;
+
+    pub fn get_chat(chat_telegram_id: String) -> Result<Option<Chat>, Error> {
+        let conn = establish_connection();
+        let mut resp = chats
+            .filter(telegram_id.eq(chat_telegram_id))
+            .load::<Chat>(&conn)?;
+        Ok(resp.pop())
+    }
 
+    // chats has no unique key, so an existing row is updated instead of replaced
+    pub fn save_chat(chat: Chat) -> Result<Chat, Error> {
+        let conn = establish_connection();
+        let updated = update(chats.filter(telegram_id.eq(chat.telegram_id.clone())))
+            .set((
+                default_game.eq(chat.default_game.clone()), 
+                score_format.eq(chat.score_format.clone()), 
+                theme.eq(chat.theme.clone()),
+            ))
+            .execute(&conn)?;
+        if updated == 0 {
+            insert_into(chats)
+                .values(&chat)
+                .execute(&conn)?;
+        }
+        Ok(chat)
+    }
 }
 
 pub mod round_operations {
+    use diesel::{prelude::*, insert_into};
+    use diesel::result::Error;
+    use crate::models::round::Round;
+    use crate::models::schema::rounds::dsl::*;
+
+    use super::sqlite_operations::establish_connection;
+
+    pub fn insert_rounds(new_rounds: Vec<Round>) -> Result<usize, Error> {
+        let conn = establish_connection();
+        insert_into(rounds)
+            .values(&new_rounds)
+            .execute(&conn)
+    }
+
+    pub fn get_rounds_by_chat(round_chat_id: String) -> Result<Vec<Round>, Error> {
+        let conn = establish_connection();
+        rounds
+            .filter(chat_id.eq(round_chat_id))
+            .load::<Round>(&conn)
+    }
+}
+
+pub mod game_operations {
+    use diesel::{prelude::*, insert_into};
+    use diesel::result::Error;
+    use crate::models::game::{ArchivedGame, GameResult};
+    use crate::models::schema::games::dsl::*;
+    use crate::models::schema::game_results::dsl::{game_results, game_id};
+
+    use super::sqlite_operations::establish_connection;
+
+    pub fn insert_game(game: ArchivedGame, results: Vec<GameResult>) -> Result<ArchivedGame, Error> {
+        let conn = establish_connection();
+        conn.transaction::<_, Error, _>(|| {
+            insert_into(games)
+                .values(&game)
+                .execute(&conn)?;
+            insert_into(game_results)
+                .values(&results)
+                .execute(&conn)?;
+            Ok(())
+        })?;
+        Ok(game)
+    }
+
+    pub fn get_games_by_chat(game_chat_id: String) -> Result<Vec<ArchivedGame>, Error> {
+        let conn = establish_connection();
+        games
+            .filter(chat_id.eq(game_chat_id))
+            .order((finished_at.asc(), id.asc()))
+            .load::<ArchivedGame>(&conn)
+    }
+
+    pub fn get_games_by_season(game_season_id: String) -> Result<Vec<ArchivedGame>, Error> {
+        let conn = establish_connection();
+        games
+            .filter(season_id.eq(game_season_id))
+            .order((finished_at.asc(), id.asc()))
+            .load::<ArchivedGame>(&conn)
+    }
+
+    pub fn get_results_by_games(game_ids: Vec<String>) -> Result<Vec<GameResult>, Error> {
+        let conn = establish_connection();
+        game_results
+            .filter(game_id.eq_any(game_ids))
+            .load::<GameResult>(&conn)
+    }
+}
+
+pub mod settlement_operations {
+    use diesel::{prelude::*, insert_into, replace_into};
+    use diesel::result::Error;
+    use crate::models::settlement::{SettlementConfig, LedgerEntry};
+    use crate::models::schema::settlements::dsl::{settlements, chat_id as settlement_chat_id};
+    use crate::models::schema::ledger_entries::dsl::{ledger_entries, chat_id as ledger_chat_id};
+
+    use super::sqlite_operations::establish_connection;
+
+    pub fn get_settlement_config(config_chat_id: String) -> Result<Option<SettlementConfig>, Error> {
+        let conn = establish_connection();
+        let mut resp = settlements
+            .filter(settlement_chat_id.eq(config_chat_id))
+            .load::<SettlementConfig>(&conn)?;
+        Ok(resp.pop())
+    }
+
+    pub fn save_settlement_config(config: SettlementConfig) -> Result<SettlementConfig, Error> {
+        let conn = establish_connection();
+        let _ = replace_into(settlements)
+            .values(&config)
+            .execute(&conn)?;
+        Ok(config)
+    }
+
+    pub fn insert_ledger_entries(entries: Vec<LedgerEntry>) -> Result<usize, Error> {
+        let conn = establish_connection();
+        insert_into(ledger_entries)
+            .values(&entries)
+            .execute(&conn)
+    }
+
+    pub fn get_ledger_by_chat(entry_chat_id: String) -> Result<Vec<LedgerEntry>, Error> {
+        let conn = establish_connection();
+        ledger_entries
+            .filter(ledger_chat_id.eq(entry_chat_id))
+            .load::<LedgerEntry>(&conn)
+    }
+}
+
+pub mod season_operations {
+    use diesel::{prelude::*, insert_into, update};
+    use diesel::result::Error;
+    use crate::models::season::Season;
+    use crate::models::schema::seasons::dsl::*;
+
+    use super::sqlite_operations::establish_connection;
+
+    pub fn insert_season(season: Season) -> Result<Season, Error> {
+        let conn = establish_connection();
+        let _ = insert_into(seasons)
+            .values(&season)
+            .execute(&conn)?;
+        Ok(season)
+    }
+
+    pub fn get_active_season(season_chat_id: String) -> Result<Option<Season>, Error> {
+        let conn = establish_connection();
+        let mut resp = seasons
+            .filter(chat_id.eq(season_chat_id))
+            .filter(ended_at.is_null())
+            .load::<Season>(&conn)?;
+        Ok(resp.pop())
+    }
+
+    pub fn end_season(season_id: String, end: String) -> Result<usize, Error> {
+        let conn = establish_connection();
+        update(seasons.filter(id.eq(season_id)))
+            .set(ended_at.eq(Some(end)))
+            .execute(&conn)
+    }
+}
+
+pub mod achievement_operations {
+    use diesel::{prelude::*, insert_into};
+    use diesel::result::Error;
+    use crate::models::achievement::PlayerAchievement;
+    use crate::models::schema::achievements::dsl::*;
+
+    use super::sqlite_operations::establish_connection;
+
+    pub fn insert_achievements(new_achievements: Vec<PlayerAchievement>) -> Result<usize, Error> {
+        let conn = establish_connection();
+        insert_into(achievements)
+            .values(&new_achievements)
+            .execute(&conn)
+    }
+
+    pub fn get_achievements_by_player(achievement_player_id: String) -> Result<Vec<PlayerAchievement>, Error> {
+        let conn = establish_connection();
+        achievements
+            .filter(player_id.eq(achievement_player_id))
+            .order(created_at.asc())
+            .load::<PlayerAchievement>(&conn)
+    }
+}
+
+pub mod rules_operations {
+    use diesel::{prelude::*, replace_into};
+    use diesel::result::Error;
+    use crate::models::rules::ChatRules;
+    use crate::models::schema::chat_rules::dsl::*;
 
+    use super::sqlite_operations::establish_connection;
+
+    pub fn get_chat_rules(rules_chat_id: String) -> Result<Option<ChatRules>, Error> {
+        let conn = establish_connection();
+        let mut resp = chat_rules
+            .filter(chat_id.eq(rules_chat_id))
+            .load::<ChatRules>(&conn)?;
+        Ok(resp.pop())
+    }
+
+    pub fn save_chat_rules(rules: ChatRules) -> Result<ChatRules, Error> {
+        let conn = establish_connection();
+        let _ = replace_into(chat_rules)
+            .values(&rules)
+            .execute(&conn)?;
+        Ok(rules)
+    }
 }
 
 pub mod sqlite_operations {
@@ -39,4 +265,59 @@ pub mod sqlite_operations {
             &env::var("DATABASE_URL").expect("No DATABASE_URL in .env")
         ).expect("Error connecting to database!")
     }
+}
+
+#[cfg(test)]
+pub mod test_database {
+    use std::{env, fs, sync::Once};
+    use diesel::{Connection, SqliteConnection, connection::SimpleConnection};
+    use teloxide::types::Message;
+    use uuid::Uuid;
+
+    use crate::models::user::{NewUser, User};
+    use super::user_operations::insert_user;
+
+    static DATABASE: Once = Once::new();
+
+    // fresh sqlite file with all migrations applied, shared by every test of the run
+    pub fn setup_database() {
+        DATABASE.call_once(|| {
+            let path = env::temp_dir().join(format!("score_keeper_{}.sqlite", Uuid::new_v4()));
+            let url = path.to_string_lossy().to_string();
+            let conn = SqliteConnection::establish(&url).expect("Error creating test database");
+            let mut migrations: Vec<_> = fs::read_dir("migrations")
+                .expect("Error reading migrations")
+                .map(|entry| entry.expect("Error reading migration").path())
+                .collect();
+            migrations.sort();
+            for migration in migrations.iter() {
+                let sql = fs::read_to_string(migration.join("up.sql")).expect("Error reading migration");
+                conn.batch_execute(&sql).expect("Error running migration");
+            }
+            env::set_var("DATABASE_URL", url);
+        });
+    }
+
+    // a player of the chat that is not stored
+    pub fn user(chat_id: i64, name: &str) -> User {
+        let mut new_user = NewUser::from(name.to_string(), chat_id.to_string());
+        new_user.validate();
+        User::from(new_user).expect("Error building user")
+    }
+
+    // a player of the chat, stored so the commands can find it by name
+    pub fn register(chat_id: i64, name: &str) -> User {
+        insert_user(user(chat_id, name)).expect("Error registering user")
+    }
+
+    // a group message as the bot receives it
+    pub fn message(chat_id: i64, text: &str) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "message_id": 1,
+            "date": 0,
+            "chat": { "id": chat_id, "type": "group", "title": "Score keeper" },
+            "from": { "id": 1, "is_bot": false, "first_name": "Tester" },
+            "text": text,
+        })).expect("Error building message")
+    }
 }
\ No newline at end of file
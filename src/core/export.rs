@@ -0,0 +1,203 @@
+use std::{collections::HashMap, io::Error};
+
+use serde::{Serialize, Deserialize};
+
+use crate::{models::user::User, games::tarok::rules::TarokRules};
+
+use super::{score_sheet::ScoreSheet, spreadsheet::{build_workbook, to_xlsx, to_ods}, pdf::to_pdf, database::game_operations::get_games_by_chat};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExportPlayer {
+    pub id: String,
+    pub name: String,
+}
+
+// Full state of a game. Attributes are kept in their /round tag form (e.g. I3, MC:ANA)
+// so the export reads the same as the rounds that were entered.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct GameExport {
+    pub game_id: String,
+    pub game_type: String,
+    pub players: Vec<ExportPlayer>,
+    pub rounds: i32,
+    pub score: HashMap<String, Vec<Option<i32>>>,
+    pub totals: HashMap<String, i32>,
+    pub game_attributes: Vec<Vec<String>>,
+    pub player_attributes: HashMap<String, Vec<Option<Vec<String>>>>,
+    pub radlci: HashMap<String, Vec<String>>,
+    pub dealers: Vec<Option<String>>,
+    #[serde(default)]
+    pub details: Vec<RoundDetail>,  // how the rounds were scored, only for rounds played with the bot
+    #[serde(default)]
+    pub seating: Option<Vec<String>>,   // player ids in seating order, only for seated games
+    #[serde(default)]
+    pub dealer: Option<usize>,          // index into seating of who deals the next round
+    #[serde(default)]
+    pub rules: Option<TarokRules>,      // rules a tarok game was scored with, missing in older exports
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RoundDetail {
+    pub round: i32,
+    pub contract: String,
+    pub base: i32,
+    pub diff: i32,
+    pub bonuses: Vec<(String, i32)>,
+    pub game_points: i32,
+}
+
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Xlsx,
+    Ods,
+    Pdf,
+}
+
+impl GameExport {
+    // export of a game without any attributes, only the scores
+    pub fn from_sheet(sheet: ScoreSheet) -> Self {
+        Self {
+            game_id: sheet.game_id,
+            game_type: sheet.game_type,
+            players: sheet.players.iter().map(ExportPlayer::from).collect(),
+            rounds: sheet.rounds,
+            score: sheet.score,
+            totals: sheet.totals,
+            game_attributes: vec![],
+            player_attributes: HashMap::new(),
+            radlci: HashMap::new(),
+            dealers: vec![],
+            details: vec![],
+            seating: None,
+            dealer: None,
+            rules: None,
+        }
+    }
+
+    pub fn file_name(&self, format: &ExportFormat) -> String {
+        let extension = match format {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Ods => "ods",
+            ExportFormat::Pdf => "pdf",
+        };
+        format!("{}_{}.{}", self.game_type, self.game_id.split('-').next().unwrap_or(""), extension)
+    }
+
+    pub fn to_json(&self) -> Result<String, Error> {
+        match serde_json::to_string_pretty(self) {
+            Ok(json) => Ok(json),
+            Err(e) => Err(Error::other(format!("Error serializing game: {}", e))),
+        }
+    }
+
+    // one row per round: the round's contract and dealer, then score and attributes of every player
+    pub fn to_csv(&self) -> String {
+        let mut header = vec!["round".to_string(), "contract".to_string(), "dealer".to_string()];
+        for player in self.players.iter() {
+            header.push(player.name.clone());
+            header.push(format!("{} attributes", player.name));
+        }
+        let mut lines = vec![csv_line(&header)];
+        for round in 0..self.rounds as usize {
+            let contract = match self.game_attributes.get(round) {
+                Some(tags) => tags.join(" "),
+                None => "".to_string(),
+            };
+            let dealer = match self.dealers.get(round) {
+                Some(Some(dealer)) => self.player_name(dealer),
+                _ => "".to_string(),
+            };
+            let mut fields = vec![(round + 1).to_string(), contract, dealer];
+            for player in self.players.iter() {
+                fields.push(match self.score.get(&player.id).and_then(|s| s.get(round)) {
+                    Some(Some(score)) => score.to_string(),
+                    _ => "".to_string(),
+                });
+                fields.push(match self.player_attributes.get(&player.id).and_then(|a| a.get(round)) {
+                    Some(Some(tags)) => tags.join(" "),
+                    _ => "".to_string(),
+                });
+            }
+            lines.push(csv_line(&fields));
+        }
+        lines.join("\n")
+    }
+
+    fn player_name(&self, id: &String) -> String {
+        match self.players.iter().find(|p| &p.id == id) {
+            Some(player) => player.name.clone(),
+            None => id.clone(),
+        }
+    }
+}
+
+impl ExportPlayer {
+    pub fn from(user: &User) -> Self {
+        Self {
+            id: user.id.clone(),
+            name: user.name.clone(),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub fn from_arg(arg: &str) -> Option<Self> {
+        match arg {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            "xlsx" => Some(ExportFormat::Xlsx),
+            "ods" => Some(ExportFormat::Ods),
+            "pdf" => Some(ExportFormat::Pdf),
+            _ => None,
+        }
+    }
+
+    pub fn render(&self, export: &GameExport) -> Result<Vec<u8>, Error> {
+        match self {
+            ExportFormat::Csv => Ok(export.to_csv().into_bytes()),
+            ExportFormat::Json => export.to_json().map(|json| json.into_bytes()),
+            ExportFormat::Xlsx => to_xlsx(&build_workbook(export)),
+            ExportFormat::Ods => to_ods(&build_workbook(export)),
+            ExportFormat::Pdf => Ok(to_pdf(export)),
+        }
+    }
+}
+
+// Archived game of the chat by id (the first block of the id is enough), or the last one.
+pub fn archived_export(chat_id: String, game_id: Option<&str>) -> Result<GameExport, Error> {
+    let games = match get_games_by_chat(chat_id) {
+        Ok(games) => games,
+        Err(e) => return Err(Error::other(format!("Error fetching games from DB: {}", e))),
+    };
+    let game = match game_id {
+        Some(game_id) => games.into_iter().find(|g| g.id.starts_with(&game_id.to_lowercase())),
+        None => games.into_iter().last(),
+    };
+    let game = match game {
+        Some(game) => game,
+        None => return Err(Error::other("No such game archived in this chat".to_string())),
+    };
+    let data = match game.data {
+        Some(data) => data,
+        None => return Err(Error::other(format!("Game {} was archived before exports were kept", game.id))),
+    };
+    match serde_json::from_str(&data) {
+        Ok(export) => Ok(export),
+        Err(e) => Err(Error::other(format!("Error reading archived game {}: {}", game.id, e))),
+    }
+}
+
+fn csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|f| if f.contains([',', '"', '\n']) {
+            format!("\"{}\"", f.replace('"', "\"\""))
+        } else {
+            f.clone()
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use once_cell::sync::Lazy;
 use tokio::sync::Mutex;
 
-use super::traits::Game;
+use super::{traits::Game, tournament::Tournament};
 
-pub static RUNNING_GAMES: Lazy<Mutex<HashMap<String, Box<dyn Game + Send >>>> = Lazy::new(|| {Mutex::new(HashMap::new())});
\ No newline at end of file
+pub static RUNNING_GAMES: Lazy<Mutex<HashMap<String, Box<dyn Game + Send >>>> = Lazy::new(|| {Mutex::new(HashMap::new())});
+pub static TOURNAMENTS: Lazy<Mutex<HashMap<String, Tournament>>> = Lazy::new(|| {Mutex::new(HashMap::new())});
\ No newline at end of file
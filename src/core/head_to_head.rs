@@ -0,0 +1,113 @@
+use std::{collections::HashMap, io::Error};
+
+use crate::{games::tarok::stats::partnership_summary, models::game::GameResult};
+
+use super::database::{game_operations::{get_games_by_chat, get_results_by_games}, user_operations::get_user_by_name};
+
+// /h2h A B
+pub fn head_to_head(chat_id: String, text: &str) -> Result<String, Error> {
+    let names: Vec<&str> = text.split_whitespace().skip(1).collect();
+    let (name_a, name_b) = match names.as_slice() {
+        [a, b] => (a, b),
+        _ => return Err(Error::other("Use /h2h <player> <player>".to_string())),
+    };
+    let a = match get_user_by_name(name_a.to_uppercase()) {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(Error::other(format!("Unknown player {}", name_a))),
+        Err(e) => return Err(Error::other(format!("Error fetching user from DB: {}", e))),
+    };
+    let b = match get_user_by_name(name_b.to_uppercase()) {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(Error::other(format!("Unknown player {}", name_b))),
+        Err(e) => return Err(Error::other(format!("Error fetching user from DB: {}", e))),
+    };
+    let games = match get_games_by_chat(chat_id.clone()) {
+        Ok(games) => games,
+        Err(e) => return Err(Error::other(format!("Error fetching games from DB: {}", e))),
+    };
+    let results = match get_results_by_games(games.iter().map(|g| g.id.clone()).collect()) {
+        Ok(results) => results,
+        Err(e) => return Err(Error::other(format!("Error fetching game results from DB: {}", e))),
+    };
+    let mut by_game: HashMap<&String, (Option<&GameResult>, Option<&GameResult>)> = HashMap::new();
+    for result in results.iter() {
+        let entry = by_game.entry(&result.game_id).or_insert((None, None));
+        if result.player_id == a.id { entry.0 = Some(result); }
+        if result.player_id == b.id { entry.1 = Some(result); }
+    }
+
+    let (mut together, mut points_a, mut points_b) = (0, 0, 0);
+    let (mut higher_a, mut higher_b, mut ties) = (0, 0, 0);
+    for (result_a, result_b) in by_game.values().filter_map(|(ra, rb)| ra.zip(*rb)) {
+        together += 1;
+        points_a += result_a.total;
+        points_b += result_b.total;
+        match result_a.placement.cmp(&result_b.placement) {
+            std::cmp::Ordering::Less => higher_a += 1,
+            std::cmp::Ordering::Greater => higher_b += 1,
+            std::cmp::Ordering::Equal => ties += 1,
+        };
+    }
+
+    let mut lines = vec![format!("{} vs {}: {} finished games together", a.name, b.name, together)];
+    if together > 0 {
+        lines.push(format!(
+            "Points: {} {}, {} {} (difference {:+})",
+            a.name, points_a, b.name, points_b, points_a - points_b
+        ));
+        lines.push(format!(
+            "Finished higher: {} {}, {} {}, ties {}",
+            a.name, higher_a, b.name, higher_b, ties
+        ));
+    }
+    if let Some(tarok) = partnership_summary(chat_id, &a, &b)? {
+        lines.push(tarok);
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::database::{game_operations::insert_game, test_database::{setup_database, register}},
+        models::{game::{ArchivedGame, GameResult}, user::User},
+    };
+
+    use super::head_to_head;
+
+    const CHAT_ID: i64 = -10391;
+
+    fn archive(id: &str, results: &[(&User, i32, i32)]) {
+        let game = ArchivedGame::from(id.to_string(), CHAT_ID.to_string(), "table".to_string(), "2026-02-01T00:00:00+00:00".to_string(), None, None);
+        let results = results
+            .iter()
+            .map(|(player, total, placement)| GameResult::from(id.to_string(), player.id.clone(), *total, *placement))
+            .collect();
+        insert_game(game, results).expect("Error archiving game");
+    }
+
+    #[test]
+    fn head_to_head_compares_shared_games() {
+        setup_database();
+        let (ana, bor, cene) = (register(CHAT_ID, "HTHANA"), register(CHAT_ID, "HTHBOR"), register(CHAT_ID, "HTHCENE"));
+        register(CHAT_ID, "HTHDAN");
+        archive("h2h-1", &[(&ana, 30, 1), (&bor, 10, 2), (&cene, -40, 3)]);
+        archive("h2h-2", &[(&ana, -5, 3), (&bor, 20, 1), (&cene, 0, 2)]);
+        archive("h2h-3", &[(&ana, 15, 1), (&bor, 15, 1)]);
+        // only one of the pair played
+        archive("h2h-4", &[(&ana, 100, 1), (&cene, 0, 2)]);
+
+        let report = head_to_head(CHAT_ID.to_string(), "/h2h hthana HTHBOR").expect("Error comparing players");
+        assert_eq!(report, [
+            "HTHANA vs HTHBOR: 3 finished games together",
+            "Points: HTHANA 40, HTHBOR 45 (difference -5)",
+            "Finished higher: HTHANA 1, HTHBOR 1, ties 1",
+        ].join("\n"));
+
+        let report = head_to_head(CHAT_ID.to_string(), "/h2h HTHANA HTHDAN").expect("Error comparing players");
+        assert_eq!(report, "HTHANA vs HTHDAN: 0 finished games together");
+
+        assert!(head_to_head(CHAT_ID.to_string(), "/h2h HTHANA").is_err());
+        assert!(head_to_head(CHAT_ID.to_string(), "/h2h HTHANA NOBODY").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::Utc;
+
+use crate::models::user::User;
+
+use super::database::chat_operations::get_chat;
+
+const SHEET_TEMPLATE: &str = include_str!("../../templates/score_sheet.html");
+const SHEET_STYLE: &str = include_str!("../../templates/sheet.css");
+const LIGHT_THEME: &str = include_str!("../../templates/themes/light.css");
+const DARK_THEME: &str = include_str!("../../templates/themes/dark.css");
+const PRINT_THEME: &str = include_str!("../../templates/themes/print.css");
+
+pub const THEMES: [&str; 3] = ["light", "dark", "print"];
+
+pub enum Theme {
+    Light,
+    Dark,
+    Print,
+}
+
+pub enum Icon {
+    Dealer,
+    MondLost,
+    MondCaptured,
+    Renonce,
+    RenonceBy,
+    T,
+    Declarer,
+    Supporting,
+}
+
+impl Theme {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "light" => Some(Theme::Light),
+            "dark" => Some(Theme::Dark),
+            "print" => Some(Theme::Print),
+            _ => None,
+        }
+    }
+
+    fn style(&self) -> &'static str {
+        match self {
+            Theme::Light => LIGHT_THEME,
+            Theme::Dark => DARK_THEME,
+            Theme::Print => PRINT_THEME,
+        }
+    }
+}
+
+// theme the chat picked with /score theme, light when it never did
+pub fn chat_theme(chat_id: String) -> Theme {
+    match get_chat(chat_id) {
+        Ok(Some(chat)) => Theme::from_name(&chat.theme).unwrap_or(Theme::Light),
+        Ok(None) => Theme::Light,
+        Err(e) => {
+            log::error!("Failed to fetch chat theme: {}", e);
+            Theme::Light
+        },
+    }
+}
+
+// Full score sheet page: header with the game, date and players, the table rows and whatever
+// the game adds below the table.
+pub fn render_sheet(game: &str, players: &[User], table: &str, legend: &str, theme: &Theme) -> String {
+    let date = Utc::now().format("%Y-%m-%d %H:%M").to_string();
+    fill_template(SHEET_TEMPLATE, &[
+        ("title", escape_html(&format!("{} {}", game, date))),
+        ("style", format!("{}\n{}", SHEET_STYLE, theme.style())),
+        ("game", escape_html(game)),
+        ("date", date),
+        ("players", escape_html(&players.iter().map(|p| p.name.clone()).collect::<Vec<String>>().join(", "))),
+        ("table", table.to_string()),
+        ("legend", legend.to_string()),
+    ])
+}
+
+// inline svg, so the sheet needs nothing from the network
+pub fn icon(icon: Icon, title: &str) -> String {
+    let shape = match icon {
+        Icon::Dealer => "<rect x='2' y='4' width='8' height='10' rx='1'/><path d='M6 4V2h8v10h-4'/>",
+        Icon::MondLost => "<circle cx='8' cy='8' r='5'/><path d='M8 1v4M8 11v4M1 8h4M11 8h4'/>",
+        Icon::MondCaptured => "<circle cx='8' cy='8' r='6'/><circle cx='8' cy='8' r='2.5'/>",
+        Icon::Renonce => "<path d='M8 2l7 12H1z'/><path d='M8 6v4M8 12v0.5'/>",
+        Icon::RenonceBy => "<circle cx='8' cy='8' r='6'/><path d='M8 4.5v4.5M8 11.5v0.5'/>",
+        Icon::T => "<path d='M3 3h10M8 3v10'/>",
+        Icon::Declarer => "<path d='M8 1.5l2 4.3 4.6.5-3.4 3.1 1 4.6L8 11.6 3.8 14l1-4.6-3.4-3.1 4.6-.5z'/>",
+        Icon::Supporting => "<circle cx='8' cy='8' r='6'/><path d='M8 5v6M5 8h6'/>",
+    };
+    format!("<svg class='icon' viewBox='0 0 16 16' role='img'><title>{}</title>{}</svg>", escape_html(title), shape)
+}
+
+// Anything a user typed (player and season names, tags) goes through this before it ends up
+// in html, svg or xml.
+pub fn escape_html(text: &str) -> String {
+    text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// replaces {{key}} in one pass, so values can't introduce placeholders of their own
+fn fill_template(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let key = &after[..end];
+                match values.iter().find(|(k, _)| *k == key) {
+                    Some((_, value)) => out.push_str(value),
+                    None => out.push_str(&rest[start..start + end + 4]),
+                }
+                rest = &after[end + 2..];
+            },
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            },
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_html, icon, Icon};
+
+    #[test]
+    fn markup_is_escaped() {
+        assert_eq!(
+            escape_html("<b onclick=\"x('y')\">A&B</b>"),
+            "&lt;b onclick=&quot;x(&#39;y&#39;)&quot;&gt;A&amp;B&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn icon_titles_are_escaped() {
+        let svg = icon(Icon::MondLost, "Mond lost to </title><script>");
+        assert!(svg.contains("<title>Mond lost to &lt;/title&gt;&lt;script&gt;</title>"));
+    }
+}
\ No newline at end of file
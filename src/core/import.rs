@@ -0,0 +1,281 @@
+use std::{collections::HashMap, io::Error};
+
+use crate::{games::{tarok::game::Tarok, table::game::Table}, models::user::{User, NewUser}};
+
+use super::{
+    traits::Game,
+    export::{GameExport, ExportPlayer},
+    game_aggregator::GameAggregator,
+    database::user_operations::{get_users_by_chat, insert_user},
+};
+
+// Reads a game in the /export format. Json is recognised by its content, anything else is read as csv.
+pub fn parse_import(contents: &str) -> Result<GameExport, Error> {
+    if contents.trim_start().starts_with('{') {
+        return match serde_json::from_str(contents) {
+            Ok(export) => Ok(export),
+            Err(e) => Err(Error::other(format!("Error reading json: {}", e))),
+        };
+    }
+    parse_csv(contents)
+}
+
+// Matches the players of the import to the users registered in the chat and rewrites the
+// import to their ids. Missing players are prepared only when create is set, they are not
+// registered until the game is built (see register_players).
+// Returns the players and the ones still to register.
+pub fn resolve_players(chat_id: String, export: &mut GameExport, create: bool) -> Result<(Vec<User>, Vec<User>), Error> {
+    let mut names: Vec<String> = export.players.iter().map(|p| p.name.to_uppercase()).collect();
+    names.sort();
+    if let Some(pair) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(Error::other(format!("Player {} is listed more than once", pair[0])));
+    }
+    let users = match get_users_by_chat(chat_id.clone()) {
+        Ok(users) => users,
+        Err(e) => return Err(Error::other(format!("Error fetching users from DB: {}", e))),
+    };
+    let missing: Vec<String> = export.players
+        .iter()
+        .map(|p| p.name.to_uppercase())
+        .filter(|name| !users.iter().any(|u| &u.name == name))
+        .collect();
+    if !missing.is_empty() && !create {
+        return Err(Error::other(format!(
+            "Players not registered in this chat: {}\nReply again with /import archive|play create to register them",
+            missing.join(", "),
+        )));
+    }
+    let mut created = vec![];
+    let mut players = vec![];
+    for player in export.players.iter() {
+        let name = player.name.to_uppercase();
+        let user = match users.iter().find(|u| u.name == name) {
+            Some(user) => user.clone(),
+            None => {
+                let user = new_user(&name, &chat_id)?;
+                created.push(user.clone());
+                user
+            },
+        };
+        players.push(user);
+    }
+    remap_players(export, &players);
+    Ok((players, created))
+}
+
+// Registers the players prepared by resolve_players, returns their names.
+pub fn register_players(users: Vec<User>) -> Result<Vec<String>, Error> {
+    let mut registered = vec![];
+    for user in users.into_iter() {
+        let name = user.name.clone();
+        match insert_user(user) {
+            Ok(user) => registered.push(user.name),
+            Err(e) => return Err(Error::other(format!("Error registering {}: {}", name, e))),
+        };
+    }
+    Ok(registered)
+}
+
+pub fn build_game(chat_id: String, export: &GameExport, players: Vec<User>) -> Result<Box<dyn Game + Send>, Error> {
+    match export.game_type.as_str() {
+        "tarok" => Ok(Box::new(Tarok::import(chat_id, export, players)?)),
+        "table" => Ok(Box::new(Table::import(export, players))),
+        other => Err(Error::other(format!("Unknown game type: {}", other))),
+    }
+}
+
+// Columns as written by /export csv: round, contract, dealer, then score and attributes of each player.
+// Rounds with a contract are read as tarok, otherwise as a generic table.
+fn parse_csv(contents: &str) -> Result<GameExport, Error> {
+    let mut lines = contents.lines().filter(|l| !l.trim().is_empty());
+    let header = match lines.next() {
+        Some(header) => csv_fields(header),
+        None => return Err(Error::other("The file is empty".to_string())),
+    };
+    if header.len() < 5 || header.len() % 2 == 0 || header[0] != "round" {
+        return Err(Error::other("Expected columns: round,contract,dealer,<player>,<player> attributes,...".to_string()));
+    }
+    // players are identified by their name until they are resolved
+    let players: Vec<ExportPlayer> = header[3..]
+        .iter()
+        .step_by(2)
+        .map(|name| ExportPlayer { id: name.to_uppercase(), name: name.to_uppercase() })
+        .collect();
+    let mut export = GameExport {
+        game_id: "".to_string(),
+        game_type: "table".to_string(),
+        players: vec![],
+        rounds: 0,
+        score: HashMap::new(),
+        totals: HashMap::new(),
+        game_attributes: vec![],
+        player_attributes: HashMap::new(),
+        radlci: HashMap::new(),
+        dealers: vec![],
+        details: vec![],
+        seating: None,
+        dealer: None,
+        rules: None,
+    };
+    for (index, line) in lines.enumerate() {
+        let fields = csv_fields(line);
+        if fields.len() > header.len() {
+            return Err(Error::other(format!("Too many columns in round {}", index + 1)));
+        }
+        let field = |column: usize| fields.get(column).map(|f| f.trim()).unwrap_or("");
+        if !field(1).is_empty() {
+            export.game_type = "tarok".to_string();
+        }
+        export.game_attributes.push(field(1).split_whitespace().map(|t| t.to_string()).collect());
+        export.dealers.push(match field(2) {
+            "" => None,
+            dealer => Some(dealer.to_uppercase()),
+        });
+        for (column, player) in (3..header.len()).step_by(2).zip(players.iter()) {
+            let score = match field(column) {
+                "" => None,
+                score => match score.parse() {
+                    Ok(score) => Some(score),
+                    Err(_) => return Err(Error::other(format!("Error parsing score {} of {} in round {}", score, player.name, index + 1))),
+                },
+            };
+            export.score.entry(player.id.clone()).or_default().push(score);
+            export.player_attributes.entry(player.id.clone()).or_default().push(match field(column + 1) {
+                "" => None,
+                tags => Some(tags.split_whitespace().map(|t| t.to_string()).collect()),
+            });
+        }
+        export.rounds += 1;
+    }
+    export.players = players;
+    Ok(export)
+}
+
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+// replaces the imported player ids with the ids of the registered users
+fn remap_players(export: &mut GameExport, players: &[User]) {
+    let ids: HashMap<String, User> = export.players
+        .iter()
+        .zip(players.iter())
+        .map(|(p, u)| (p.id.clone(), u.clone()))
+        .collect();
+    let new_id = |id: &String| match ids.get(id) {
+        Some(user) => user.id.clone(),
+        None => id.clone(),
+    };
+    export.score = export.score.drain().map(|(id, v)| (new_id(&id), v)).collect();
+    export.totals = export.totals.drain().map(|(id, v)| (new_id(&id), v)).collect();
+    export.radlci = export.radlci.drain().map(|(id, v)| (new_id(&id), v)).collect();
+    export.player_attributes = export.player_attributes.drain().map(|(id, v)| (new_id(&id), v)).collect();
+    export.dealers = export.dealers.iter().map(|d| d.as_ref().map(new_id)).collect();
+    export.seating = export.seating.as_ref().map(|seating| seating.iter().map(new_id).collect());
+    // exports of older games can reference players by id in their tags (M:<id>)
+    for rounds in export.player_attributes.values_mut() {
+        for tag in rounds.iter_mut().flatten().flatten() {
+            if let Some((attribute, reference)) = tag.clone().split_once(':') {
+                if let Some(user) = ids.get(reference) {
+                    *tag = format!("{}:{}", attribute, user.name);
+                }
+            }
+        }
+    }
+    export.players = players.iter().map(ExportPlayer::from).collect();
+}
+
+fn new_user(name: &str, chat_id: &str) -> Result<User, Error> {
+    let mut new_user = NewUser::from(name.to_string(), chat_id.to_string());
+    GameAggregator::new().validate_user(&mut new_user);
+    match User::from(new_user) {
+        Ok(user) => Ok(user),
+        Err(_) => Err(Error::other(format!("Username {} is on a reserved list. Rename the player in the file.", name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::database::test_database::user;
+
+    use super::{csv_fields, parse_csv, remap_players};
+
+    const CHAT_ID: i64 = -1044;
+
+    #[test]
+    fn csv_fields_handle_quotes() {
+        assert_eq!(csv_fields("1,I3 T,,ana"), vec!["1", "I3 T", "", "ana"]);
+        assert_eq!(csv_fields("\"a,b\",\"say \"\"hi\"\"\","), vec!["a,b", "say \"hi\"", ""]);
+        assert_eq!(csv_fields(""), vec![""]);
+    }
+
+    #[test]
+    fn csv_with_contracts_is_tarok() {
+        let export = parse_csv("round,contract,dealer,Ana,Ana attributes,Bor,Bor attributes\n\
+            1,I3 T,bor,25,IG M:BOR,,M\n\
+            \n\
+            2,KL,ana,-10,,-20,IG\n").expect("Error parsing csv");
+        assert_eq!(export.game_type, "tarok");
+        assert_eq!(export.rounds, 2);
+        assert_eq!(export.players.iter().map(|p| p.name.as_str()).collect::<Vec<&str>>(), vec!["ANA", "BOR"]);
+        assert_eq!(export.score["ANA"], vec![Some(25), Some(-10)]);
+        assert_eq!(export.score["BOR"], vec![None, Some(-20)]);
+        assert_eq!(export.game_attributes, vec![vec!["I3", "T"], vec!["KL"]]);
+        assert_eq!(export.player_attributes["ANA"], vec![Some(vec!["IG".to_string(), "M:BOR".to_string()]), None]);
+        assert_eq!(export.dealers, vec![Some("BOR".to_string()), Some("ANA".to_string())]);
+        assert!(export.seating.is_none());
+    }
+
+    #[test]
+    fn csv_without_contracts_is_a_table() {
+        let export = parse_csv("round,contract,dealer,Ana,Ana attributes,Bor,Bor attributes,Cene,Cene attributes\n1,,,3,,5,,\n").expect("Error parsing csv");
+        assert_eq!(export.game_type, "table");
+        assert_eq!(export.score["CENE"], vec![None]);
+        assert_eq!(export.dealers, vec![None]);
+    }
+
+    #[test]
+    fn broken_csv_is_rejected() {
+        assert!(parse_csv("").is_err());
+        assert!(parse_csv("round,contract,dealer,Ana").is_err());
+        assert!(parse_csv("id,contract,dealer,Ana,Ana attributes").is_err());
+        assert!(parse_csv("round,contract,dealer,Ana,Ana attributes\n1,I3,,ten,").is_err());
+        assert!(parse_csv("round,contract,dealer,Ana,Ana attributes\n1,I3,,10,,5").is_err());
+    }
+
+    #[test]
+    fn players_are_remapped_to_registered_users() {
+        let mut export = parse_csv("round,contract,dealer,Ana,Ana attributes,Bor,Bor attributes\n1,I3,ana,20,M:BOR,,\n").expect("Error parsing csv");
+        export.totals.insert("ANA".to_string(), 20);
+        export.radlci.insert("BOR".to_string(), vec!["A".to_string()]);
+        export.seating = Some(vec!["BOR".to_string(), "ANA".to_string()]);
+        let (ana, bor) = (user(CHAT_ID, "ANA"), user(CHAT_ID, "BOR"));
+        remap_players(&mut export, &[ana.clone(), bor.clone()]);
+
+        assert_eq!(export.players.iter().map(|p| p.id.clone()).collect::<Vec<String>>(), vec![ana.id.clone(), bor.id.clone()]);
+        assert_eq!(export.score[&ana.id], vec![Some(20)]);
+        assert_eq!(export.totals[&ana.id], 20);
+        assert_eq!(export.radlci[&bor.id], vec!["A".to_string()]);
+        assert_eq!(export.dealers, vec![Some(ana.id.clone())]);
+        assert_eq!(export.seating, Some(vec![bor.id.clone(), ana.id.clone()]));
+        assert_eq!(export.player_attributes[&ana.id], vec![Some(vec!["M:BOR".to_string()])]);
+        assert_eq!(export.player_attributes[&bor.id], vec![None]);
+        assert!(!export.score.contains_key("ANA"));
+    }
+}
@@ -0,0 +1,327 @@
+use std::{collections::HashMap, io::Error};
+
+use chrono::{DateTime, Datelike, Utc};
+
+use crate::models::{game::{ArchivedGame, GameResult}, user::User};
+
+use super::{html_template::{escape_html, render_sheet, Theme}, score_image::{GridRow, RowStyle, ScoreGrid}};
+use super::database::{game_operations::{get_games_by_chat, get_results_by_games}, user_operations::get_users_by_chat};
+
+pub enum LeaderboardWindow {
+    AllTime,
+    ThisMonth,
+    LastGames(usize),
+}
+
+pub struct LeaderboardEntry {
+    pub player_id: String,
+    pub name: String,
+    pub games: i32,
+    pub wins: i32,
+    pub placement_sum: i32,
+    pub points: i32,
+}
+
+pub struct Leaderboard {
+    pub title: String,
+    pub games: usize,
+    pub entries: Vec<LeaderboardEntry>,
+    pub players: Vec<User>,     // registered players of the entries, in ranking order
+}
+
+impl LeaderboardEntry {
+    pub fn average_placement(&self) -> f32 {
+        if self.games == 0 {
+            return 0.;
+        }
+        self.placement_sum as f32 / self.games as f32
+    }
+}
+
+// /leaderboard [all|month|last N] [tarok|table] [image], the flag is true for an image
+pub fn parse_leaderboard_args(text: &str) -> Result<(LeaderboardWindow, Option<String>, bool), Error> {
+    let mut window = LeaderboardWindow::AllTime;
+    let mut game_type = None;
+    let mut image = false;
+    let mut args = text.split_whitespace().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_lowercase().as_str() {
+            "all" => window = LeaderboardWindow::AllTime,
+            "month" => window = LeaderboardWindow::ThisMonth,
+            "last" => {
+                let count = match args.next().map(|n| n.parse::<usize>()) {
+                    Some(Ok(count)) if count > 0 => count,
+                    _ => return Err(Error::other("Expected a number of games after last".to_string())),
+                };
+                window = LeaderboardWindow::LastGames(count);
+            },
+            "tarok" | "table" => game_type = Some(arg.to_lowercase()),
+            "image" => image = true,
+            _ => return Err(Error::other(
+                format!("Unknown option {}. Use /leaderboard [all|month|last N] [tarok|table] [image]", arg))
+            ),
+        };
+    }
+    Ok((window, game_type, image))
+}
+
+pub fn build_leaderboard(
+    chat_id: String,
+    window: LeaderboardWindow,
+    game_type: Option<String>,
+) -> Result<Leaderboard, Error> {
+    let archived = match get_games_by_chat(chat_id.clone()) {
+        Ok(games) => games,
+        Err(e) => return Err(Error::other(format!("Error fetching games from DB: {}", e))),
+    };
+    let games = filter_games(archived, &window, &game_type);
+    let results = match get_results_by_games(games.iter().map(|g| g.id.clone()).collect()) {
+        Ok(results) => results,
+        Err(e) => return Err(Error::other(format!("Error fetching game results from DB: {}", e))),
+    };
+    let users = match get_users_by_chat(chat_id) {
+        Ok(users) => users,
+        Err(e) => return Err(Error::other(format!("Error fetching users from DB: {}", e))),
+    };
+    let names: HashMap<String, String> = users
+        .iter()
+        .map(|u| (u.id.clone(), u.name.clone()))
+        .collect();
+    let entries = aggregate_results(&results, &names);
+    let players = entries
+        .iter()
+        .filter_map(|e| users.iter().find(|u| u.id == e.player_id).cloned())
+        .collect();
+    Ok(Leaderboard {
+        title: leaderboard_title(&window, &game_type),
+        games: games.len(),
+        entries,
+        players,
+    })
+}
+
+fn filter_games(
+    games: Vec<ArchivedGame>,
+    window: &LeaderboardWindow,
+    game_type: &Option<String>,
+) -> Vec<ArchivedGame> {
+    // games come ordered by finish time
+    let mut games: Vec<ArchivedGame> = games
+        .into_iter()
+        .filter(|g| match game_type {
+            Some(game_type) => &g.game_type == game_type,
+            None => true,
+        })
+        .collect();
+    match window {
+        LeaderboardWindow::AllTime => games,
+        LeaderboardWindow::ThisMonth => {
+            let now = Utc::now();
+            games
+                .into_iter()
+                .filter(|g| match DateTime::parse_from_rfc3339(&g.finished_at) {
+                    Ok(finished) => finished.year() == now.year() && finished.month() == now.month(),
+                    Err(_) => false,
+                })
+                .collect()
+        },
+        LeaderboardWindow::LastGames(count) => {
+            let skip = games.len().saturating_sub(*count);
+            games.drain(..skip);
+            games
+        },
+    }
+}
+
+fn aggregate_results(results: &[GameResult], names: &HashMap<String, String>) -> Vec<LeaderboardEntry> {
+    let mut entries: HashMap<String, LeaderboardEntry> = HashMap::new();
+    for result in results.iter() {
+        let entry = entries
+            .entry(result.player_id.clone())
+            .or_insert(LeaderboardEntry {
+                player_id: result.player_id.clone(),
+                name: match names.get(&result.player_id) {
+                    Some(name) => name.clone(),
+                    None => result.player_id.clone(),
+                },
+                games: 0,
+                wins: 0,
+                placement_sum: 0,
+                points: 0,
+            });
+        entry.games += 1;
+        entry.placement_sum += result.placement;
+        entry.points += result.total;
+        if result.placement == 1 {
+            entry.wins += 1;
+        }
+    }
+    let mut entries: Vec<LeaderboardEntry> = entries.into_values().collect();
+    entries.sort_by(|a, b| b.wins
+        .cmp(&a.wins)
+        .then(a.average_placement().total_cmp(&b.average_placement()))
+        .then(b.points.cmp(&a.points))
+        .then(a.name.cmp(&b.name))
+    );
+    entries
+}
+
+fn leaderboard_title(window: &LeaderboardWindow, game_type: &Option<String>) -> String {
+    let window = match window {
+        LeaderboardWindow::AllTime => "all time".to_string(),
+        LeaderboardWindow::ThisMonth => "this month".to_string(),
+        LeaderboardWindow::LastGames(count) => format!("last {} games", count),
+    };
+    match game_type {
+        Some(game_type) => format!("Leaderboard ({}, {})", window, game_type),
+        None => format!("Leaderboard ({})", window),
+    }
+}
+
+pub fn leaderboard_to_text(leaderboard: &Leaderboard) -> String {
+    if leaderboard.entries.is_empty() {
+        return format!("{}\nNo finished games yet!", leaderboard.title);
+    }
+    let mut lines = vec![format!("{} - {} games", leaderboard.title, leaderboard.games)];
+    for (index, entry) in leaderboard.entries.iter().enumerate() {
+        lines.push(format!(
+            "{}. {}: {} wins, avg. place {:.2}, {} points ({} games)",
+            index + 1,
+            entry.name,
+            entry.wins,
+            entry.average_placement(),
+            entry.points,
+            entry.games,
+        ));
+    }
+    lines.join("\n")
+}
+
+// same page as the score sheets, so the chat's /score theme applies
+pub fn leaderboard_to_html(leaderboard: &Leaderboard, theme: &Theme) -> String {
+    let mut table = "<tr><th>#</th><th>Player</th><th>Wins</th><th>Avg. place</th><th>Points</th><th>Games</th></tr>".to_string();
+    for (index, entry) in leaderboard.entries.iter().enumerate() {
+        table = format!(
+            "{}<tr><td>{}</td><th>{}</th><td>{}</td><td>{:.2}</td><td>{}</td><td>{}</td></tr>",
+            table,
+            index + 1,
+            escape_html(&entry.name),
+            entry.wins,
+            entry.average_placement(),
+            entry.points,
+            entry.games,
+        );
+    }
+    render_sheet(
+        &format!("{} - {} games", leaderboard.title, leaderboard.games),
+        &leaderboard.players,
+        &table,
+        "",
+        theme
+    )
+}
+
+pub fn leaderboard_to_grid(leaderboard: &Leaderboard) -> ScoreGrid {
+    let mut rows = vec![GridRow::new(
+        RowStyle::Header,
+        ["#", "Player", "Wins", "Avg. place", "Points", "Games"].iter().map(|c| c.to_string()).collect(),
+    )];
+    for (index, entry) in leaderboard.entries.iter().enumerate() {
+        rows.push(GridRow::new(RowStyle::Round, vec![
+            (index + 1).to_string(),
+            entry.name.clone(),
+            entry.wins.to_string(),
+            format!("{:.2}", entry.average_placement()),
+            entry.points.to_string(),
+            entry.games.to_string(),
+        ]));
+    }
+    rows.push(GridRow::new(RowStyle::Note, vec![format!("{} - {} games", leaderboard.title, leaderboard.games)]));
+    ScoreGrid { rows }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{Duration, Utc};
+
+    use crate::{core::html_template::Theme, models::game::{ArchivedGame, GameResult}};
+
+    use super::{aggregate_results, filter_games, leaderboard_to_grid, leaderboard_to_html, parse_leaderboard_args, Leaderboard, LeaderboardWindow};
+
+    fn game(id: &str, game_type: &str, finished_at: String) -> ArchivedGame {
+        ArchivedGame::from(id.to_string(), "-1034".to_string(), game_type.to_string(), finished_at, None, None)
+    }
+
+    fn ids(games: &[ArchivedGame]) -> Vec<&str> {
+        games.iter().map(|g| g.id.as_str()).collect()
+    }
+
+    #[test]
+    fn leaderboard_args_are_parsed() {
+        assert!(matches!(parse_leaderboard_args("/leaderboard").unwrap(), (LeaderboardWindow::AllTime, None, false)));
+        assert!(matches!(
+            parse_leaderboard_args("/leaderboard MONTH tarok").unwrap(),
+            (LeaderboardWindow::ThisMonth, Some(t), false) if t == "tarok"
+        ));
+        assert!(matches!(
+            parse_leaderboard_args("/leaderboard table last 5 image").unwrap(),
+            (LeaderboardWindow::LastGames(5), Some(t), true) if t == "table"
+        ));
+        for text in ["/leaderboard last", "/leaderboard last 0", "/leaderboard last few", "/leaderboard weekly"] {
+            assert!(parse_leaderboard_args(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn games_are_filtered_by_window_and_type() {
+        let now = Utc::now();
+        let long_ago = (now - Duration::days(400)).to_rfc3339();
+        let games = || vec![
+            game("old", "tarok", long_ago.clone()),
+            game("broken", "tarok", "yesterday".to_string()),
+            game("table", "table", now.to_rfc3339()),
+            game("new", "tarok", now.to_rfc3339()),
+        ];
+        assert_eq!(ids(&filter_games(games(), &LeaderboardWindow::AllTime, &None)), vec!["old", "broken", "table", "new"]);
+        assert_eq!(ids(&filter_games(games(), &LeaderboardWindow::ThisMonth, &None)), vec!["table", "new"]);
+        assert_eq!(ids(&filter_games(games(), &LeaderboardWindow::LastGames(2), &None)), vec!["table", "new"]);
+        // the type is filtered before the last games are counted
+        let tarok = Some("tarok".to_string());
+        assert_eq!(ids(&filter_games(games(), &LeaderboardWindow::LastGames(2), &tarok)), vec!["broken", "new"]);
+        assert_eq!(ids(&filter_games(games(), &LeaderboardWindow::LastGames(10), &tarok)), vec!["old", "broken", "new"]);
+    }
+
+    #[test]
+    fn leaderboard_is_rendered_with_the_theme() {
+        let results = vec![
+            GameResult::from("1".to_string(), "a".to_string(), 30, 1),
+            GameResult::from("1".to_string(), "b".to_string(), -30, 2),
+            GameResult::from("2".to_string(), "b".to_string(), 10, 1),
+            GameResult::from("2".to_string(), "a".to_string(), 0, 2),
+            GameResult::from("3".to_string(), "a".to_string(), 5, 1),
+        ];
+        let names = HashMap::from([("a".to_string(), "A<B>".to_string())]);
+        let board = Leaderboard {
+            title: "Leaderboard (all time)".to_string(),
+            games: 3,
+            entries: aggregate_results(&results, &names),
+            players: vec![],
+        };
+        assert_eq!(board.entries.iter().map(|e| e.name.as_str()).collect::<Vec<&str>>(), vec!["A<B>", "b"]);
+        assert_eq!((board.entries[0].wins, board.entries[0].points, board.entries[0].games), (2, 35, 3));
+
+        let html = leaderboard_to_html(&board, &Theme::Dark);
+        assert!(html.contains("Leaderboard (all time) - 3 games"));
+        assert!(html.contains("<th>A&lt;B&gt;</th><td>2</td><td>1.33</td><td>35</td><td>3</td>"), "{}", html);
+        assert!(!html.contains("A<B>"));
+        assert_ne!(html, leaderboard_to_html(&board, &Theme::Light));
+
+        let grid = leaderboard_to_grid(&board);
+        assert_eq!(grid.rows.len(), 4);
+        assert_eq!(grid.rows[1].cells[1].0, "A<B>");
+        assert_eq!(grid.rows[3].cells[0].0, "Leaderboard (all time) - 3 games");
+    }
+}
@@ -2,4 +2,22 @@ pub mod traits;
 pub mod game_aggregator;
 pub mod game_handler;
 pub mod database;
-pub mod message_helper;
\ No newline at end of file
+pub mod message_helper;
+pub mod score_sheet;
+pub mod archive;
+pub mod leaderboard;
+pub mod rating;
+pub mod settlement;
+pub mod season;
+pub mod tournament;
+pub mod head_to_head;
+pub mod achievements;
+pub mod score_image;
+pub mod score_text;
+pub mod export;
+pub mod import;
+pub mod spreadsheet;
+pub mod chart;
+pub mod html_template;
+pub mod pdf;
+pub mod storage;
\ No newline at end of file
@@ -0,0 +1,465 @@
+use chrono::Utc;
+
+use crate::games::tarok::{game::contract_values, rules::TarokRules};
+
+use super::{export::GameExport, spreadsheet::capitalize};
+
+// A4 portrait in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 40.0;
+const ROW_HEIGHT: f32 = 14.0;
+const FONT_SIZE: f32 = 8.0;
+const MARKER_SIZE: f32 = 6.5;
+const ROUND_WIDTH: f32 = 24.0;
+const CONTRACT_WIDTH: f32 = 72.0;
+const CELL_PADDING: f32 = 3.0;
+const LEGEND_HEIGHT: f32 = 200.0;
+
+// tags of the player attributes as they show up next to the score
+const MARKERS: [(&str, &str); 7] = [
+    ("IG", "Declarer"),
+    ("SL", "Supporting"),
+    ("M", "Mond lost (M:<player> to the player)"),
+    ("MC", "Mond captured (MC:<player> from the player)"),
+    ("R", "Renonce"),
+    ("R:<player>", "Renonce by an opponent"),
+    ("D", "Dealer"),
+];
+
+// Helvetica advance widths of the printable ascii characters, in 1/1000 of the font size
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+#[derive(Clone, Copy)]
+struct Font {
+    size: f32,
+    bold: bool,
+    gray: f32,
+}
+
+const TITLE: Font = Font { size: 18.0, bold: true, gray: 0.0 };
+const SUBTITLE: Font = Font { size: 9.0, bold: false, gray: 0.3 };
+const SECTION: Font = Font { size: 10.0, bold: true, gray: 0.0 };
+const BOLD: Font = Font { size: FONT_SIZE, bold: true, gray: 0.0 };
+const REGULAR: Font = Font { size: FONT_SIZE, bold: false, gray: 0.0 };
+const MUTED: Font = Font { size: FONT_SIZE, bold: false, gray: 0.3 };
+const MARKER: Font = Font { size: MARKER_SIZE, bold: false, gray: 0.35 };
+const FOOTER: Font = Font { size: 7.0, bold: false, gray: 0.4 };
+
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// Pages are collected as raw content streams and only numbered and linked in the end.
+struct Document {
+    pages: Vec<Vec<u8>>,
+    current: usize,
+}
+
+impl Document {
+    fn new_page(&mut self) {
+        self.pages.push(vec![]);
+        self.current = self.pages.len() - 1;
+    }
+
+    fn ops(&mut self, ops: &[u8]) {
+        if let Some(page) = self.pages.get_mut(self.current) {
+            page.extend_from_slice(ops);
+        }
+    }
+
+    fn text(&mut self, x: f32, y: f32, text: &str, font: Font, align: Align) {
+        let x = match align {
+            Align::Left => x,
+            Align::Center => x - text_width(text, font.size, font.bold) / 2.0,
+            Align::Right => x - text_width(text, font.size, font.bold),
+        };
+        self.ops(format!("{:.2} g BT /{} {:.1} Tf {:.2} {:.2} Td ", font.gray, if font.bold { "F2" } else { "F1" }, font.size, x, y).as_bytes());
+        self.ops(&pdf_string(text));
+        self.ops(b" Tj ET\n");
+    }
+
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, gray: f32) {
+        self.ops(format!("{:.2} g {:.2} {:.2} {:.2} {:.2} re f\n", gray, x, y, width, height).as_bytes());
+    }
+
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), gray: f32) {
+        self.ops(format!("{:.2} G 0.5 w {:.2} {:.2} m {:.2} {:.2} l S\n", gray, from.0, from.1, to.0, to.1).as_bytes());
+    }
+}
+
+// Print-ready A4 scorecard: header, every round with its contract and markers, radlci,
+// totals and for tarok a legend of the codes. Written by hand with the standard
+// Helvetica font, so nothing has to be embedded.
+pub fn to_pdf(export: &GameExport) -> Vec<u8> {
+    let title = capitalize(&export.game_type);
+    let date = Utc::now().format("%Y-%m-%d %H:%M").to_string();
+    let with_contracts = export.game_attributes.iter().any(|a| !a.is_empty());
+    let contract_width = if with_contracts { CONTRACT_WIDTH } else { 0.0 };
+    let player_width = (PAGE_WIDTH - 2.0 * MARGIN - ROUND_WIDTH - contract_width) / export.players.len().max(1) as f32;
+    let player_x = |index: usize| MARGIN + ROUND_WIDTH + contract_width + index as f32 * player_width;
+    let table_right = PAGE_WIDTH - MARGIN;
+
+    let mut document = Document { pages: vec![], current: 0 };
+    document.new_page();
+    let mut y = PAGE_HEIGHT - MARGIN;
+    document.text(MARGIN, y - 16.0, &title, TITLE, Align::Left);
+    y -= 34.0;
+    document.text(MARGIN, y, &format!("Game {}, printed {}", short_id(&export.game_id), date), SUBTITLE, Align::Left);
+    y -= 13.0;
+    let players = export.players.iter().map(|p| p.name.clone()).collect::<Vec<String>>().join(", ");
+    document.text(MARGIN, y, &fit(&format!("Players: {}", players), table_right - MARGIN, 9.0, false), SUBTITLE, Align::Left);
+    y -= 22.0;
+
+    let table_header = |document: &mut Document, y: f32| {
+        document.rect(MARGIN, y - 4.0, table_right - MARGIN, ROW_HEIGHT, 0.85);
+        document.text(MARGIN + CELL_PADDING, y, "#", BOLD, Align::Left);
+        if with_contracts {
+            document.text(MARGIN + ROUND_WIDTH + CELL_PADDING, y, "Contract", BOLD, Align::Left);
+        }
+        for (index, player) in export.players.iter().enumerate() {
+            let name = fit(&player.name, player_width - 2.0 * CELL_PADDING, FONT_SIZE, true);
+            document.text(player_x(index) + player_width / 2.0, y, &name, BOLD, Align::Center);
+        }
+    };
+    table_header(&mut document, y);
+    y -= ROW_HEIGHT;
+
+    // radlci left to play and what they cost at the end
+    if !export.radlci.is_empty() {
+        document.text(MARGIN + CELL_PADDING, y, "Radlci", MUTED, Align::Left);
+        for (index, player) in export.players.iter().enumerate() {
+            let mut radlci: Vec<&str> = export.radlci
+                .get(&player.id)
+                .into_iter()
+                .flatten()
+                .map(|r| if r == "available" { "O" } else { "Ø" })
+                .collect();
+            let played: i32 = export.score.get(&player.id).map_or(0, |s| s.iter().flatten().sum());
+            let adjustment = export.totals.get(&player.id).unwrap_or(&played) - played;
+            let adjustment = format!("({})", adjustment);
+            if adjustment != "(0)" {
+                radlci.push(&adjustment);
+            }
+            let text = fit(&radlci.join(" "), player_width - 2.0 * CELL_PADDING, FONT_SIZE, false);
+            document.text(player_x(index) + player_width / 2.0, y, &text, MUTED, Align::Center);
+        }
+        y -= ROW_HEIGHT;
+    }
+
+    for round in 0..export.rounds as usize {
+        if y - ROW_HEIGHT < MARGIN + 12.0 {
+            document.new_page();
+            y = PAGE_HEIGHT - MARGIN - 10.0;
+            table_header(&mut document, y);
+            y -= ROW_HEIGHT;
+        }
+        if round % 2 == 1 {
+            document.rect(MARGIN, y - 4.0, table_right - MARGIN, ROW_HEIGHT, 0.95);
+        }
+        document.text(MARGIN + CELL_PADDING, y, &(round + 1).to_string(), MUTED, Align::Left);
+        if let Some(tags) = export.game_attributes.get(round) {
+            let contract = fit(&tags.join(" "), CONTRACT_WIDTH - 2.0 * CELL_PADDING, FONT_SIZE, false);
+            document.text(MARGIN + ROUND_WIDTH + CELL_PADDING, y, &contract, REGULAR, Align::Left);
+        }
+        for (index, player) in export.players.iter().enumerate() {
+            let score = match export.score.get(&player.id).and_then(|s| s.get(round)) {
+                Some(Some(score)) => score.to_string(),
+                _ => "".to_string(),
+            };
+            let right = player_x(index) + player_width - CELL_PADDING;
+            document.text(right, y, &score, REGULAR, Align::Right);
+            let mut markers: Vec<String> = match export.player_attributes.get(&player.id).and_then(|a| a.get(round)) {
+                // the point differences are already in the score
+                Some(Some(tags)) => tags.iter().filter(|t| t.parse::<i32>().is_err()).cloned().collect(),
+                _ => vec![],
+            };
+            if let Some(Some(dealer)) = export.dealers.get(round) {
+                if dealer == &player.id {
+                    markers.push("D".to_string());
+                }
+            }
+            let room = player_width - 3.0 * CELL_PADDING - text_width(&score, FONT_SIZE, false);
+            let markers = fit(&markers.join(" "), room, MARKER_SIZE, false);
+            document.text(player_x(index) + CELL_PADDING, y, &markers, MARKER, Align::Left);
+        }
+        y -= ROW_HEIGHT;
+    }
+
+    document.line((MARGIN, y + ROW_HEIGHT - 4.0), (table_right, y + ROW_HEIGHT - 4.0), 0.0);
+    document.text(MARGIN + CELL_PADDING, y, "Total", BOLD, Align::Left);
+    for (index, player) in export.players.iter().enumerate() {
+        let total = export.totals.get(&player.id).map(|t| t.to_string()).unwrap_or_default();
+        document.text(player_x(index) + player_width - CELL_PADDING, y, &total, BOLD, Align::Right);
+    }
+    y -= 2.0 * ROW_HEIGHT;
+
+    if export.game_type == "tarok" {
+        if y - LEGEND_HEIGHT < MARGIN + 12.0 {
+            document.new_page();
+            y = PAGE_HEIGHT - MARGIN - 10.0;
+        }
+        draw_tarok_legend(&mut document, y, &export.rules.clone().unwrap_or_default());
+    }
+
+    // the page count is only known now
+    let count = document.pages.len();
+    for index in 0..count {
+        document.current = index;
+        let footer = format!("{} {} - page {} of {}", title, short_id(&export.game_id), index + 1, count);
+        document.text(table_right, MARGIN / 2.0, &footer, FOOTER, Align::Right);
+    }
+    write_pdf(&document.pages, &format!("{} {}", title, short_id(&export.game_id)))
+}
+
+fn draw_tarok_legend(document: &mut Document, top: f32, rules: &TarokRules) {
+    let column = (PAGE_WIDTH - 2.0 * MARGIN) / 2.0;
+    let mut y = top;
+    document.text(MARGIN, y, "Markers", SECTION, Align::Left);
+    y -= ROW_HEIGHT;
+    for (index, (code, title)) in MARKERS.iter().enumerate() {
+        let x = MARGIN + (index % 2) as f32 * column;
+        document.text(x, y, code, BOLD, Align::Left);
+        document.text(x + 52.0, y, title, REGULAR, Align::Left);
+        if index % 2 == 1 || index == MARKERS.len() - 1 {
+            y -= 11.0;
+        }
+    }
+    y -= ROW_HEIGHT;
+    document.text(MARGIN, y, "Contracts", SECTION, Align::Left);
+    y -= ROW_HEIGHT;
+    let contracts = contract_values(rules);
+    let per_column = contracts.len().div_ceil(2);
+    for (index, (code, name, value)) in contracts.iter().enumerate() {
+        let x = MARGIN + (index / per_column) as f32 * column;
+        let row_y = y - (index % per_column) as f32 * 11.0;
+        document.text(x, row_y, code, BOLD, Align::Left);
+        document.text(x + 40.0, row_y, name, REGULAR, Align::Left);
+        document.text(x + column - 20.0, row_y, &value.to_string(), REGULAR, Align::Right);
+    }
+}
+
+fn write_pdf(pages: &[Vec<u8>], title: &str) -> Vec<u8> {
+    // 1 catalog, 2 page tree, 3 and 4 fonts, 5 info, then a page and its content for every page
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!(
+            "<< /Type /Pages /Count {} /Kids [{}] >>",
+            pages.len(),
+            (0..pages.len()).map(|i| format!("{} 0 R", 6 + 2 * i)).collect::<Vec<String>>().join(" ")
+        ).into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+    let mut info = b"<< /Producer (telegram_bot_score_keeper) /Title ".to_vec();
+    info.extend(pdf_string(title));
+    info.extend_from_slice(b" >>");
+    objects.push(info);
+    for (index, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            PAGE_WIDTH, PAGE_HEIGHT, 7 + 2 * index
+        ).into_bytes());
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(content);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = vec![];
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", index + 1).into_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets.iter() {
+        pdf.extend(format!("{:010} 00000 n \n", offset).into_bytes());
+    }
+    pdf.extend(format!(
+        "trailer\n<< /Size {} /Root 1 0 R /Info 5 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ).into_bytes());
+    pdf
+}
+
+fn short_id(game_id: &str) -> &str {
+    game_id.split('-').next().unwrap_or("")
+}
+
+// shortens the text with an ellipsis until it fits the width
+fn fit(text: &str, width: f32, size: f32, bold: bool) -> String {
+    if text_width(text, size, bold) <= width {
+        return text.to_string();
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let shortened = format!("{}…", chars.iter().collect::<String>());
+        if text_width(&shortened, size, bold) <= width {
+            return shortened;
+        }
+    }
+    "".to_string()
+}
+
+fn text_width(text: &str, size: f32, bold: bool) -> f32 {
+    let units: u32 = encode(text)
+        .iter()
+        .map(|b| match b {
+            32..=126 => HELVETICA_WIDTHS[(b - 32) as usize] as u32,
+            _ => 600,
+        })
+        .sum();
+    // the bold cut runs about a tenth wider
+    let units = if bold { units as f32 * 1.1 } else { units as f32 };
+    units * size / 1000.0
+}
+
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut bytes = vec![b'('];
+    for byte in encode(text) {
+        if let b'(' | b')' | b'\\' = byte {
+            bytes.push(b'\\');
+        }
+        bytes.push(byte);
+    }
+    bytes.push(b')');
+    bytes
+}
+
+// Text in WinAnsi, the encoding of the standard fonts. Letters it lacks lose their accent (Č -> C).
+fn encode(text: &str) -> Vec<u8> {
+    text
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            'Š' => 0x8a,
+            'š' => 0x9a,
+            'Ž' => 0x8e,
+            'ž' => 0x9e,
+            'Œ' => 0x8c,
+            'œ' => 0x9c,
+            'Ÿ' => 0x9f,
+            '€' => 0x80,
+            '…' => 0x85,
+            '–' => 0x96,
+            '—' => 0x97,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            'Č' | 'Ć' => b'C',
+            'č' | 'ć' => b'c',
+            'Đ' => 0xd0,
+            'đ' => b'd',
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{core::export::{ExportPlayer, GameExport}, games::tarok::rules::TarokRules};
+
+    use super::{encode, fit, pdf_string, text_width, to_pdf, FONT_SIZE};
+
+    fn export(game_type: &str, rounds: usize) -> GameExport {
+        let players: Vec<ExportPlayer> = ["ŽIGA", "ČRT", "ŠPELA"]
+            .iter()
+            .enumerate()
+            .map(|(index, name)| ExportPlayer { id: index.to_string(), name: name.to_string() })
+            .collect();
+        GameExport {
+            game_id: "1a2b3c4d-0000".to_string(),
+            game_type: game_type.to_string(),
+            rounds: rounds as i32,
+            score: players.iter().map(|p| (p.id.clone(), vec![Some(10); rounds])).collect(),
+            totals: players.iter().map(|p| (p.id.clone(), 10 * rounds as i32)).collect(),
+            players,
+            game_attributes: vec![vec![]; rounds],
+            player_attributes: HashMap::new(),
+            radlci: HashMap::new(),
+            dealers: vec![],
+            details: vec![],
+            seating: None,
+            dealer: None,
+            rules: None,
+        }
+    }
+
+    fn count(pdf: &[u8], needle: &[u8]) -> usize {
+        pdf.windows(needle.len()).filter(|w| w == &needle).count()
+    }
+
+    #[test]
+    fn slovenian_letters_are_encoded() {
+        assert_eq!(encode("ŠŽšž"), vec![0x8a, 0x8e, 0x9a, 0x9e]);
+        // WinAnsi has no č, ć or đ, so they lose the accent
+        assert_eq!(encode("ČĆčćĐđ"), vec![b'C', b'C', b'c', b'c', 0xd0, b'd']);
+        assert_eq!(encode("Ø O…€"), vec![0xd8, b' ', b'O', 0x85, 0x80]);
+        assert_eq!(encode("ß→"), vec![0xdf, b'?']);
+        assert_eq!(pdf_string("ŠKRAT (2)"), b"(\x8aKRAT \\(2\\))".to_vec());
+    }
+
+    #[test]
+    fn long_names_are_shortened_to_fit() {
+        assert_eq!(fit("ŽIGA", 100.0, FONT_SIZE, false), "ŽIGA");
+        let name = "ŠPELA ČEBAŠEK ŽUPANČIČ";
+        let width = text_width("ŠPELA ČEB", FONT_SIZE, true);
+        let shortened = fit(name, width, FONT_SIZE, true);
+        assert!(shortened.ends_with('…') && shortened.starts_with("ŠPELA"), "{}", shortened);
+        assert!(text_width(&shortened, FONT_SIZE, true) <= width);
+        assert!(shortened.chars().count() < name.chars().count());
+        assert_eq!(fit(name, 1.0, FONT_SIZE, false), "");
+    }
+
+    #[test]
+    fn rounds_beyond_a_page_continue_on_the_next() {
+        let short = to_pdf(&export("table", 10));
+        assert!(short.starts_with(b"%PDF-1.4"));
+        assert!(short.ends_with(b"%%EOF\n"));
+        assert_eq!(count(&short, b"/Type /Page "), 1);
+        assert_eq!(count(&short, b"page 1 of 1"), 1);
+        assert_eq!(count(&short, b"(\x8eIGA)"), 1);
+
+        let long = to_pdf(&export("table", 80));
+        assert_eq!(count(&long, b"/Type /Page "), 2);
+        assert_eq!(count(&long, b"/Count 2"), 1);
+        assert_eq!(count(&long, b"page 2 of 2"), 1);
+        // the player header is repeated on the new page
+        assert_eq!(count(&long, b"(\x8eIGA)"), 2);
+        assert_eq!(count(&long, b"(800)"), 3);
+    }
+
+    #[test]
+    fn tarok_legend_shows_the_contract_values_of_the_rules() {
+        let mut tarok = export("tarok", 2);
+        let defaults = to_pdf(&tarok);
+        assert_eq!(count(&defaults, b"(500)"), 1);
+        assert_eq!(count(&defaults, b"(650)"), 0);
+
+        let mut rules = TarokRules::new();
+        rules.announced_valat = 650;
+        tarok.rules = Some(rules);
+        let changed = to_pdf(&tarok);
+        assert_eq!(count(&changed, b"(500)"), 0);
+        assert_eq!(count(&changed, b"(650)"), 1);
+    }
+}
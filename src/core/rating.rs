@@ -0,0 +1,260 @@
+use std::{collections::HashMap, io::Error};
+
+use crate::models::game::{ArchivedGame, GameResult};
+
+use super::database::{game_operations::{get_games_by_chat, get_results_by_games}, user_operations::{get_users_by_chat, get_user_by_name}};
+
+const INITIAL_RATING: f64 = 1500.;
+const K_FACTOR: f64 = 32.;
+
+pub struct RatingChange {
+    pub finished_at: String,
+    pub before: f64,
+    pub after: f64,
+}
+
+pub struct PlayerRating {
+    pub player_id: String,
+    pub rating: f64,
+    pub history: Vec<RatingChange>,
+}
+
+// game type -> player id -> rating
+pub type Ratings = HashMap<String, HashMap<String, PlayerRating>>;
+
+// Replays every archived game of the chat in finishing order. Ratings are never stored,
+// so the same archive always gives the same ratings.
+pub fn replay_ratings(chat_id: String) -> Result<Ratings, Error> {
+    let games = match get_games_by_chat(chat_id) {
+        Ok(games) => games,
+        Err(e) => return Err(Error::other(format!("Error fetching games from DB: {}", e))),
+    };
+    let results = match get_results_by_games(games.iter().map(|g| g.id.clone()).collect()) {
+        Ok(results) => results,
+        Err(e) => return Err(Error::other(format!("Error fetching game results from DB: {}", e))),
+    };
+    let mut results_by_game: HashMap<String, Vec<GameResult>> = HashMap::new();
+    for result in results.into_iter() {
+        results_by_game.entry(result.game_id.clone()).or_default().push(result);
+    }
+    let mut ratings: Ratings = HashMap::new();
+    for game in games.iter() {
+        if let Some(results) = results_by_game.get_mut(&game.id) {
+            results.sort_by(|a, b| a.player_id.cmp(&b.player_id));
+            let type_ratings = ratings.entry(game.game_type.clone()).or_default();
+            rate_game(type_ratings, game, results);
+        }
+    }
+    Ok(ratings)
+}
+
+// Multiplayer elo: every player is compared to every other player of the game as if it
+// was a 1v1 match decided by placement. The sum of those is scaled down by the number of opponents.
+fn rate_game(ratings: &mut HashMap<String, PlayerRating>, game: &ArchivedGame, results: &[GameResult]) {
+    if results.len() < 2 {
+        return;
+    }
+    let before: Vec<f64> = results
+        .iter()
+        .map(|r| match ratings.get(&r.player_id) {
+            Some(rating) => rating.rating,
+            None => INITIAL_RATING,
+        })
+        .collect();
+    let opponents = (results.len() - 1) as f64;
+    for (i, result) in results.iter().enumerate() {
+        let mut expected = 0.;
+        let mut actual = 0.;
+        for (j, opponent) in results.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            expected += 1. / (1. + 10_f64.powf((before[j] - before[i]) / 400.));
+            actual += match result.placement.cmp(&opponent.placement) {
+                std::cmp::Ordering::Less => 1.,
+                std::cmp::Ordering::Equal => 0.5,
+                std::cmp::Ordering::Greater => 0.,
+            };
+        }
+        let after = before[i] + K_FACTOR * (actual - expected) / opponents;
+        let rating = ratings
+            .entry(result.player_id.clone())
+            .or_insert(PlayerRating {
+                player_id: result.player_id.clone(),
+                rating: INITIAL_RATING,
+                history: vec![],
+            });
+        rating.rating = after;
+        rating.history.push(RatingChange {
+            finished_at: game.finished_at.clone(),
+            before: before[i],
+            after,
+        });
+    }
+}
+
+// /rating [player] [tarok|table]
+pub fn rating_report(chat_id: String, text: &str) -> Result<String, Error> {
+    let mut player_name = None;
+    let mut game_type = None;
+    for arg in text.split_whitespace().skip(1) {
+        match arg.to_lowercase().as_str() {
+            "tarok" | "table" => game_type = Some(arg.to_lowercase()),
+            _ => player_name = Some(arg.to_string()),
+        };
+    }
+    let ratings = replay_ratings(chat_id.clone())?;
+    let mut game_types: Vec<&String> = ratings
+        .keys()
+        .filter(|t| match &game_type {
+            Some(game_type) => *t == game_type,
+            None => true,
+        })
+        .collect();
+    game_types.sort();
+    if game_types.is_empty() {
+        return Ok("No finished games to rate yet!".to_string());
+    }
+
+    match player_name {
+        Some(name) => {
+            let user = match get_user_by_name(name.to_uppercase()) {
+                Ok(Some(user)) => user,
+                Ok(None) => return Err(Error::other(format!("Unknown player {}", name))),
+                Err(e) => return Err(Error::other(format!("Error fetching user from DB: {}", e))),
+            };
+            let mut lines = vec![format!("Rating history of {}", user.name)];
+            for game_type in game_types.iter() {
+                let rating = match ratings[*game_type].get(&user.id) {
+                    Some(rating) => rating,
+                    None => continue,
+                };
+                lines.push(format!("{}: {:.0}", game_type, rating.rating));
+                for change in rating.history.iter() {
+                    lines.push(format!(
+                        "  {} {:.0} -> {:.0} ({:+.0})",
+                        change.finished_at.split('T').next().unwrap_or(""),
+                        change.before,
+                        change.after,
+                        change.after - change.before,
+                    ));
+                }
+            }
+            Ok(lines.join("\n"))
+        },
+        None => {
+            let users = match get_users_by_chat(chat_id) {
+                Ok(users) => users,
+                Err(e) => return Err(Error::other(format!("Error fetching users from DB: {}", e))),
+            };
+            let mut lines = vec!["Ratings (use /rating <player> for history):".to_string()];
+            for game_type in game_types.iter() {
+                lines.push(format!("{}:", game_type));
+                let mut type_ratings: Vec<&PlayerRating> = ratings[*game_type].values().collect();
+                type_ratings.sort_by(|a, b| b.rating
+                    .total_cmp(&a.rating)
+                    .then(a.player_id.cmp(&b.player_id))
+                );
+                for (index, rating) in type_ratings.iter().enumerate() {
+                    let name = match users.iter().find(|u| u.id == rating.player_id) {
+                        Some(user) => user.name.clone(),
+                        None => rating.player_id.clone(),
+                    };
+                    lines.push(format!(
+                        "  {}. {} {:.0} ({} games)",
+                        index + 1,
+                        name,
+                        rating.rating,
+                        rating.history.len()
+                    ));
+                }
+            }
+            Ok(lines.join("\n"))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::models::game::{ArchivedGame, GameResult};
+
+    use super::{rate_game, PlayerRating, INITIAL_RATING};
+
+    fn game(id: &str) -> ArchivedGame {
+        ArchivedGame::from(id.to_string(), "-1035".to_string(), "tarok".to_string(), format!("2026-01-01T00:00:0{}", id), None, None)
+    }
+
+    fn results(game_id: &str, placements: &[(&str, i32)]) -> Vec<GameResult> {
+        placements
+            .iter()
+            .map(|(player, placement)| GameResult::from(game_id.to_string(), player.to_string(), 0, *placement))
+            .collect()
+    }
+
+    fn rating(ratings: &HashMap<String, PlayerRating>, player: &str) -> f64 {
+        ratings[player].rating
+    }
+
+    fn assert_zero_sum(ratings: &HashMap<String, PlayerRating>, players: usize) {
+        let sum: f64 = ratings.values().map(|r| r.rating).sum();
+        assert!((sum - INITIAL_RATING * players as f64).abs() < 1e-9, "{}", sum);
+    }
+
+    #[test]
+    fn three_player_game_is_rated() {
+        let mut ratings = HashMap::new();
+        rate_game(&mut ratings, &game("1"), &results("1", &[("ana", 1), ("bor", 2), ("cene", 3)]));
+        assert_eq!(rating(&ratings, "ana"), 1516.);
+        assert_eq!(rating(&ratings, "bor"), 1500.);
+        assert_eq!(rating(&ratings, "cene"), 1484.);
+        assert_zero_sum(&ratings, 3);
+        assert_eq!(ratings["ana"].history.len(), 1);
+        assert_eq!(ratings["ana"].history[0].before, 1500.);
+    }
+
+    #[test]
+    fn four_player_game_with_a_tie_is_rated() {
+        let mut ratings = HashMap::new();
+        rate_game(&mut ratings, &game("1"), &results("1", &[("ana", 1), ("bor", 2), ("cene", 2), ("dan", 4)]));
+        assert_eq!(rating(&ratings, "ana"), 1516.);
+        assert_eq!(rating(&ratings, "bor"), 1500.);
+        assert_eq!(rating(&ratings, "cene"), 1500.);
+        assert_eq!(rating(&ratings, "dan"), 1484.);
+        assert_zero_sum(&ratings, 4);
+    }
+
+    #[test]
+    fn replay_is_zero_sum_and_independent_of_result_order() {
+        let games = [
+            ("1", vec![("ana", 1), ("bor", 2), ("cene", 3), ("dan", 4)]),
+            ("2", vec![("ana", 3), ("bor", 1), ("cene", 2)]),
+            ("3", vec![("dan", 1), ("ana", 2), ("cene", 2), ("bor", 4)]),
+        ];
+        let mut ratings = HashMap::new();
+        let mut reversed = HashMap::new();
+        for (id, placements) in games.iter() {
+            rate_game(&mut ratings, &game(id), &results(id, placements));
+            let mut backwards = results(id, placements);
+            backwards.reverse();
+            rate_game(&mut reversed, &game(id), &backwards);
+        }
+        assert_zero_sum(&ratings, 4);
+        for player in ["ana", "bor", "cene", "dan"] {
+            assert!((rating(&ratings, player) - rating(&reversed, player)).abs() < 1e-9, "{}", player);
+        }
+        assert_eq!(ratings["dan"].history.len(), 2);
+        // the lowest rated player wins game 3 and gains more than an even start would give
+        let change = &ratings["dan"].history[1];
+        assert!(change.before < INITIAL_RATING);
+        assert!(change.after - change.before > 16.);
+    }
+
+    #[test]
+    fn single_player_game_is_not_rated() {
+        let mut ratings = HashMap::new();
+        rate_game(&mut ratings, &game("1"), &results("1", &[("ana", 1)]));
+        assert!(ratings.is_empty());
+    }
+}
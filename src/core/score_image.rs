@@ -0,0 +1,210 @@
+use std::{convert::Infallible, io::Error};
+
+use embedded_graphics::{
+    mono_font::{iso_8859_2::{FONT_8X13, FONT_8X13_BOLD}, MonoTextStyle},
+    pixelcolor::Rgb888,
+    prelude::*,
+    primitives::{PrimitiveStyle, Rectangle},
+    text::{Alignment, Baseline, Text, TextStyleBuilder},
+};
+
+// every pixel of the layout is drawn as a SCALE x SCALE block so the text stays readable on phones
+const SCALE: u32 = 2;
+const ROW_HEIGHT: u32 = 20;
+const CHAR_WIDTH: u32 = 8;
+const CELL_PADDING: u32 = 16;
+const MIN_CELL_WIDTH: u32 = 56;
+const MIN_IMAGE_WIDTH: u32 = 200;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RowStyle {
+    Header,
+    Radlc,
+    Round,
+    Total,
+    Note,   // one line of text across the whole table, e.g. the marker legend
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CellTone {
+    Normal,
+    Best,
+    Worst,
+}
+
+pub struct GridRow {
+    pub style: RowStyle,
+    pub cells: Vec<(String, CellTone)>,
+}
+
+// layout independent description of a score sheet, filled by each game and drawn by render_png
+pub struct ScoreGrid {
+    pub rows: Vec<GridRow>,
+}
+
+impl GridRow {
+    pub fn new(style: RowStyle, cells: Vec<String>) -> Self {
+        Self {
+            style,
+            cells: cells.into_iter().map(|c| (c, CellTone::Normal)).collect(),
+        }
+    }
+}
+
+// white drawing surface, also used to draw charts
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![255; (width * SCALE * height * SCALE * 3) as usize],
+        }
+    }
+}
+
+impl OriginDimensions for Canvas {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
+
+impl DrawTarget for Canvas {
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let row_bytes = (self.width * SCALE * 3) as usize;
+        for Pixel(point, color) in pixels.into_iter() {
+            if point.x < 0 || point.y < 0 || point.x as u32 >= self.width || point.y as u32 >= self.height {
+                continue;
+            }
+            for dy in 0..SCALE {
+                for dx in 0..SCALE {
+                    let x = (point.x as u32 * SCALE + dx) as usize;
+                    let y = (point.y as u32 * SCALE + dy) as usize;
+                    let index = y * row_bytes + x * 3;
+                    self.pixels[index] = color.r();
+                    self.pixels[index + 1] = color.g();
+                    self.pixels[index + 2] = color.b();
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn render_png(grid: &ScoreGrid) -> Result<Vec<u8>, Error> {
+    let columns = grid.rows
+        .iter()
+        .filter(|r| r.style != RowStyle::Note)
+        .map(|r| r.cells.len())
+        .max()
+        .unwrap_or(0);
+    let mut column_widths = vec![MIN_CELL_WIDTH; columns];
+    for row in grid.rows.iter().filter(|r| r.style != RowStyle::Note) {
+        for (index, (text, _)) in row.cells.iter().enumerate() {
+            column_widths[index] = column_widths[index].max(text.chars().count() as u32 * CHAR_WIDTH + CELL_PADDING);
+        }
+    }
+    let table_width: u32 = column_widths.iter().sum();
+    let note_width = grid.rows
+        .iter()
+        .filter(|r| r.style == RowStyle::Note)
+        .flat_map(|r| r.cells.iter())
+        .map(|(text, _)| text.chars().count() as u32 * CHAR_WIDTH + CELL_PADDING)
+        .max()
+        .unwrap_or(0);
+    let width = table_width.max(note_width).max(MIN_IMAGE_WIDTH);
+    let height = grid.rows.len() as u32 * ROW_HEIGHT;
+    let mut canvas = Canvas::new(width, height);
+
+    for (row_index, row) in grid.rows.iter().enumerate() {
+        let top = (row_index as u32 * ROW_HEIGHT) as i32;
+        if let Some(background) = row_background(row.style, row_index) {
+            fill(&mut canvas, Point::new(0, top), Size::new(table_width, ROW_HEIGHT), background);
+        }
+        if row.style == RowStyle::Note {
+            for (text, _) in row.cells.iter() {
+                draw_text(&mut canvas, text, Point::new(4, top + ROW_HEIGHT as i32 / 2), Rgb888::new(90, 90, 90), false, Alignment::Left);
+            }
+            continue;
+        }
+        let mut left = 0;
+        for (index, (text, tone)) in row.cells.iter().enumerate() {
+            let center = Point::new((left + column_widths[index] / 2) as i32, top + ROW_HEIGHT as i32 / 2);
+            draw_text(&mut canvas, text, center, text_color(row.style, *tone), row.style != RowStyle::Round, Alignment::Center);
+            left += column_widths[index];
+        }
+        // cell borders
+        fill(&mut canvas, Point::new(0, top + ROW_HEIGHT as i32 - 1), Size::new(table_width, 1), Rgb888::new(190, 190, 190));
+    }
+    let mut left = 0;
+    let table_height = grid.rows.iter().filter(|r| r.style != RowStyle::Note).count() as u32 * ROW_HEIGHT;
+    for column_width in column_widths.iter() {
+        fill(&mut canvas, Point::new(left as i32, 0), Size::new(1, table_height), Rgb888::new(190, 190, 190));
+        left += column_width;
+    }
+    fill(&mut canvas, Point::new(left as i32 - 1, 0), Size::new(1, table_height), Rgb888::new(190, 190, 190));
+
+    encode_png(&canvas)
+}
+
+fn row_background(style: RowStyle, row_index: usize) -> Option<Rgb888> {
+    match style {
+        RowStyle::Header | RowStyle::Total => Some(Rgb888::new(240, 241, 253)),
+        RowStyle::Radlc => Some(Rgb888::new(253, 245, 235)),
+        RowStyle::Round if row_index.is_multiple_of(2) => Some(Rgb888::new(229, 228, 228)),
+        _ => None,
+    }
+}
+
+fn text_color(style: RowStyle, tone: CellTone) -> Rgb888 {
+    match (style, tone) {
+        (_, CellTone::Best) => Rgb888::new(0, 128, 0),
+        (_, CellTone::Worst) => Rgb888::new(255, 0, 0),
+        (RowStyle::Header, _) => Rgb888::new(90, 98, 220),
+        (RowStyle::Radlc, _) => Rgb888::new(160, 90, 0),
+        _ => Rgb888::new(0, 0, 0),
+    }
+}
+
+fn fill(canvas: &mut Canvas, top_left: Point, size: Size, color: Rgb888) {
+    let _ = Rectangle::new(top_left, size)
+        .into_styled(PrimitiveStyle::with_fill(color))
+        .draw(canvas);
+}
+
+pub fn draw_text(canvas: &mut Canvas, text: &str, position: Point, color: Rgb888, bold: bool, alignment: Alignment) {
+    let font = if bold { &FONT_8X13_BOLD } else { &FONT_8X13 };
+    let text_style = TextStyleBuilder::new()
+        .alignment(alignment)
+        .baseline(Baseline::Middle)
+        .build();
+    let _ = Text::with_text_style(text, position, MonoTextStyle::new(font, color), text_style).draw(canvas);
+}
+
+pub fn encode_png(canvas: &Canvas) -> Result<Vec<u8>, Error> {
+    let mut out = vec![];
+    {
+        let mut encoder = png::Encoder::new(&mut out, canvas.width * SCALE, canvas.height * SCALE);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = match encoder.write_header() {
+            Ok(writer) => writer,
+            Err(e) => return Err(Error::other(format!("Error encoding image: {}", e))),
+        };
+        if let Err(e) = writer.write_image_data(&canvas.pixels) {
+            return Err(Error::other(format!("Error encoding image: {}", e)));
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+
+use crate::models::user::User;
+
+// final state of a game, shared by everything that looks at finished games
+pub struct ScoreSheet {
+    pub game_id: String,
+    pub game_type: String,
+    pub players: Vec<User>,
+    pub score: HashMap<String, Vec<Option<i32>>>,
+    pub rounds: i32,
+    pub totals: HashMap<String, i32>,
+}
+
+impl ScoreSheet {
+    // competition ranking: equal totals share a placement (1, 2, 2, 4)
+    pub fn placements(&self) -> HashMap<String, i32> {
+        let mut ordered: Vec<(&String, &i32)> = self.totals.iter().collect();
+        ordered.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        let mut placements = HashMap::new();
+        for (index, (player_id, total)) in ordered.iter().enumerate() {
+            let placement = match ordered.iter().position(|(_, t)| t == total) {
+                Some(first) => first as i32 + 1,
+                None => index as i32 + 1,
+            };
+            placements.insert(player_id.to_string(), placement);
+        }
+        placements
+    }
+}
\ No newline at end of file
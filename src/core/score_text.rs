@@ -0,0 +1,149 @@
+use super::{score_image::{ScoreGrid, GridRow, RowStyle}, html_template::escape_html};
+
+// what fits a phone screen in telegram's monospace font without wrapping
+const LINE_WIDTH: usize = 34;
+const MIN_CELL_WIDTH: usize = 3;
+const MAX_CELL_WIDTH: usize = 8;
+// only the latest rounds are listed, the earlier ones are still counted in the totals
+const MAX_ROUNDS: usize = 10;
+
+// Renders the grid as a monospace table for a telegram message with html parse mode.
+// Notes are placed under the table as plain text so they can wrap.
+pub fn render_text(grid: &ScoreGrid) -> String {
+    let table_rows: Vec<&GridRow> = grid.rows.iter().filter(|r| r.style != RowStyle::Note).collect();
+    let columns = table_rows.iter().map(|r| r.cells.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return "No scores yet".to_string();
+    }
+    let limit = ((LINE_WIDTH + 1) / columns).saturating_sub(1).clamp(MIN_CELL_WIDTH, MAX_CELL_WIDTH);
+    let mut column_widths = vec![1; columns];
+    for row in table_rows.iter() {
+        for (index, (text, _)) in row.cells.iter().enumerate() {
+            column_widths[index] = column_widths[index].max(text.chars().count().min(limit));
+        }
+    }
+
+    let rounds = table_rows.iter().filter(|r| r.style == RowStyle::Round).count();
+    let hidden = rounds.saturating_sub(MAX_ROUNDS);
+    let separator = "-".repeat(column_widths.iter().sum::<usize>() + columns - 1);
+    let mut lines = vec![];
+    let mut round = 0;
+    for row in table_rows.iter() {
+        match row.style {
+            RowStyle::Round => {
+                round += 1;
+                if round == 1 && hidden > 0 {
+                    lines.push(format!("({} earlier rounds)", hidden));
+                }
+                if round <= hidden {
+                    continue;
+                }
+            },
+            RowStyle::Total => lines.push(separator.clone()),
+            _ => (),
+        }
+        lines.push(format_row(row, &column_widths));
+        if row.style == RowStyle::Header {
+            lines.push(separator.clone());
+        }
+    }
+
+    let mut text = format!("<pre>{}</pre>", escape_html(&lines.join("\n")));
+    for row in grid.rows.iter().filter(|r| r.style == RowStyle::Note) {
+        for (note, _) in row.cells.iter() {
+            text.push('\n');
+            text.push_str(&escape_html(note));
+        }
+    }
+    text
+}
+
+fn format_row(row: &GridRow, column_widths: &[usize]) -> String {
+    column_widths
+        .iter()
+        .enumerate()
+        .map(|(index, width)| {
+            let text = match row.cells.get(index) {
+                Some((text, _)) => fit_cell(text, *width),
+                None => "".to_string(),
+            };
+            format!("{:>width$}", text, width = width)
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+        .trim_end()
+        .to_string()
+}
+
+// squeezes a cell into the column: drops the spaces and brackets first, then the markers
+// after the score, and only cuts the text when nothing else helps (e.g. long player names)
+fn fit_cell(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let compact: String = text.split_whitespace().collect();
+    if compact.chars().count() <= width {
+        return compact;
+    }
+    let compact = compact.replace(['(', ')'], "");
+    if compact.chars().count() <= width {
+        return compact;
+    }
+    if let Some(first) = text.split_whitespace().next() {
+        if first.chars().count() <= width && first.parse::<i32>().is_ok() {
+            return first.to_string();
+        }
+    }
+    text.chars().take(width).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::core::score_image::{GridRow, RowStyle, ScoreGrid};
+
+    use super::{fit_cell, render_text, MAX_ROUNDS};
+
+    fn row(style: RowStyle, cells: &[&str]) -> GridRow {
+        GridRow::new(style, cells.iter().map(|c| c.to_string()).collect())
+    }
+
+    #[test]
+    fn cells_are_squeezed_before_they_are_cut() {
+        assert_eq!(fit_cell("15", 4), "15");
+        assert_eq!(fit_cell("15 (M)", 5), "15(M)");
+        assert_eq!(fit_cell("15 (M)", 4), "15M");
+        assert_eq!(fit_cell("-120 (M T)", 4), "-120");
+        assert_eq!(fit_cell("ŠTEFANIJA", 5), "ŠTEFA");
+        assert_eq!(fit_cell("", 3), "");
+    }
+
+    #[test]
+    fn earlier_rounds_are_collapsed_but_totals_and_radlci_stay() {
+        let mut rows = vec![row(RowStyle::Header, &["ANA", "BOR"]), row(RowStyle::Radlc, &["O", "X"])];
+        for round in 1..=MAX_ROUNDS + 3 {
+            rows.push(row(RowStyle::Round, &[&format!("{}", round), &format!("-{}", round)]));
+        }
+        rows.push(row(RowStyle::Total, &["91", "-91"]));
+        rows.push(row(RowStyle::Note, &["Radlci: O unused, X used"]));
+        let text = render_text(&ScoreGrid { rows });
+
+        let table = text.trim_start_matches("<pre>").split("</pre>").next().unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "ANA BOR");
+        assert_eq!(lines[2], "  O   X");
+        assert_eq!(lines[3], "(3 earlier rounds)");
+        assert_eq!(lines[4], "  4  -4");
+        // header, radlci, the hidden rounds line and the total with their separators around the shown rounds
+        assert_eq!(lines.len(), MAX_ROUNDS + 6);
+        assert!(!lines.contains(&"  3  -3"));
+        assert_eq!(lines[lines.len() - 1], " 91 -91");
+        assert_eq!(lines[lines.len() - 2], "-------");
+        assert!(text.ends_with("</pre>\nRadlci: O unused, X used"));
+    }
+
+    #[test]
+    fn empty_grid_has_no_table() {
+        assert_eq!(render_text(&ScoreGrid { rows: vec![] }), "No scores yet");
+    }
+}
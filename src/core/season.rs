@@ -0,0 +1,277 @@
+use std::{collections::HashMap, io::Error};
+
+use chrono::Utc;
+
+use crate::{models::{season::Season, game::GameResult, user::User}, games::table::html_helper::build_score_table_html};
+
+use super::html_template::chat_theme;
+use super::database::{season_operations::{insert_season, get_active_season, end_season}, game_operations::{get_games_by_season, get_results_by_games}, user_operations::get_users_by_chat};
+
+pub enum SeasonScoring {
+    Sum,
+    Placement(Vec<i32>),    // points by placement, e.g. 3,2,1,0
+    BestN(usize),           // sum of the best N game totals
+}
+
+pub struct SeasonStandings {
+    pub players: Vec<User>,
+    pub per_game: HashMap<String, Vec<Option<i32>>>,
+    pub games: i32,
+    pub totals: HashMap<String, i32>,
+}
+
+impl SeasonScoring {
+    pub fn code(&self) -> String {
+        match self {
+            SeasonScoring::Sum => "sum".to_string(),
+            SeasonScoring::Placement(points) => format!(
+                "placement:{}",
+                points.iter().map(|p| p.to_string()).collect::<Vec<String>>().join(",")
+            ),
+            SeasonScoring::BestN(count) => format!("best:{}", count),
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.split_once(':') {
+            None if code == "sum" => Some(SeasonScoring::Sum),
+            Some(("placement", points)) => parse_placement_points(points).map(SeasonScoring::Placement),
+            Some(("best", count)) => match count.parse() {
+                Ok(count) if count > 0 => Some(SeasonScoring::BestN(count)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn from_args(args: &[&str]) -> Result<Self, Error> {
+        let scoring = match args {
+            [] => Some(SeasonScoring::Sum),
+            ["sum"] => Some(SeasonScoring::Sum),
+            ["placement", points] => parse_placement_points(points).map(SeasonScoring::Placement),
+            ["best", count] => SeasonScoring::from_code(&format!("best:{}", count)),
+            _ => None,
+        };
+        match scoring {
+            Some(scoring) => Ok(scoring),
+            None => Err(Error::other(
+                "Season scoring is one of: sum | placement 3,2,1,0 | best N".to_string())
+            ),
+        }
+    }
+}
+
+// /season start [name] [sum|placement 3,2,1,0|best N], /season end, /season status
+// Returns the reply and, when the season ends, the html report.
+pub fn season_command(chat_id: String, text: &str) -> Result<(String, Option<String>), Error> {
+    let args: Vec<String> = text.split_whitespace().skip(1).map(|a| a.to_lowercase()).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    let active = match get_active_season(chat_id.clone()) {
+        Ok(season) => season,
+        Err(e) => return Err(Error::other(format!("Error fetching season from DB: {}", e))),
+    };
+    match (args.first(), active) {
+        (Some(&"start"), Some(season)) => Err(Error::other(
+            format!("Season {} is still running. End it first with /season end", season.name))
+        ),
+        (Some(&"start"), None) => {
+            let (name, scoring_args) = match args.get(1) {
+                Some(name) if !["sum", "placement", "best"].contains(name) => (name.to_uppercase(), &args[2..]),
+                _ => (format!("{}", Utc::now().format("%Y-%m")), &args[1..]),
+            };
+            let scoring = SeasonScoring::from_args(scoring_args)?;
+            match insert_season(Season::from(chat_id, name, scoring.code())) {
+                Ok(season) => Ok((format!("Season {} started! Scoring: {}", season.name, season.scoring), None)),
+                Err(e) => Err(Error::other(format!("Error saving season to DB: {}", e))),
+            }
+        },
+        (Some(&"end"), Some(season)) => {
+            let theme = chat_theme(chat_id.clone());
+            let standings = season_standings(chat_id, &season)?;
+            if let Err(e) = end_season(season.id.clone(), Utc::now().to_rfc3339()) {
+                return Err(Error::other(format!("Error ending season in DB: {}", e)));
+            }
+            let html = build_score_table_html(
+                &format!("Season {}", season.name),
+                &standings.players,
+                &standings.per_game,
+                standings.games,
+                standings.totals.clone(),
+                &theme,
+            );
+            Ok((format!("Season ended!\n{}", describe_standings(&season, &standings)), Some(html)))
+        },
+        (Some(&"status"), Some(season)) | (None, Some(season)) => {
+            let standings = season_standings(chat_id, &season)?;
+            Ok((describe_standings(&season, &standings), None))
+        },
+        (Some(&"end"), None) | (Some(&"status"), None) | (None, None) => Ok((
+            "No season running. Start one with /season start [name] [sum|placement 3,2,1,0|best N]".to_string(),
+            None
+        )),
+        _ => Err(Error::other("Use /season start|end|status".to_string())),
+    }
+}
+
+pub fn season_standings(chat_id: String, season: &Season) -> Result<SeasonStandings, Error> {
+    let scoring = match SeasonScoring::from_code(&season.scoring) {
+        Some(scoring) => scoring,
+        None => return Err(Error::other(format!("Unknown season scoring: {}", season.scoring))),
+    };
+    let games = match get_games_by_season(season.id.clone()) {
+        Ok(games) => games,
+        Err(e) => return Err(Error::other(format!("Error fetching games from DB: {}", e))),
+    };
+    let results = match get_results_by_games(games.iter().map(|g| g.id.clone()).collect()) {
+        Ok(results) => results,
+        Err(e) => return Err(Error::other(format!("Error fetching game results from DB: {}", e))),
+    };
+    let users = match get_users_by_chat(chat_id) {
+        Ok(users) => users,
+        Err(e) => return Err(Error::other(format!("Error fetching users from DB: {}", e))),
+    };
+
+    // one row per game, a player's cell is what the game is worth to the standings
+    let mut per_game: HashMap<String, Vec<Option<i32>>> = HashMap::new();
+    for (index, game) in games.iter().enumerate() {
+        for result in results.iter().filter(|r| r.game_id == game.id) {
+            let row = per_game.entry(result.player_id.clone()).or_default();
+            row.resize(index, None);
+            row.push(Some(game_worth(&scoring, result)));
+        }
+    }
+    let mut totals = HashMap::new();
+    for (player_id, row) in per_game.iter_mut() {
+        row.resize(games.len(), None);
+        let mut worth: Vec<i32> = row.iter().filter_map(|x| *x).collect();
+        let total: i32 = match scoring {
+            SeasonScoring::BestN(count) => {
+                worth.sort_by(|a, b| b.cmp(a));
+                worth.iter().take(count).sum()
+            },
+            _ => worth.iter().sum(),
+        };
+        totals.insert(player_id.clone(), total);
+    }
+    let mut players: Vec<User> = users
+        .into_iter()
+        .filter(|u| per_game.contains_key(&u.id))
+        .collect();
+    players.sort_by(|a, b| totals[&b.id].cmp(&totals[&a.id]).then(a.name.cmp(&b.name)));
+    Ok(SeasonStandings {
+        players,
+        per_game,
+        games: games.len() as i32,
+        totals,
+    })
+}
+
+fn game_worth(scoring: &SeasonScoring, result: &GameResult) -> i32 {
+    match scoring {
+        SeasonScoring::Placement(points) => match points.get((result.placement - 1) as usize) {
+            Some(points) => *points,
+            None => 0,
+        },
+        _ => result.total,
+    }
+}
+
+fn describe_standings(season: &Season, standings: &SeasonStandings) -> String {
+    let mut lines = vec![format!(
+        "Season {} ({}), started {}, {} games",
+        season.name,
+        season.scoring,
+        season.started_at.split('T').next().unwrap_or(""),
+        standings.games,
+    )];
+    for (index, player) in standings.players.iter().enumerate() {
+        let played = match standings.per_game.get(&player.id) {
+            Some(row) => row.iter().filter(|x| x.is_some()).count(),
+            None => 0,
+        };
+        lines.push(format!(
+            "{}. {}: {} ({} games)",
+            index + 1,
+            player.name,
+            standings.totals.get(&player.id).unwrap_or(&0),
+            played,
+        ));
+    }
+    lines.join("\n")
+}
+
+fn parse_placement_points(points: &str) -> Option<Vec<i32>> {
+    let parsed: Result<Vec<i32>, _> = points.split(',').map(|p| p.parse()).collect();
+    match parsed {
+        Ok(points) if !points.is_empty() => Some(points),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::database::{game_operations::insert_game, test_database::{setup_database, register}},
+        models::{game::{ArchivedGame, GameResult}, season::Season, user::User},
+    };
+
+    use super::season_standings;
+
+    const CHAT_ID: i64 = -1037;
+
+    // three games: CENE misses the last one
+    fn play_season() -> (Season, User, User, User) {
+        setup_database();
+        let (ana, bor, cene) = (register(CHAT_ID, "SEAANA"), register(CHAT_ID, "SEABOR"), register(CHAT_ID, "SEACENE"));
+        let season = Season::from(CHAT_ID.to_string(), "TEST".to_string(), "sum".to_string());
+        let games = [
+            vec![(&ana, 50, 1), (&bor, 20, 2), (&cene, -10, 3)],
+            vec![(&ana, -30, 3), (&bor, 40, 1), (&cene, 10, 2)],
+            vec![(&ana, 5, 2), (&bor, 60, 1)],
+        ];
+        for (index, results) in games.iter().enumerate() {
+            let game_id = format!("{}-{}", season.id, index);
+            let game = ArchivedGame::from(
+                game_id.clone(),
+                CHAT_ID.to_string(),
+                "tarok".to_string(),
+                format!("2026-01-0{}T00:00:00+00:00", index + 1),
+                Some(season.id.clone()),
+                None,
+            );
+            let results = results
+                .iter()
+                .map(|(player, total, placement)| GameResult::from(game_id.clone(), player.id.clone(), *total, *placement))
+                .collect();
+            insert_game(game, results).expect("Error archiving game");
+        }
+        (season, ana, bor, cene)
+    }
+
+    fn totals(season: &Season, scoring: &str, players: &[&User]) -> Vec<i32> {
+        let season = Season { scoring: scoring.to_string(), ..season.clone() };
+        let standings = season_standings(CHAT_ID.to_string(), &season).expect("Error building standings");
+        assert_eq!(standings.games, 3);
+        players.iter().map(|p| standings.totals[&p.id]).collect()
+    }
+
+    #[test]
+    fn seasons_are_scored_by_sum_placement_and_best_games() {
+        let (season, ana, bor, cene) = play_season();
+        let players = [&ana, &bor, &cene];
+        assert_eq!(totals(&season, "sum", &players), vec![25, 120, 0]);
+        assert_eq!(totals(&season, "placement:3,2,1", &players), vec![6, 8, 3]);
+        // placements without points are worth nothing
+        assert_eq!(totals(&season, "placement:1", &players), vec![1, 2, 0]);
+        assert_eq!(totals(&season, "best:1", &players), vec![50, 60, 10]);
+        assert_eq!(totals(&season, "best:2", &players), vec![55, 100, 0]);
+        // fewer games than N count all of them
+        assert_eq!(totals(&season, "best:3", &players), vec![25, 120, 0]);
+        assert_eq!(totals(&season, "best:5", &players), vec![25, 120, 0]);
+
+        let standings = season_standings(CHAT_ID.to_string(), &season).expect("Error building standings");
+        assert_eq!(standings.players.iter().map(|p| p.name.as_str()).collect::<Vec<&str>>(), vec!["SEABOR", "SEAANA", "SEACENE"]);
+        assert_eq!(standings.per_game[&cene.id], vec![Some(-10), Some(10), None]);
+        assert!(season_standings(CHAT_ID.to_string(), &Season { scoring: "best:0".to_string(), ..season.clone() }).is_err());
+    }
+}
@@ -0,0 +1,416 @@
+use std::{collections::HashMap, io::Error};
+
+use crate::models::{settlement::{SettlementConfig, LedgerEntry}, user::User};
+
+use super::{score_sheet::ScoreSheet, database::{settlement_operations::{get_settlement_config, save_settlement_config, insert_ledger_entries, get_ledger_by_chat}, user_operations::get_users_by_chat}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettlementMode {
+    Off,
+    ZeroSum,    // everyone settles against the average score
+    Winner,     // everyone pays the winner the difference to the winning score
+}
+
+impl SettlementMode {
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.to_lowercase().as_str() {
+            "off" => Some(SettlementMode::Off),
+            "zerosum" => Some(SettlementMode::ZeroSum),
+            "winner" => Some(SettlementMode::Winner),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            SettlementMode::Off => "off",
+            SettlementMode::ZeroSum => "zerosum",
+            SettlementMode::Winner => "winner",
+        }
+    }
+}
+
+// /settle [mode off|zerosum|winner] [rate <cents per point>]
+pub fn settlement_command(chat_id: String, text: &str) -> Result<String, Error> {
+    let mut config = load_config(chat_id)?;
+    let args: Vec<&str> = text.split_whitespace().skip(1).collect();
+    if args.is_empty() {
+        return Ok(describe_config(&config));
+    }
+    for pair in args.chunks(2) {
+        let value = match pair.get(1) {
+            Some(value) => *value,
+            None => return Err(Error::other(format!("Missing value for {}", pair[0]))),
+        };
+        match pair[0].to_lowercase().as_str() {
+            "mode" => match SettlementMode::from_code(value) {
+                Some(mode) => config.mode = mode.code().to_string(),
+                None => return Err(Error::other(format!("Unknown settlement mode: {}", value))),
+            },
+            "rate" => match value.parse::<i32>() {
+                Ok(rate) if rate >= 0 => config.rate = rate,
+                _ => return Err(Error::other(format!("Expected cents per point, got: {}", value))),
+            },
+            _ => return Err(Error::other(
+                "Use /settle [mode off|zerosum|winner] [rate <cents per point>]".to_string())
+            ),
+        };
+    }
+    match save_settlement_config(config) {
+        Ok(config) => Ok(format!("Settlement updated!\n{}", describe_config(&config))),
+        Err(e) => Err(Error::other(format!("Error saving settlement to DB: {}", e))),
+    }
+}
+
+// Settles a finished game: records the amounts in the ledger and returns the transfers.
+// Returns None when the chat doesn't play for money.
+pub fn settle_game(chat_id: String, sheet: &ScoreSheet) -> Result<Option<String>, Error> {
+    let config = load_config(chat_id.clone())?;
+    let mode = match SettlementMode::from_code(&config.mode) {
+        Some(mode) => mode,
+        None => SettlementMode::Off,
+    };
+    if mode == SettlementMode::Off || config.rate == 0 || sheet.players.len() < 2 {
+        return Ok(None);
+    }
+    let amounts = game_amounts(sheet, mode, config.rate);
+    let entries = amounts
+        .iter()
+        .map(|(player_id, amount)| LedgerEntry::from(chat_id.clone(), sheet.game_id.clone(), player_id.clone(), *amount))
+        .collect();
+    if let Err(e) = insert_ledger_entries(entries) {
+        return Err(Error::other(format!("Error saving ledger to DB: {}", e)));
+    }
+    let mut lines = vec![format!("Settlement ({}, {} per point):", mode.code(), format_cents(config.rate))];
+    lines.append(&mut describe_transfers(&sheet.players, minimal_transfers(&amounts)));
+    Ok(Some(lines.join("\n")))
+}
+
+pub fn balance_report(chat_id: String) -> Result<String, Error> {
+    let ledger = match get_ledger_by_chat(chat_id.clone()) {
+        Ok(ledger) => ledger,
+        Err(e) => return Err(Error::other(format!("Error fetching ledger from DB: {}", e))),
+    };
+    if ledger.is_empty() {
+        return Ok("The ledger is empty!".to_string());
+    }
+    let users = match get_users_by_chat(chat_id) {
+        Ok(users) => users,
+        Err(e) => return Err(Error::other(format!("Error fetching users from DB: {}", e))),
+    };
+    let mut balances: HashMap<String, i32> = HashMap::new();
+    for entry in ledger.iter() {
+        *balances.entry(entry.player_id.clone()).or_insert(0) += entry.amount;
+    }
+    let mut balances: Vec<(String, i32)> = balances.into_iter().collect();
+    balances.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut lines = vec!["Balance:".to_string()];
+    for (player_id, amount) in balances.iter() {
+        lines.push(format!("{}: {}", player_name(&users, player_id), format_cents(*amount)));
+    }
+    lines.push("To settle up:".to_string());
+    lines.append(&mut describe_transfers(&users, minimal_transfers(&balances)));
+    Ok(lines.join("\n"))
+}
+
+fn load_config(chat_id: String) -> Result<SettlementConfig, Error> {
+    match get_settlement_config(chat_id.clone()) {
+        Ok(Some(config)) => Ok(config),
+        Ok(None) => Ok(SettlementConfig::from(chat_id)),
+        Err(e) => Err(Error::other(format!("Error fetching settlement from DB: {}", e))),
+    }
+}
+
+fn describe_config(config: &SettlementConfig) -> String {
+    format!("mode {} (off|zerosum|winner)\nrate {} cents per point", config.mode, config.rate)
+}
+
+// amount in cents per player, positive when the player receives money. Amounts always sum to 0.
+fn game_amounts(sheet: &ScoreSheet, mode: SettlementMode, rate: i32) -> Vec<(String, i32)> {
+    let mut totals: Vec<(String, i32)> = sheet.players
+        .iter()
+        .map(|p| (p.id.clone(), *sheet.totals.get(&p.id).unwrap_or(&0)))
+        .collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let count = totals.len() as i32;
+    // exact amounts as fractions with a common denominator, rounded to cents by split_cents
+    let (shares, denominator): (Vec<(String, i32)>, i32) = match mode {
+        SettlementMode::Off => return vec![],
+        SettlementMode::ZeroSum => {
+            let sum: i32 = totals.iter().map(|(_, t)| t).sum();
+            let shares = totals
+                .iter()
+                .map(|(id, total)| (id.clone(), (total * count - sum) * rate))
+                .collect();
+            (shares, count)
+        },
+        SettlementMode::Winner => {
+            let best = totals[0].1;
+            let winners = totals.iter().filter(|(_, t)| *t == best).count() as i32;
+            let pot: i32 = totals.iter().map(|(_, t)| (best - t) * rate).sum();
+            let shares = totals
+                .iter()
+                .map(|(id, total)| match *total == best {
+                    true => (id.clone(), pot),
+                    false => (id.clone(), (total - best) * rate * winners),
+                })
+                .collect();
+            (shares, winners)
+        },
+    };
+    // the leftover cents rotate with the game
+    let rotation = sheet.game_id.bytes().map(|b| b as usize).sum::<usize>();
+    split_cents(shares, denominator, rotation)
+}
+
+// Rounds every share down to whole cents and hands the cents left over to the players with the
+// largest remainders. Shares of a game mostly have the same remainder, so ties go round the
+// players (ordered by id) starting at rotation instead of always to the same one.
+fn split_cents(shares: Vec<(String, i32)>, denominator: i32, rotation: usize) -> Vec<(String, i32)> {
+    let mut amounts: Vec<(String, i32, i32)> = shares
+        .into_iter()
+        .map(|(id, share)| (id, share.div_euclid(denominator), share.rem_euclid(denominator)))
+        .collect();
+    amounts.sort_by(|a, b| a.0.cmp(&b.0));
+    let count = amounts.len().max(1);
+    let leftover = -amounts.iter().map(|(_, amount, _)| amount).sum::<i32>();
+    let mut by_remainder: Vec<usize> = (0..amounts.len()).collect();
+    by_remainder.sort_by_key(|i| (-amounts[*i].2, (i + count - rotation % count) % count));
+    for index in by_remainder.into_iter().take(leftover.max(0) as usize) {
+        amounts[index].1 += 1;
+    }
+    amounts.into_iter().map(|(id, amount, _)| (id, amount)).collect()
+}
+
+// partitioning is exhaustive over subsets, bigger ledgers are settled as one group
+const MAX_EXACT_PLAYERS: usize = 16;
+
+// Fewest transfers: a group of k players whose amounts sum to 0 settles with k - 1 transfers,
+// so the players are split into as many such groups as possible and each group is settled
+// on its own.
+fn minimal_transfers(amounts: &[(String, i32)]) -> Vec<(String, String, i32)> {
+    let mut open: Vec<(String, i32)> = amounts.iter().filter(|(_, a)| *a != 0).cloned().collect();
+    open.sort_by(|a, b| a.0.cmp(&b.0));
+    let groups = match open.len() <= MAX_EXACT_PLAYERS {
+        true => zero_sum_groups(&open),
+        false => vec![open],
+    };
+    groups.iter().flat_map(|group| settle_greedily(group)).collect()
+}
+
+// Largest number of groups summing to 0. best[mask] is the most groups that can be closed while
+// adding the players of mask one by one, a group closes whenever the added players sum to 0.
+fn zero_sum_groups(amounts: &[(String, i32)]) -> Vec<Vec<(String, i32)>> {
+    let count = amounts.len();
+    let full = (1usize << count) - 1;
+    let mut sums = vec![0; full + 1];
+    let mut best = vec![0; full + 1];
+    for mask in 1..=full {
+        let lowest = mask.trailing_zeros() as usize;
+        sums[mask] = sums[mask & (mask - 1)] + amounts[lowest].1;
+        let most = (0..count)
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| best[mask ^ (1 << i)])
+            .max()
+            .unwrap_or(0);
+        best[mask] = most + (sums[mask] == 0) as i32;
+    }
+    // walk back from all players, the order they were added in splits into the groups
+    let mut order = vec![];
+    let mut mask = full;
+    while mask != 0 {
+        let last = match (0..count)
+            .filter(|i| mask & (1 << i) != 0)
+            .max_by_key(|i| (best[mask ^ (1 << i)], std::cmp::Reverse(*i)))
+        {
+            Some(last) => last,
+            None => break,
+        };
+        order.push(last);
+        mask ^= 1 << last;
+    }
+    order.reverse();
+    let mut groups = vec![];
+    let mut group = vec![];
+    let mut sum = 0;
+    for index in order.into_iter() {
+        group.push(amounts[index].clone());
+        sum += amounts[index].1;
+        if sum == 0 {
+            groups.push(std::mem::take(&mut group));
+        }
+    }
+    if !group.is_empty() {
+        groups.push(group);
+    }
+    groups
+}
+
+// The biggest debtor pays the biggest creditor until everyone is even. Every step settles
+// at least one player, so a group of k players needs at most k - 1 transfers.
+fn settle_greedily(amounts: &[(String, i32)]) -> Vec<(String, String, i32)> {
+    let mut debtors: Vec<(String, i32)> = amounts.iter().filter(|(_, a)| *a < 0).map(|(id, a)| (id.clone(), -a)).collect();
+    let mut creditors: Vec<(String, i32)> = amounts.iter().filter(|(_, a)| *a > 0).cloned().collect();
+    let mut transfers = vec![];
+    loop {
+        debtors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        creditors.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        if debtors.is_empty() || creditors.is_empty() {
+            break;
+        }
+        let amount = debtors[0].1.min(creditors[0].1);
+        transfers.push((debtors[0].0.clone(), creditors[0].0.clone(), amount));
+        debtors[0].1 -= amount;
+        creditors[0].1 -= amount;
+        debtors.retain(|(_, a)| *a > 0);
+        creditors.retain(|(_, a)| *a > 0);
+    }
+    transfers
+}
+
+fn describe_transfers(players: &[User], transfers: Vec<(String, String, i32)>) -> Vec<String> {
+    if transfers.is_empty() {
+        return vec!["Nobody owes anything".to_string()];
+    }
+    transfers
+        .iter()
+        .map(|(from, to, amount)| format!(
+            "{} pays {} {}",
+            player_name(players, from),
+            player_name(players, to),
+            format_cents(*amount)
+        ))
+        .collect()
+}
+
+fn player_name(players: &[User], id: &String) -> String {
+    match players.iter().find(|p| &p.id == id) {
+        Some(player) => player.name.clone(),
+        None => id.clone(),
+    }
+}
+
+fn format_cents(cents: i32) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, cents.abs() / 100, cents.abs() % 100)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{core::{score_sheet::ScoreSheet, database::test_database::user}, models::user::User};
+
+    use super::{game_amounts, minimal_transfers, SettlementMode};
+
+    const CHAT_ID: i64 = -1036;
+
+    fn sheet_of(totals: &[i32]) -> ScoreSheet {
+        let players: Vec<User> = totals
+            .iter()
+            .enumerate()
+            .map(|(index, _)| user(CHAT_ID, &format!("P{}", index)))
+            .collect();
+        ScoreSheet {
+            game_id: "game".to_string(),
+            game_type: "tarok".to_string(),
+            totals: players.iter().zip(totals.iter()).map(|(p, t)| (p.id.clone(), *t)).collect(),
+            players,
+            score: HashMap::new(),
+            rounds: 1,
+        }
+    }
+
+    // amounts in the order of the players on the sheet
+    fn amounts(sheet: &ScoreSheet, mode: SettlementMode, rate: i32) -> Vec<i32> {
+        let amounts: HashMap<String, i32> = game_amounts(sheet, mode, rate).into_iter().collect();
+        assert_eq!(amounts.values().sum::<i32>(), 0);
+        sheet.players.iter().map(|p| amounts[&p.id]).collect()
+    }
+
+    #[test]
+    fn zerosum_settles_against_the_average() {
+        let sheet = sheet_of(&[100, 0, -40]);
+        assert_eq!(amounts(&sheet, SettlementMode::ZeroSum, 1), vec![80, -20, -60]);
+        assert_eq!(amounts(&sheet, SettlementMode::ZeroSum, 5), vec![400, -100, -300]);
+    }
+
+    #[test]
+    fn winner_is_paid_the_difference() {
+        let sheet = sheet_of(&[30, 10, 0]);
+        assert_eq!(amounts(&sheet, SettlementMode::Winner, 2), vec![100, -40, -60]);
+        // tied winners split the pot
+        let sheet = sheet_of(&[30, 30, 0]);
+        assert_eq!(amounts(&sheet, SettlementMode::Winner, 2), vec![30, 30, -60]);
+    }
+
+    #[test]
+    fn off_settles_nothing() {
+        assert!(game_amounts(&sheet_of(&[10, -10]), SettlementMode::Off, 1).is_empty());
+    }
+
+    #[test]
+    fn rounding_stays_within_a_cent() {
+        // exact amounts are 1.33, -0.67, -0.67
+        let sheet = sheet_of(&[2, 0, 0]);
+        let rounded = amounts(&sheet, SettlementMode::ZeroSum, 1);
+        assert!(rounded[0] == 1 || rounded[0] == 2, "{:?}", rounded);
+        assert!(rounded[1..].iter().all(|a| *a == -1 || *a == 0), "{:?}", rounded);
+        assert_eq!(rounded, amounts(&sheet, SettlementMode::ZeroSum, 1));
+
+        // exact amounts are 2.5, 2.5, -5
+        let sheet = sheet_of(&[5, 5, 0]);
+        let mut rounded = amounts(&sheet, SettlementMode::Winner, 1);
+        assert_eq!(rounded[2], -5);
+        rounded.sort();
+        assert_eq!(rounded, vec![-5, 2, 3]);
+    }
+
+    #[test]
+    fn leftover_cents_rotate_between_games() {
+        // exact amounts are 0.67, -0.33, -0.33: one player keeps the rounded down amount
+        let mut sheet = sheet_of(&[1, 0, 0]);
+        let mut rounded_down = vec![];
+        for game_id in ["a", "b", "c"] {
+            sheet.game_id = game_id.to_string();
+            let rounded = amounts(&sheet, SettlementMode::ZeroSum, 1);
+            let floors = [0, -1, -1];
+            let down: Vec<usize> = (0..3).filter(|i| rounded[*i] == floors[*i]).collect();
+            assert_eq!(down.len(), 1, "{:?}", rounded);
+            rounded_down.push(down[0]);
+        }
+        rounded_down.sort();
+        assert_eq!(rounded_down, vec![0, 1, 2]);
+    }
+
+    fn settle(amounts: &[i32]) -> Vec<(String, String, i32)> {
+        let named: Vec<(String, i32)> = amounts.iter().enumerate().map(|(i, a)| (format!("P{}", i), *a)).collect();
+        let transfers = minimal_transfers(&named);
+        let mut balances: HashMap<String, i32> = named.into_iter().collect();
+        for (from, to, amount) in transfers.iter() {
+            assert!(*amount > 0);
+            *balances.get_mut(from).unwrap() += amount;
+            *balances.get_mut(to).unwrap() -= amount;
+        }
+        assert!(balances.values().all(|b| *b == 0), "{:?}", balances);
+        transfers
+    }
+
+    #[test]
+    fn transfers_settle_everyone_with_the_fewest_payments() {
+        assert!(settle(&[0, 0]).is_empty());
+        assert_eq!(settle(&[10, -10]), vec![("P1".to_string(), "P0".to_string(), 10)]);
+        assert_eq!(settle(&[30, -10, -20]).len(), 2);
+        // paying the biggest debts first takes 4 payments, -8 and 8 settle on their own
+        assert_eq!(settle(&[-9, -8, 2, 7, 8]).len(), 3);
+        assert_eq!(settle(&[5, -5, 3, -3, 1, -1]).len(), 3);
+    }
+
+    #[test]
+    fn large_ledgers_still_settle() {
+        let mut amounts: Vec<i32> = (1..=10).chain((1..=10).map(|a| -a)).collect();
+        amounts.push(0);
+        assert!(settle(&amounts).len() < amounts.len());
+    }
+}
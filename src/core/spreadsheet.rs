@@ -0,0 +1,394 @@
+use std::io::{Cursor, Error, Write};
+
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use super::{export::GameExport, html_template::escape_html};
+
+pub enum Cell {
+    Empty,
+    Text(String),
+    Number(i32),
+    Sum(usize, usize, i32),     // SUM over rows first..=last of the same column, with the computed value
+}
+
+pub struct Sheet {
+    pub name: String,
+    pub rows: Vec<Vec<Cell>>,
+    pub bold_rows: Vec<usize>,
+    pub highlight: Vec<(usize, usize, usize)>,  // (column, first row, last row) with the best and worst value marked
+}
+
+// Workbook of a game: the score sheet with live totals and, for games with attributes, the round details.
+pub fn build_workbook(export: &GameExport) -> Vec<Sheet> {
+    let rounds = export.rounds as usize;
+    let mut header = vec![Cell::Text("Round".to_string())];
+    header.extend(export.players.iter().map(|p| Cell::Text(p.name.clone())));
+    let mut rows = vec![header];
+    for round in 0..rounds {
+        let mut row = vec![Cell::Number(round as i32 + 1)];
+        for player in export.players.iter() {
+            row.push(match export.score.get(&player.id).and_then(|s| s.get(round)) {
+                Some(Some(score)) => Cell::Number(*score),
+                _ => Cell::Empty,
+            });
+        }
+        rows.push(row);
+    }
+    // whatever the totals hold beyond the rounds are radlc penalties
+    let adjustments: Vec<i32> = export.players
+        .iter()
+        .map(|p| {
+            let played: i32 = export.score.get(&p.id).map_or(0, |s| s.iter().flatten().sum());
+            export.totals.get(&p.id).unwrap_or(&played) - played
+        })
+        .collect();
+    if adjustments.iter().any(|a| *a != 0) {
+        let mut row = vec![Cell::Text("Radlci".to_string())];
+        row.extend(adjustments.iter().map(|a| Cell::Number(*a)));
+        rows.push(row);
+    }
+    let last = rows.len() - 1;
+    let mut totals = vec![Cell::Text("Total".to_string())];
+    for (index, player) in export.players.iter().enumerate() {
+        let played: i32 = export.score.get(&player.id).map_or(0, |s| s.iter().flatten().sum());
+        totals.push(Cell::Sum(1, last, played + adjustments[index]));
+    }
+    rows.push(totals);
+    let mut score_sheet = Sheet {
+        name: capitalize(&export.game_type),
+        bold_rows: vec![0, rows.len() - 1],
+        highlight: vec![],
+        rows,
+    };
+    if rounds > 0 {
+        score_sheet.highlight = (1..=export.players.len()).map(|column| (column, 1, rounds)).collect();
+    }
+    if export.game_attributes.iter().all(|a| a.is_empty()) {
+        return vec![score_sheet];
+    }
+    vec![score_sheet, details_sheet(export)]
+}
+
+fn details_sheet(export: &GameExport) -> Sheet {
+    let mut header: Vec<Cell> = ["Round", "Dealer", "Contract", "Base", "Diff", "Bonuses", "Game points"]
+        .iter()
+        .map(|h| Cell::Text(h.to_string()))
+        .collect();
+    header.extend(export.players.iter().map(|p| Cell::Text(p.name.clone())));
+    let mut rows = vec![header];
+    for round in 0..export.rounds as usize {
+        let dealer = match export.dealers.get(round) {
+            Some(Some(dealer)) => match export.players.iter().find(|p| &p.id == dealer) {
+                Some(player) => Cell::Text(player.name.clone()),
+                None => Cell::Empty,
+            },
+            _ => Cell::Empty,
+        };
+        let mut row = vec![Cell::Number(round as i32 + 1), dealer];
+        match export.details.iter().find(|d| d.round as usize == round + 1) {
+            Some(detail) => {
+                row.push(Cell::Text(detail.contract.clone()));
+                row.push(Cell::Number(detail.base));
+                row.push(Cell::Number(detail.diff));
+                row.push(Cell::Text(detail.bonuses
+                    .iter()
+                    .map(|(name, points)| format!("{} {:+}", name, points))
+                    .collect::<Vec<String>>()
+                    .join(", ")));
+                row.push(Cell::Number(detail.game_points));
+            },
+            // imported rounds only know what was entered
+            None => {
+                row.push(Cell::Text(export.game_attributes.get(round).map_or("".to_string(), |a| a.join(" "))));
+                row.extend([Cell::Empty, Cell::Empty, Cell::Empty, Cell::Empty]);
+            },
+        };
+        for player in export.players.iter() {
+            row.push(match export.player_attributes.get(&player.id).and_then(|a| a.get(round)) {
+                Some(Some(tags)) if !tags.is_empty() => Cell::Text(tags.join(" ")),
+                _ => Cell::Empty,
+            });
+        }
+        rows.push(row);
+    }
+    Sheet {
+        name: "Rounds".to_string(),
+        rows,
+        bold_rows: vec![0],
+        highlight: vec![],
+    }
+}
+
+pub fn to_xlsx(sheets: &[Sheet]) -> Result<Vec<u8>, Error> {
+    let mut files = vec![];
+    let mut overrides = String::new();
+    let mut workbook_sheets = String::new();
+    let mut relationships = String::new();
+    for (index, sheet) in sheets.iter().enumerate() {
+        let number = index + 1;
+        overrides.push_str(&format!(
+            r#"<Override PartName="/xl/worksheets/sheet{}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#,
+            number
+        ));
+        workbook_sheets.push_str(&format!(r#"<sheet name="{}" sheetId="{}" r:id="rId{}"/>"#, escape_html(&sheet.name), number, number));
+        relationships.push_str(&format!(
+            r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{}.xml"/>"#,
+            number, number
+        ));
+        files.push((format!("xl/worksheets/sheet{}.xml", number), xlsx_worksheet(sheet)));
+    }
+    relationships.push_str(&format!(
+        r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#,
+        sheets.len() + 1
+    ));
+    files.insert(0, ("[Content_Types].xml".to_string(), format!(
+        r#"{}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>{}</Types>"#,
+        XML_HEADER, overrides
+    )));
+    files.insert(1, ("_rels/.rels".to_string(), format!(
+        r#"{}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#,
+        XML_HEADER
+    )));
+    files.insert(2, ("xl/workbook.xml".to_string(), format!(
+        r#"{}<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>{}</sheets></workbook>"#,
+        XML_HEADER, workbook_sheets
+    )));
+    files.insert(3, ("xl/_rels/workbook.xml.rels".to_string(), format!(
+        r#"{}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{}</Relationships>"#,
+        XML_HEADER, relationships
+    )));
+    // cell style 1 is bold, differential styles 0 and 1 colour the best and worst rounds
+    files.insert(4, ("xl/styles.xml".to_string(), format!(
+        r#"{}<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs><cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles><dxfs count="2"><dxf><font><color rgb="FF008000"/></font></dxf><dxf><font><color rgb="FFFF0000"/></font></dxf></dxfs></styleSheet>"#,
+        XML_HEADER
+    )));
+    zip_files(None, files)
+}
+
+fn xlsx_worksheet(sheet: &Sheet) -> String {
+    let mut data = String::new();
+    for (row_index, row) in sheet.rows.iter().enumerate() {
+        let style = if sheet.bold_rows.contains(&row_index) { r#" s="1""# } else { "" };
+        data.push_str(&format!(r#"<row r="{}">"#, row_index + 1));
+        for (column, cell) in row.iter().enumerate() {
+            let reference = cell_reference(column, row_index);
+            match cell {
+                Cell::Empty => (),
+                Cell::Text(text) => data.push_str(&format!(
+                    r#"<c r="{}" t="inlineStr"{}><is><t>{}</t></is></c>"#, reference, style, escape_html(text)
+                )),
+                Cell::Number(value) => data.push_str(&format!(r#"<c r="{}"{}><v>{}</v></c>"#, reference, style, value)),
+                Cell::Sum(first, last, value) => data.push_str(&format!(
+                    r#"<c r="{}"{}><f>SUM({}:{})</f><v>{}</v></c>"#,
+                    reference, style, cell_reference(column, *first), cell_reference(column, *last), value
+                )),
+            };
+        }
+        data.push_str("</row>");
+    }
+    let mut formatting = String::new();
+    for (index, (column, first, last)) in sheet.highlight.iter().enumerate() {
+        formatting.push_str(&format!(
+            r#"<conditionalFormatting sqref="{}:{}"><cfRule type="top10" dxfId="0" priority="{}" rank="1"/><cfRule type="top10" dxfId="1" priority="{}" rank="1" bottom="1"/></conditionalFormatting>"#,
+            cell_reference(*column, *first), cell_reference(*column, *last), index * 2 + 1, index * 2 + 2
+        ));
+    }
+    format!(
+        r#"{}<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>{}</sheetData>{}</worksheet>"#,
+        XML_HEADER, data, formatting
+    )
+}
+
+pub fn to_ods(sheets: &[Sheet]) -> Result<Vec<u8>, Error> {
+    let mut automatic_styles = String::new();
+    let mut tables = String::new();
+    for (sheet_index, sheet) in sheets.iter().enumerate() {
+        // one cell style per highlighted column, its conditions compare against the column's rounds
+        for (column, first, last) in sheet.highlight.iter() {
+            let range = format!("[.${}${}:.${}${}]", column_name(*column), first + 1, column_name(*column), last + 1);
+            automatic_styles.push_str(&format!(
+                r#"<style:style style:name="hl{}_{}" style:family="table-cell" style:parent-style-name="Default"><style:map style:condition="cell-content()=MAX({})" style:apply-style-name="Best" style:base-cell-address="{}.{}"/><style:map style:condition="cell-content()=MIN({})" style:apply-style-name="Worst" style:base-cell-address="{}.{}"/></style:style>"#,
+                sheet_index, column, range, escape_html(&sheet.name), cell_reference(*column, *first), range, escape_html(&sheet.name), cell_reference(*column, *first)
+            ));
+        }
+        tables.push_str(&format!(r#"<table:table table:name="{}">"#, escape_html(&sheet.name)));
+        for (row_index, row) in sheet.rows.iter().enumerate() {
+            tables.push_str("<table:table-row>");
+            for (column, cell) in row.iter().enumerate() {
+                let style = if sheet.bold_rows.contains(&row_index) {
+                    r#" table:style-name="Heading""#.to_string()
+                } else if sheet.highlight.iter().any(|(c, first, last)| *c == column && row_index >= *first && row_index <= *last) {
+                    format!(r#" table:style-name="hl{}_{}""#, sheet_index, column)
+                } else {
+                    "".to_string()
+                };
+                match cell {
+                    Cell::Empty => tables.push_str(&format!("<table:table-cell{}/>", style)),
+                    Cell::Text(text) => tables.push_str(&format!(
+                        r#"<table:table-cell office:value-type="string"{}><text:p>{}</text:p></table:table-cell>"#, style, escape_html(text)
+                    )),
+                    Cell::Number(value) => tables.push_str(&format!(
+                        r#"<table:table-cell office:value-type="float" office:value="{}"{}><text:p>{}</text:p></table:table-cell>"#, value, style, value
+                    )),
+                    Cell::Sum(first, last, value) => tables.push_str(&format!(
+                        r#"<table:table-cell table:formula="of:=SUM([.{}:.{}])" office:value-type="float" office:value="{}"{}><text:p>{}</text:p></table:table-cell>"#,
+                        cell_reference(column, *first), cell_reference(column, *last), value, style, value
+                    )),
+                };
+            }
+            tables.push_str("</table:table-row>");
+        }
+        tables.push_str("</table:table>");
+    }
+    let files = vec![
+        ("META-INF/manifest.xml".to_string(), format!(
+            r#"{}<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2"><manifest:file-entry manifest:full-path="/" manifest:media-type="{}"/><manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/><manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/></manifest:manifest>"#,
+            XML_HEADER, ODS_MIMETYPE
+        )),
+        ("styles.xml".to_string(), format!(
+            r##"{}<office:document-styles {} office:version="1.2"><office:styles><style:style style:name="Default" style:family="table-cell"/><style:style style:name="Heading" style:family="table-cell" style:parent-style-name="Default"><style:text-properties fo:font-weight="bold"/></style:style><style:style style:name="Best" style:family="table-cell" style:parent-style-name="Default"><style:text-properties fo:color="#008000"/></style:style><style:style style:name="Worst" style:family="table-cell" style:parent-style-name="Default"><style:text-properties fo:color="#ff0000"/></style:style></office:styles></office:document-styles>"##,
+            XML_HEADER, ODS_NAMESPACES
+        )),
+        ("content.xml".to_string(), format!(
+            r#"{}<office:document-content {} office:version="1.2"><office:automatic-styles>{}</office:automatic-styles><office:body><office:spreadsheet>{}</office:spreadsheet></office:body></office:document-content>"#,
+            XML_HEADER, ODS_NAMESPACES, automatic_styles, tables
+        )),
+    ];
+    zip_files(Some(ODS_MIMETYPE), files)
+}
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+const ODS_MIMETYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+const ODS_NAMESPACES: &str = r#"xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:of="urn:oasis:names:tc:opendocument:xmlns:of:1.2""#;
+
+// opendocument requires the mimetype as the first, uncompressed entry
+fn zip_files(mimetype: Option<&str>, files: Vec<(String, String)>) -> Result<Vec<u8>, Error> {
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+    if let Some(mimetype) = mimetype {
+        if let Err(e) = zip.start_file("mimetype", FileOptions::default().compression_method(CompressionMethod::Stored)) {
+            return Err(Error::other(format!("Error writing spreadsheet: {}", e)));
+        }
+        zip.write_all(mimetype.as_bytes())?;
+    }
+    for (name, contents) in files.into_iter() {
+        if let Err(e) = zip.start_file(name, FileOptions::default().compression_method(CompressionMethod::Deflated)) {
+            return Err(Error::other(format!("Error writing spreadsheet: {}", e)));
+        }
+        zip.write_all(contents.as_bytes())?;
+    }
+    match zip.finish() {
+        Ok(cursor) => Ok(cursor.into_inner()),
+        Err(e) => Err(Error::other(format!("Error writing spreadsheet: {}", e))),
+    }
+}
+
+// 0 -> A, 25 -> Z, 26 -> AA
+fn column_name(column: usize) -> String {
+    let mut name = String::new();
+    let mut column = column + 1;
+    while column > 0 {
+        name.insert(0, (b'A' + ((column - 1) % 26) as u8) as char);
+        column = (column - 1) / 26;
+    }
+    name
+}
+
+fn cell_reference(column: usize, row: usize) -> String {
+    format!("{}{}", column_name(column), row + 1)
+}
+
+pub fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => "".to_string(),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::core::export::{ExportPlayer, GameExport};
+
+    use super::{build_workbook, column_name, xlsx_worksheet, to_ods, to_xlsx, Cell};
+
+    fn export(totals: &[i32]) -> GameExport {
+        let players: Vec<ExportPlayer> = ["ANA", "B&R"]
+            .iter()
+            .enumerate()
+            .map(|(index, name)| ExportPlayer { id: index.to_string(), name: name.to_string() })
+            .collect();
+        GameExport {
+            game_id: "sheet".to_string(),
+            game_type: "tarok".to_string(),
+            rounds: 2,
+            score: HashMap::from([
+                ("0".to_string(), vec![Some(20), Some(-10)]),
+                ("1".to_string(), vec![Some(-20), None]),
+            ]),
+            totals: players.iter().map(|p| p.id.clone()).zip(totals.iter().copied()).collect(),
+            players,
+            game_attributes: vec![vec![], vec![]],
+            player_attributes: HashMap::new(),
+            radlci: HashMap::new(),
+            dealers: vec![],
+            details: vec![],
+            seating: None,
+            dealer: None,
+            rules: None,
+        }
+    }
+
+    #[test]
+    fn columns_are_named_like_spreadsheets() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(27), "AB");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(column_name(702), "AAA");
+    }
+
+    #[test]
+    fn totals_sum_the_radlci_row() {
+        let sheets = build_workbook(&export(&[10, -70]));
+        assert_eq!(sheets.len(), 1);
+        let rows = &sheets[0].rows;
+        // header, two rounds, radlci and the totals
+        assert_eq!(rows.len(), 5);
+        assert!(matches!(&rows[3][0], Cell::Text(t) if t == "Radlci"));
+        assert!(matches!(rows[3][2], Cell::Number(-50)));
+        assert!(matches!(rows[4][1], Cell::Sum(1, 3, 10)));
+        assert!(matches!(rows[4][2], Cell::Sum(1, 3, -70)));
+        assert_eq!(sheets[0].bold_rows, vec![0, 4]);
+        assert_eq!(sheets[0].highlight, vec![(1, 1, 2), (2, 1, 2)]);
+
+        let xml = xlsx_worksheet(&sheets[0]);
+        assert!(xml.contains(r#"<c r="B5" s="1"><f>SUM(B2:B4)</f><v>10</v></c>"#), "{}", xml);
+        assert!(xml.contains(r#"<c r="C5" s="1"><f>SUM(C2:C4)</f><v>-70</v></c>"#), "{}", xml);
+        assert!(xml.contains("<t>B&amp;R</t>"));
+        assert!(!xml.contains(r#"r="C3""#), "sat out rounds stay empty");
+    }
+
+    #[test]
+    fn totals_without_radlci_sum_the_rounds() {
+        let sheets = build_workbook(&export(&[10, -20]));
+        assert_eq!(sheets[0].rows.len(), 4);
+        assert!(matches!(sheets[0].rows[3][1], Cell::Sum(1, 2, 10)));
+        assert!(xlsx_worksheet(&sheets[0]).contains("<f>SUM(B2:B3)</f>"));
+    }
+
+    #[test]
+    fn workbooks_are_zipped() {
+        let sheets = build_workbook(&export(&[10, -70]));
+        let xlsx = to_xlsx(&sheets).expect("Error writing xlsx");
+        let ods = to_ods(&sheets).expect("Error writing ods");
+        assert!(xlsx.starts_with(b"PK"));
+        // the uncompressed mimetype comes first
+        assert!(ods.starts_with(b"PK"));
+        assert_eq!(&ods[30..38], b"mimetype");
+        assert!(ods.windows(46).any(|w| w == b"application/vnd.oasis.opendocument.spreadsheet"));
+    }
+}
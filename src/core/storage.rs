@@ -0,0 +1,126 @@
+use std::{env, fs, io::Error, path::{Path, PathBuf}, time::{Duration, SystemTime}};
+
+use chrono::Utc;
+use teloxide::types::InputFile;
+use uuid::Uuid;
+
+const DEFAULT_DIR: &str = "./res/games";
+const DEFAULT_MAX_FILES: usize = 50;
+const DEFAULT_MAX_AGE_DAYS: u64 = 90;
+
+// Keeps a copy of a generated document (score sheets, reports) in the chat's own directory
+// and returns it as an in-memory file, so sending never depends on the disk.
+// Older copies of the chat are pruned by age and count (STORAGE_MAX_AGE_DAYS, STORAGE_MAX_FILES).
+pub fn store_document(chat_id: &str, name: &str, contents: Vec<u8>) -> Result<InputFile, Error> {
+    let dir = chat_dir(chat_id);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        return Err(Error::other(format!("Error creating storage directory {}: {}", dir.display(), e)));
+    }
+    let sent_name = format!("{}_{}", Utc::now().format("%Y%m%d_%H%M%S"), sanitize(name));
+    // the uuid keeps two documents of the same second apart
+    let stored_name = format!("{}_{}", Uuid::new_v4().simple(), sent_name);
+    if let Err(e) = fs::write(dir.join(&stored_name), &contents) {
+        return Err(Error::other(format!("Error saving {}: {}", stored_name, e)));
+    }
+    if let Err(e) = prune(&dir, max_files(), max_age()) {
+        log::error!("Failed to prune stored files of chat {}: {}", chat_id, e);
+    }
+    Ok(InputFile::memory(contents).file_name(sent_name))
+}
+
+// Removes files older than max_age, then the oldest ones above max_files.
+fn prune(dir: &Path, max_files: usize, max_age: Duration) -> Result<usize, Error> {
+    let now = SystemTime::now();
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            files.push((metadata.modified()?, entry.path()));
+        }
+    }
+    // newest first
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    let mut removed = 0;
+    for (index, (modified, path)) in files.iter().enumerate() {
+        let expired = now.duration_since(*modified).is_ok_and(|age| age > max_age);
+        if expired || index >= max_files {
+            fs::remove_file(path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+fn chat_dir(chat_id: &str) -> PathBuf {
+    let root = env::var("STORAGE_DIR").unwrap_or_else(|_| DEFAULT_DIR.to_string());
+    Path::new(&root).join(sanitize(chat_id))
+}
+
+fn max_files() -> usize {
+    match env::var("STORAGE_MAX_FILES").ok().and_then(|v| v.parse().ok()) {
+        Some(max) => max,
+        None => DEFAULT_MAX_FILES,
+    }
+}
+
+fn max_age() -> Duration {
+    let days = match env::var("STORAGE_MAX_AGE_DAYS").ok().and_then(|v| v.parse().ok()) {
+        Some(days) => days,
+        None => DEFAULT_MAX_AGE_DAYS,
+    };
+    Duration::from_secs(days * 24 * 60 * 60)
+}
+
+// names can contain user input (e.g. tournament tables), keep them inside the directory
+fn sanitize(name: &str) -> String {
+    name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, time::{Duration, SystemTime}};
+
+    use uuid::Uuid;
+
+    use super::{prune, sanitize};
+
+    fn write_file(dir: &std::path::Path, name: &str, age: Duration) {
+        let path = dir.join(name);
+        fs::write(&path, name).expect("Error writing file");
+        let file = fs::File::options().write(true).open(&path).expect("Error opening file");
+        file.set_modified(SystemTime::now() - age).expect("Error setting modification time");
+    }
+
+    #[test]
+    fn prune_keeps_the_newest_files_within_the_age() {
+        let dir = env::temp_dir().join(format!("score_keeper_storage_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).expect("Error creating directory");
+        let hour = Duration::from_secs(60 * 60);
+        write_file(&dir, "new.html", hour);
+        write_file(&dir, "recent.html", 2 * hour);
+        write_file(&dir, "older.html", 3 * hour);
+        write_file(&dir, "expired.html", 100 * hour);
+
+        assert_eq!(prune(&dir, 2, 50 * hour).expect("Error pruning"), 2);
+        let mut left: Vec<String> = fs::read_dir(&dir)
+            .expect("Error reading directory")
+            .map(|e| e.expect("Error reading entry").file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["new.html", "recent.html"]);
+        fs::remove_dir_all(&dir).expect("Error removing directory");
+    }
+
+    #[test]
+    fn names_stay_inside_the_directory() {
+        assert_eq!(sanitize("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(sanitize("table_1 <B>.html"), "table_1__B_.html");
+        assert_eq!(sanitize("-100123"), "-100123");
+    }
+}
\ No newline at end of file
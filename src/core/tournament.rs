@@ -0,0 +1,496 @@
+use std::{collections::HashMap, io::Error};
+
+use crate::{games::tarok::game::Tarok, models::user::User};
+
+use super::{traits::Game, html_template::Theme, archive::{finish_game, save_round_rows}, database::user_operations::get_user_by_name};
+
+// spread evenly over the places of a table, so tables of 3 and 4 hand out the same range
+const PLACEMENT_POINTS: i32 = 6;
+
+pub struct Tournament {
+    participants: Vec<User>,
+    round: i32,
+    tables: Vec<TournamentTable>,
+    points: HashMap<String, i32>,       // placement points: first at a table gets PLACEMENT_POINTS, last 0
+    scores: HashMap<String, i32>,       // sum of game totals, breaks ties in points
+    met: HashMap<(String, String), i32>,
+}
+
+struct TournamentTable {
+    number: usize,
+    players: Vec<User>,
+    game: Option<Tarok>,                // None once the table finished its game
+}
+
+impl Tournament {
+    pub fn new() -> Self {
+        Self {
+            participants: vec![],
+            round: 0,
+            tables: vec![],
+            points: HashMap::new(),
+            scores: HashMap::new(),
+            met: HashMap::new(),
+        }
+    }
+
+    pub fn join(&mut self, names: &[&str]) -> Result<String, Error> {
+        if names.is_empty() {
+            return Err(Error::other("Enter names of participants".to_string()));
+        }
+        let mut joined = vec![];
+        for name in names.iter() {
+            let user = find_user(name)?;
+            if !self.participants.contains(&user) {
+                joined.push(user.name.clone());
+                self.participants.push(user);
+            }
+        }
+        Ok(format!("Joined: {}\n{} participants", joined.join(", "), self.participants.len()))
+    }
+
+    pub fn leave(&mut self, names: &[&str]) -> Result<String, Error> {
+        let mut left = vec![];
+        for name in names.iter() {
+            let user = find_user(name)?;
+            if self.tables.iter().any(|t| t.game.is_some() && t.players.contains(&user)) {
+                return Err(Error::other(format!("{} is still playing at a table", user.name)));
+            }
+            self.participants.retain(|p| p != &user);
+            left.push(user.name);
+        }
+        Ok(format!("Left: {}\n{} participants", left.join(", "), self.participants.len()))
+    }
+
+    // seats the next round: first round in order of registration, later rounds by standings
+    pub fn next_round(&mut self) -> Result<String, Error> {
+        if let Some(table) = self.tables.iter().find(|t| t.game.is_some()) {
+            return Err(Error::other(format!("Table {} has not finished yet", table.number)));
+        }
+        let sizes = table_sizes(self.participants.len())?;
+        let order = match self.round {
+            0 => self.participants.clone(),
+            _ => self.ordered_participants(),
+        };
+        self.round += 1;
+        self.tables = vec![];
+        let mut lines = vec![format!("Tournament round {}", self.round)];
+        for (index, players) in seat_tables(order, &sizes, &self.met).into_iter().enumerate() {
+            for a in players.iter() {
+                for b in players.iter().filter(|b| b.id != a.id) {
+                    *self.met.entry((a.id.clone(), b.id.clone())).or_insert(0) += 1;
+                }
+            }
+            let mut game = Tarok::new();
+            let seating = game.seat_players(players.clone());
+            lines.push(format!("Table {}: {}", index + 1, seating));
+            self.tables.push(TournamentTable {
+                number: index + 1,
+                players,
+                game: Some(game),
+            });
+        }
+        Ok(lines.join("\n"))
+    }
+
+    // /tournament round <table> <contract> <players...> in the /round syntax
+    pub fn play_round(&mut self, chat_id: String, table: &str, round: &str) -> Result<String, Error> {
+        let table = self.running_table(table)?;
+        // round players and the players referenced by M: and R: must all sit at this table
+        for fragment in round.split_whitespace().skip(1) {
+            let mut parts = fragment.split(',');
+            let mut names: Vec<&str> = parts.next().into_iter().collect();
+            for part in parts {
+                if let Some((attribute, name)) = part.split_once(':') {
+                    if attribute.eq_ignore_ascii_case("M") || attribute.eq_ignore_ascii_case("R") {
+                        names.push(name);
+                    }
+                }
+            }
+            for name in names.into_iter() {
+                if !table.players.iter().any(|p| p.name == name.to_uppercase()) {
+                    return Err(Error::other(format!("{} is not seated at table {}", name, table.number)));
+                }
+            }
+        }
+        let game = match table.game.as_mut() {
+            Some(game) => game,
+            None => return Err(Error::other(format!("Table {} has finished", table.number))),
+        };
+        let mut response = game.play_round(chat_id, format!("/round {}", round))?;
+        if let Err(e) = save_round_rows(game) {
+            response = format!("{}\n{}", response, e);
+        }
+        Ok(format!("Table {}\n{}", table.number, response))
+    }
+
+    pub fn table_state(&mut self, table: &str, theme: &Theme) -> Result<String, Error> {
+        let table = self.running_table(table)?;
+        match table.game.as_mut() {
+            Some(game) => game.get_state(theme),
+            None => Err(Error::other(format!("Table {} has finished", table.number))),
+        }
+    }
+
+    // ends the game of a table like /endgame does and hands out the placement points,
+    // returns the reply and the html record of the game
+    pub fn finish_table(&mut self, chat_id: String, table: &str, theme: &Theme) -> Result<(String, String), Error> {
+        let (number, players, sheet, game) = {
+            let table = self.running_table(table)?;
+            let sheet = match table.game.as_mut() {
+                Some(game) => game.score_sheet()?,
+                None => return Err(Error::other(format!("Table {} has finished", table.number))),
+            };
+            match table.game.take() {
+                Some(game) => (table.number, table.players.clone(), sheet, game),
+                None => return Err(Error::other(format!("Table {} has finished", table.number))),
+            }
+        };
+        let (html, summary) = finish_game(chat_id, Box::new(game), theme)?;
+        let mut lines = vec![];
+        // a table that never played has no result, so nobody gets placement points
+        if sheet.rounds == 0 {
+            lines.push(format!("Table {} finished without playing a round, no points awarded", number));
+        } else {
+            let placements = sheet.placements();
+            let seated = players.len() as i32;
+            lines.push(format!("Table {} finished:", number));
+            let mut results: Vec<&User> = players.iter().collect();
+            results.sort_by_key(|p| placements.get(&p.id).unwrap_or(&seated));
+            for player in results.into_iter() {
+                let placement = *placements.get(&player.id).unwrap_or(&seated);
+                let total = *sheet.totals.get(&player.id).unwrap_or(&0);
+                *self.points.entry(player.id.clone()).or_insert(0) += placement_points(placement, seated);
+                *self.scores.entry(player.id.clone()).or_insert(0) += total;
+                lines.push(format!("{}. {} ({})", placement, player.name, total));
+            }
+        }
+        lines.extend(summary);
+        if self.tables.iter().all(|t| t.game.is_none()) {
+            lines.push(format!("All tables finished!\n{}", self.standings()));
+        }
+        Ok((lines.join("\n"), html))
+    }
+
+    // a tournament only ends between rounds, running tables have to be finished first
+    pub fn end(&self) -> Result<String, Error> {
+        if let Some(table) = self.tables.iter().find(|t| t.game.is_some()) {
+            return Err(Error::other(
+                format!("Table {} has not finished yet, end it with /tournament finish {}", table.number, table.number))
+            );
+        }
+        Ok(format!("Tournament ended!\n{}", self.standings()))
+    }
+
+    pub fn standings(&self) -> String {
+        let mut lines = vec![format!("Standings after round {}:", self.round)];
+        for (index, player) in self.ordered_participants().iter().enumerate() {
+            lines.push(format!(
+                "{}. {}: {} points ({})",
+                index + 1,
+                player.name,
+                self.points.get(&player.id).unwrap_or(&0),
+                self.scores.get(&player.id).unwrap_or(&0),
+            ));
+        }
+        for table in self.tables.iter().filter(|t| t.game.is_some()) {
+            lines.push(format!(
+                "Table {} playing: {}",
+                table.number,
+                table.players.iter().map(|p| p.name.clone()).collect::<Vec<String>>().join(", ")
+            ));
+        }
+        lines.join("\n")
+    }
+
+    fn ordered_participants(&self) -> Vec<User> {
+        let mut ordered = self.participants.clone();
+        ordered.sort_by(|a, b| self.points.get(&b.id).unwrap_or(&0)
+            .cmp(self.points.get(&a.id).unwrap_or(&0))
+            .then(self.scores.get(&b.id).unwrap_or(&0).cmp(self.scores.get(&a.id).unwrap_or(&0)))
+            .then(a.name.cmp(&b.name))
+        );
+        ordered
+    }
+
+    fn running_table(&mut self, number: &str) -> Result<&mut TournamentTable, Error> {
+        let number: usize = match number.parse() {
+            Ok(number) => number,
+            Err(_) => return Err(Error::other(format!("Expected a table number, got: {}", number))),
+        };
+        match self.tables.iter_mut().find(|t| t.number == number) {
+            Some(table) => Ok(table),
+            None => Err(Error::other(format!("There is no table {} this round", number))),
+        }
+    }
+}
+
+// 6, 4, 2, 0 at a table of 4 and 6, 3, 0 at a table of 3
+fn placement_points(placement: i32, seated: i32) -> i32 {
+    (seated - placement) * PLACEMENT_POINTS / (seated - 1).max(1)
+}
+
+// as many tables of 4 as possible, the rest of 3
+fn table_sizes(participants: usize) -> Result<Vec<usize>, Error> {
+    let tables = participants.div_ceil(4);
+    if participants < 3 || participants < tables * 3 {
+        return Err(Error::other(
+            format!("{} participants can't be seated at tables of 3-4 players", participants))
+        );
+    }
+    Ok((0..tables)
+        .map(|t| participants / tables + if t < participants % tables { 1 } else { 0 })
+        .collect())
+}
+
+// search steps before the best seating found so far is taken
+const MAX_SEATING_STEPS: usize = 200_000;
+
+// Each table is headed by the best remaining player and filled so that players meet as few
+// of the players they already met as possible. The search tries the least met players first
+// (ties go to the better placed) and stops early once nobody meets again.
+fn seat_tables(order: Vec<User>, sizes: &[usize], met: &HashMap<(String, String), i32>) -> Vec<Vec<User>> {
+    let mut search = SeatingSearch {
+        order: &order,
+        sizes,
+        met,
+        seated: vec![false; order.len()],
+        tables: vec![],
+        best: vec![],
+        best_meetings: i32::MAX,
+        steps: 0,
+    };
+    search.seat(0);
+    search.best
+        .iter()
+        .map(|table| table.iter().map(|index| order[*index].clone()).collect())
+        .collect()
+}
+
+struct SeatingSearch<'a> {
+    order: &'a [User],
+    sizes: &'a [usize],
+    met: &'a HashMap<(String, String), i32>,
+    seated: Vec<bool>,
+    tables: Vec<Vec<usize>>,    // indexes into order
+    best: Vec<Vec<usize>>,
+    best_meetings: i32,
+    steps: usize,
+}
+
+impl SeatingSearch<'_> {
+    fn seat(&mut self, meetings: i32) {
+        self.steps += 1;
+        if meetings >= self.best_meetings || self.best_meetings == 0 {
+            return;
+        }
+        if self.steps > MAX_SEATING_STEPS && !self.best.is_empty() {
+            return;
+        }
+        let table_full = match self.tables.last() {
+            Some(table) => table.len() == self.sizes[self.tables.len() - 1],
+            None => true,
+        };
+        if table_full {
+            if self.tables.len() == self.sizes.len() {
+                self.best = self.tables.clone();
+                self.best_meetings = meetings;
+                return;
+            }
+            let head = match self.seated.iter().position(|seated| !seated) {
+                Some(head) => head,
+                None => return,
+            };
+            self.seated[head] = true;
+            self.tables.push(vec![head]);
+            self.seat(meetings);
+            self.tables.pop();
+            self.seated[head] = false;
+            return;
+        }
+        // players after the head are picked in standings order, so every table is tried once
+        let table = self.tables.last().cloned().unwrap_or_default();
+        let after = if table.len() > 1 { table[table.len() - 1] } else { table[0] };
+        let mut candidates: Vec<(i32, usize)> = (after + 1..self.order.len())
+            .filter(|index| !self.seated[*index])
+            .map(|index| (self.meetings(&table, index), index))
+            .collect();
+        candidates.sort();
+        for (added, index) in candidates.into_iter() {
+            self.seated[index] = true;
+            if let Some(table) = self.tables.last_mut() {
+                table.push(index);
+            }
+            self.seat(meetings + added);
+            if let Some(table) = self.tables.last_mut() {
+                table.pop();
+            }
+            self.seated[index] = false;
+        }
+    }
+
+    fn meetings(&self, table: &[usize], candidate: usize) -> i32 {
+        table
+            .iter()
+            .map(|p| self.met.get(&(self.order[*p].id.clone(), self.order[candidate].id.clone())).unwrap_or(&0))
+            .sum()
+    }
+}
+
+fn find_user(name: &str) -> Result<User, Error> {
+    match get_user_by_name(name.to_uppercase()) {
+        Ok(Some(user)) => Ok(user),
+        Ok(None) => Err(Error::other(format!("Unknown player {}", name))),
+        Err(e) => Err(Error::other(format!("Error fetching user from DB: {}", e))),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{core::{html_template::Theme, database::test_database::{setup_database, register, user}}, models::user::User};
+
+    use super::{placement_points, seat_tables, table_sizes, Tournament};
+
+    const CHAT_ID: i64 = -1038;
+
+    fn players(count: usize) -> Vec<User> {
+        (0..count).map(|index| user(CHAT_ID, &format!("P{}", index))).collect()
+    }
+
+    // seats a round like next_round does and returns the number of pairs that met before
+    fn seat_round(order: Vec<User>, met: &mut HashMap<(String, String), i32>) -> i32 {
+        let count = order.len();
+        let sizes = table_sizes(count).expect("Error sizing tables");
+        let tables = seat_tables(order, &sizes, met);
+        assert_eq!(tables.iter().map(|t| t.len()).sum::<usize>(), count);
+        let mut repeats = 0;
+        for table in tables.iter() {
+            assert!((3..=4).contains(&table.len()), "table of {}", table.len());
+            for (index, a) in table.iter().enumerate() {
+                for b in table[index + 1..].iter() {
+                    repeats += met.get(&(a.id.clone(), b.id.clone())).map_or(0, |m| (*m > 0) as i32);
+                }
+            }
+            for a in table.iter() {
+                for b in table.iter().filter(|b| b.id != a.id) {
+                    *met.entry((a.id.clone(), b.id.clone())).or_insert(0) += 1;
+                }
+            }
+        }
+        repeats
+    }
+
+    // the standings of a later round in some other order
+    fn shuffled(players: &[User], seed: usize) -> Vec<User> {
+        let mut order: Vec<User> = players.to_vec();
+        for index in (1..order.len()).rev() {
+            order.swap(index, (index * 7 + seed * 13 + 5) % (index + 1));
+        }
+        order
+    }
+
+    #[test]
+    fn tables_seat_three_or_four() {
+        assert!(table_sizes(2).is_err());
+        assert!(table_sizes(5).is_err());
+        assert_eq!(table_sizes(3).unwrap(), vec![3]);
+        assert_eq!(table_sizes(4).unwrap(), vec![4]);
+        assert_eq!(table_sizes(6).unwrap(), vec![3, 3]);
+        assert_eq!(table_sizes(7).unwrap(), vec![4, 3]);
+        assert_eq!(table_sizes(9).unwrap(), vec![3, 3, 3]);
+        assert_eq!(table_sizes(13).unwrap(), vec![4, 3, 3, 3]);
+        for count in 6..40 {
+            let sizes = table_sizes(count).unwrap();
+            assert_eq!(sizes.iter().sum::<usize>(), count);
+            assert!(sizes.iter().all(|s| (3..=4).contains(s)), "{} -> {:?}", count, sizes);
+        }
+    }
+
+    #[test]
+    fn pairings_repeat_only_when_unavoidable() {
+        for seed in 0..20 {
+            // 6 at two tables of 3: every new table holds two players who met
+            let six = players(6);
+            let mut met = HashMap::new();
+            assert_eq!(seat_round(six.clone(), &mut met), 0);
+            assert_eq!(seat_round(shuffled(&six, seed), &mut met), 2, "seed {}", seed);
+
+            // 7 at tables of 4 and 3: the table of 3 holds one pair who met, the table of 4 two
+            let seven = players(7);
+            let mut met = HashMap::new();
+            assert_eq!(seat_round(seven.clone(), &mut met), 0);
+            assert_eq!(seat_round(shuffled(&seven, seed), &mut met), 3, "seed {}", seed);
+
+            // 9 at three tables of 3 can meet all new players for two rounds
+            let nine = players(9);
+            let mut met = HashMap::new();
+            assert_eq!(seat_round(nine.clone(), &mut met), 0);
+            assert_eq!(seat_round(shuffled(&nine, seed), &mut met), 0, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn larger_tournaments_are_seated() {
+        let sixteen = players(16);
+        let mut met = HashMap::new();
+        assert_eq!(seat_round(sixteen.clone(), &mut met), 0);
+        assert_eq!(seat_round(shuffled(&sixteen, 3), &mut met), 0);
+
+        // the search gives up on a perfect seating after a while, every round is still seated
+        let thirty = players(30);
+        let mut met = HashMap::new();
+        for round in 0..6 {
+            seat_round(shuffled(&thirty, round), &mut met);
+        }
+    }
+
+    #[test]
+    fn table_without_rounds_awards_no_points() {
+        let mut tournament = Tournament::new();
+        tournament.participants = players(6);
+        tournament.next_round().expect("Error seating round");
+        let (reply, _) = tournament.finish_table(CHAT_ID.to_string(), "1", &Theme::Light).expect("Error finishing table");
+        assert!(reply.contains("no points awarded"), "{}", reply);
+        assert!(tournament.points.values().all(|p| *p == 0));
+        assert!(tournament.scores.values().all(|s| *s == 0));
+        assert!(tournament.finish_table(CHAT_ID.to_string(), "1", &Theme::Light).is_err());
+    }
+
+    #[test]
+    fn placement_points_do_not_depend_on_table_size() {
+        assert_eq!((1..=4).map(|p| placement_points(p, 4)).collect::<Vec<i32>>(), vec![6, 4, 2, 0]);
+        assert_eq!((1..=3).map(|p| placement_points(p, 3)).collect::<Vec<i32>>(), vec![6, 3, 0]);
+        // a table winner gets the same at any table
+        assert_eq!(placement_points(1, 3), placement_points(1, 4));
+    }
+
+    #[test]
+    fn mixed_tables_award_the_same_points_per_place() {
+        setup_database();
+        let mut tournament = Tournament::new();
+        tournament.participants = (0..13).map(|index| register(CHAT_ID, &format!("TNMIX{}", index))).collect();
+        tournament.next_round().expect("Error seating round");
+        let tables: Vec<Vec<User>> = tournament.tables.iter().map(|t| t.players.clone()).collect();
+        assert_eq!(tables.iter().map(|t| t.len()).collect::<Vec<usize>>(), vec![4, 3, 3, 3]);
+        for (index, players) in tables.iter().enumerate() {
+            // klop puts the seated order in place order
+            let round = players
+                .iter()
+                .enumerate()
+                .map(|(place, p)| format!("{},-{}", p.name, (place + 1) * 10))
+                .collect::<Vec<String>>()
+                .join(" ");
+            let table = (index + 1).to_string();
+            tournament.play_round(CHAT_ID.to_string(), &table, &format!("KL {}", round)).expect("Error playing round");
+            tournament.finish_table(CHAT_ID.to_string(), &table, &Theme::Light).expect("Error finishing table");
+        }
+        let points = |players: &[User]| players.iter().map(|p| tournament.points[&p.id]).collect::<Vec<i32>>();
+        assert_eq!(points(&tables[0]), vec![6, 4, 2, 0]);
+        for table in tables[1..].iter() {
+            assert_eq!(points(table), vec![6, 3, 0]);
+        }
+    }
+}
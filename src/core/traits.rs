@@ -1,17 +1,47 @@
 use std::io::Error;
 
-use chrono::Utc;
 use teloxide::types::Message;
 
+use crate::models::round::Round;
+
+use super::{score_sheet::ScoreSheet, achievements::{Achievement, generic_achievement_rules}, score_image::{ScoreGrid, render_png}, score_text::render_text, export::GameExport, html_template::Theme};
+
 pub trait CheckName {
     fn is_valid_name(&self, name: &str) -> bool { !self.get_reserved_terms().contains(&name) }
     fn get_reserved_terms(&self) -> &'static [&'static str] { &[] }
 }
 
+pub trait AchievementRule {
+    fn detect(&self, sheet: &ScoreSheet) -> Vec<Achievement>;
+}
+
 pub trait Game {
-    fn start_game(&mut self) -> Result<String, Error>;
+    fn start_game(&mut self, message: Message) -> Result<String, Error>;
     fn handle_round(&mut self, message: Message) -> Result<String, Error>;
-    fn end_game(self: Box<Self>) -> Result<String, Error>; // https://stackoverflow.com/questions/63766721/size-of-dyn-mytrait-cannot-be-statically-determined-in-method-which-takes-self
-    fn get_state(&mut self) -> Result<String, Error>;
-    fn generate_file_name(&self) -> String { format!("{}_table.html", Utc::now()) }
+    fn end_game(self: Box<Self>, theme: &Theme) -> Result<String, Error>; // https://stackoverflow.com/questions/63766721/size-of-dyn-mytrait-cannot-be-statically-determined-in-method-which-takes-self
+    fn get_state(&mut self, theme: &Theme) -> Result<String, Error>;
+    fn score_grid(&mut self) -> Result<ScoreGrid, Error>;
+    fn get_state_image(&mut self) -> Result<Vec<u8>, Error> { render_png(&self.score_grid()?) }
+    fn get_state_text(&mut self) -> Result<String, Error> { Ok(render_text(&self.score_grid()?)) }
+    fn score_sheet(&mut self) -> Result<ScoreSheet, Error>;
+    fn export(&mut self) -> Result<GameExport, Error> { Ok(GameExport::from_sheet(self.score_sheet()?)) }
+    // statistic rows of the rounds played since the last call, persisted by the caller
+    fn take_round_rows(&mut self) -> Vec<Round> { vec![] }
+    fn end_of_game_rows(&mut self) -> Vec<Round> { vec![] }
+    fn seat(&mut self, _message: Message) -> Result<String, Error> { 
+        Err(Error::other("This game has no seating".to_string())) 
+    }
+    fn handle_rules(&mut self, _message: Message) -> Result<String, Error> { 
+        Err(Error::other("This game has no configurable rules".to_string())) 
+    }
+    fn manage_radlci(&mut self, _message: Message) -> Result<String, Error> { 
+        Err(Error::other("This game has no radlci".to_string())) 
+    }
+    fn explain_round(&self, _message: Message) -> Result<String, Error> { 
+        Err(Error::other("This game can't explain its rounds".to_string())) 
+    }
+    fn achievement_rules(&self) -> Vec<Box<dyn AchievementRule>> { generic_achievement_rules() }
+    // group of each round for the chart (e.g. the contract family), games without one leave it empty
+    fn round_groups(&self) -> Vec<Option<String>> { vec![] }
+    fn generate_file_name(&self) -> String { "table.html".to_string() }
 }
\ No newline at end of file
@@ -1,12 +1,13 @@
-use std::{collections::HashMap, io::{ErrorKind, Error}};
+use std::{collections::HashMap, io::Error};
 
-use chrono::Utc;
+use uuid::Uuid;
 
-use crate::{core::{traits::{CheckName, Game}, message_helper::extract_message_text, database::user_operations::get_user_by_name}, models::user::User};
+use crate::{core::{traits::{CheckName, Game}, score_sheet::ScoreSheet, export::GameExport, score_image::ScoreGrid, html_template::Theme, message_helper::extract_message_text, database::user_operations::get_user_by_name}, models::user::User};
 
-use super::html_helper::build_score_table_html;
+use super::{html_helper::build_score_table_html, image_helper::build_score_grid};
 
 pub struct Table {
+    id: String,
     players: Vec<User>,
     score: HashMap<String, Vec<Option<i32>>>,
     round: i32,
@@ -15,36 +16,56 @@ pub struct Table {
 impl Table {
     pub fn new() -> Self {
         Self {
+            id: Uuid::new_v4().to_string(),
             players: vec![],
             score: HashMap::new(),
             round: 0 
         }
     }
+
+    // rebuilds a game from an export whose player ids already match the given players
+    pub fn import(export: &GameExport, players: Vec<User>) -> Self {
+        let mut game = Table::new();
+        for player in players.iter() {
+            let mut score = export.score.get(&player.id).cloned().unwrap_or_default();
+            score.resize(export.rounds as usize, None);
+            game.score.insert(player.id.clone(), score);
+        }
+        game.players = players;
+        game.round = export.rounds;
+        game
+    }
+
+    // pads the score of players who sat out the last rounds
+    fn fill_missing_rounds(&mut self) -> Result<(), Error> {
+        for player in self.players.iter() {
+            if let Some(score) = self.score.get_mut(&player.id.to_string()) {
+                fill_gaps_until_round(score, self.round);
+            } else {
+                return Err(Error::other(format!("Something went wrong on entering user {} score for the missing rounds", player.name)))
+            };
+        }
+        Ok(())
+    }
 }
 
 impl CheckName for Table {}
 impl Game for Table {
     
-    fn start_game(&mut self) -> Result<String, std::io::Error> {
+    fn start_game(&mut self, _message: teloxide::types::Message) -> Result<String, std::io::Error> {
         Ok("Started a generic game score table!".to_string())
     }
 
     fn handle_round(&mut self, message: teloxide::types::Message) -> Result<String, Error> {
         let text = match extract_message_text(&message) {
             Some(text) => text,
-            None => return Err(Error::new(ErrorKind::Other, "Failed to extract message text".to_string()))
-        };
-        let users = match extract_round_users(text.clone()) {
-            Ok(users) => users,
-            Err(e) => return Err(e),
+            None => return Err(Error::other("Failed to extract message text".to_string()))
         };
+        let users = extract_round_users(text.clone())?;
 
-        let scores = match extract_round_scores(text) {
-            Ok(score) => score,
-            Err(e) => return Err(e),
-        };
+        let scores = extract_round_scores(text)?;
         if scores.len() != users.len() {
-            return Err(Error::new(ErrorKind::Other, "Number of users and scores do not match".to_string()));
+            return Err(Error::other("Number of users and scores do not match".to_string()));
         }
         for index in 0..users.len() {
             let uid = users[index].id.clone();
@@ -68,7 +89,7 @@ impl Game for Table {
                 // push latest
                 score.push(Some(scores[index]))
             } else {
-                return Err(Error::new(ErrorKind::Other, format!("Something went wrong on entering user {} score for the round", uid)))
+                return Err(Error::other(format!("Something went wrong on entering user {} score for the round", uid)))
             }
 
         }
@@ -76,28 +97,34 @@ impl Game for Table {
         Ok(format!("Round {} submitted!", self.round))
     }
 
-    fn end_game(mut self: Box<Self>) -> Result<String, std::io::Error> {
-        for player in self.players.iter() {
-            if let Some(score) = self.score.get_mut(&player.id.to_string()) {
-                fill_gaps_until_round(score, self.round);
-            } else {
-                return Err(Error::new(ErrorKind::Other, format!("Something went wrong on entering user {} score for the missing rounds", player.name)))
-            };
-        }
+    fn end_game(mut self: Box<Self>, theme: &Theme) -> Result<String, std::io::Error> {
+        self.fill_missing_rounds()?;
         let sum_by_player: HashMap<String, i32> = sum_score_by_players(&self.score, &self.players);
-        Ok(build_score_table_html(&self.players, &self.score, self.round, sum_by_player))
+        Ok(build_score_table_html("Score table", &self.players, &self.score, self.round, sum_by_player, theme))
     }
 
-    fn get_state(&mut self) -> Result<String, std::io::Error> {
-        for player in self.players.iter() {
-            if let Some(score) = self.score.get_mut(&player.id.to_string()) {
-                fill_gaps_until_round(score, self.round);
-            } else {
-                return Err(Error::new(ErrorKind::Other, format!("Something went wrong on entering user {} score for the missing rounds", player.name)))
-            };
-        }
+    fn get_state(&mut self, theme: &Theme) -> Result<String, std::io::Error> {
+        self.fill_missing_rounds()?;
         let sum_by_player: HashMap<String, i32> = sum_score_by_players(&self.score, &self.players);
-        Ok(build_score_table_html(&self.players, &self.score, self.round, sum_by_player))
+        Ok(build_score_table_html("Score table", &self.players, &self.score, self.round, sum_by_player, theme))
+    }
+
+    fn score_grid(&mut self) -> Result<ScoreGrid, std::io::Error> {
+        self.fill_missing_rounds()?;
+        let sum_by_player: HashMap<String, i32> = sum_score_by_players(&self.score, &self.players);
+        Ok(build_score_grid(&self.players, &self.score, self.round, &sum_by_player))
+    }
+
+    fn score_sheet(&mut self) -> Result<ScoreSheet, std::io::Error> {
+        self.fill_missing_rounds()?;
+        Ok(ScoreSheet {
+            game_id: self.id.clone(),
+            game_type: "table".to_string(),
+            players: self.players.clone(),
+            score: self.score.clone(),
+            rounds: self.round,
+            totals: sum_score_by_players(&self.score, &self.players),
+        })
     }
 }
 
@@ -134,11 +161,11 @@ fn extract_round_users(message_text: String) -> Result<Vec<User>, Error> {
     {
         let user_option = match get_user_by_name(fragment.to_uppercase()) {
             Ok(data) => data,
-            Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error fetching user from DB: {}", e))),
+            Err(e) => return Err(Error::other(format!("Error fetching user from DB: {}", e))),
         };
         let user = match user_option {
             Some(user) => user,
-            None => return Err(Error::new(ErrorKind::Other, "Error fetching user from DB".to_string())),
+            None => return Err(Error::other("Error fetching user from DB".to_string())),
         };
         users.push(user);
     }
@@ -154,9 +181,34 @@ fn extract_round_scores(message_text: String) -> Result<Vec<i32>, Error> {
     {
         let score = match fragment.parse() {
             Ok(num) => num,
-            Err(_e) => return Err(Error::new(ErrorKind::Other, format!("Error parsing score {}", fragment))),
+            Err(_e) => return Err(Error::other(format!("Error parsing score {}", fragment))),
         };
         scores.push(score);
     }
     Ok(scores)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{traits::Game, database::test_database::{setup_database, register, message}};
+
+    use super::Table;
+
+    const CHAT_ID: i64 = -1041;
+
+    #[test]
+    fn players_who_sat_out_are_filled_to_the_last_round() {
+        setup_database();
+        let (ana, bor) = (register(CHAT_ID, "TABANA"), register(CHAT_ID, "TABBOR"));
+        let mut game = Table::new();
+        game.handle_round(message(CHAT_ID, "/round TABANA 10 TABBOR -10")).expect("Error playing round");
+        game.handle_round(message(CHAT_ID, "/round TABANA 5")).expect("Error playing round");
+        game.handle_round(message(CHAT_ID, "/round TABANA 5")).expect("Error playing round");
+
+        let sheet = game.score_sheet().expect("Error collecting sheet");
+        assert_eq!(sheet.rounds, 3);
+        assert_eq!(sheet.score[&ana.id], vec![Some(10), Some(5), Some(5)]);
+        assert_eq!(sheet.score[&bor.id], vec![Some(-10), None, None]);
+        assert_eq!(sheet.totals[&bor.id], -10);
+    }
+}
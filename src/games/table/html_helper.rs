@@ -1,18 +1,21 @@
 use std::collections::HashMap;
 
-use crate::models::user::User;
+use crate::{models::user::User, core::html_template::{Theme, render_sheet, escape_html}};
 
 pub fn build_score_table_html(
-    players: &Vec<User>, 
+    game_name: &str,
+    players: &[User], 
     score_table: &HashMap<String, Vec<Option<i32>>>, 
     rounds: i32,
     final_scores: HashMap<String, i32>,
+    theme: &Theme,
 ) -> String {
-    format!(
-        "{}{}{}", 
-        get_html_head(), 
-        generate_table(players, score_table, rounds, final_scores), 
-        get_html_tail()
+    render_sheet(
+        game_name, 
+        players, 
+        &generate_table(players, score_table, rounds, final_scores), 
+        "", 
+        theme
     )
 }
 
@@ -25,7 +28,7 @@ fn generate_table(
     let mut table = String::from("");
     // generate table header
     for player in players.iter() {
-        let append = format!("<th>{}</th>", player.name);
+        let append = format!("<th>{}</th>", escape_html(&player.name));
         table = format!("{}{}", table,append)
     }
     table = format!("<tr>{}</tr>", table);
@@ -73,15 +76,4 @@ fn extract_field_value(score_table: &HashMap<String, Vec<Option<i32>>>, player:
         },
         None => "Missing".to_string(),
     }
-}
-
-fn get_html_tail() -> String {
-    "</table></body></html>".to_string()
-}
-
-fn get_html_head() -> String {
-    "<!DOCTYPE html><html lang='en'><head><meta charset='UTF-8'><meta http-equiv='X-UA-Compatible' content='IE=edge'>
-    <meta name='viewport' content='width=device-width, initial-scale=1.0'><title>Document</title></head><body><style>
-    table{width: 100%;text-align: center;}tr:nth-child(2n) {color: rgb(128, 128, 128)}th {color: #a6acf3;}.biggest {
-    color: green;}.smallest {color: red}td,th {border: 1px solid rgb(190, 190, 190);}</style><table>".to_string()
 }
\ No newline at end of file
@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+
+use crate::{core::score_image::{ScoreGrid, GridRow, RowStyle, CellTone}, models::user::User};
+
+// same sheet as build_score_table_html, each player's best and worst round coloured
+pub fn build_score_grid(
+    players: &[User], 
+    score_table: &HashMap<String, Vec<Option<i32>>>, 
+    rounds: i32,
+    final_scores: &HashMap<String, i32>,
+) -> ScoreGrid {
+    let mut rows = vec![];
+    rows.push(GridRow::new(RowStyle::Header, players.iter().map(|p| p.name.clone()).collect()));
+    for index in 0..rounds as usize {
+        let mut cells = vec![];
+        for player in players.iter() {
+            let scores = match score_table.get(&player.id) {
+                Some(scores) => scores,
+                None => {
+                    cells.push(("Missing".to_string(), CellTone::Normal));
+                    continue;
+                },
+            };
+            let cell = match scores.get(index) {
+                Some(Some(val)) => {
+                    let tone = if Some(val) == scores.iter().flatten().max() {
+                        CellTone::Best
+                    } else if Some(val) == scores.iter().flatten().min() {
+                        CellTone::Worst
+                    } else {
+                        CellTone::Normal
+                    };
+                    (val.to_string(), tone)
+                },
+                _ => ("".to_string(), CellTone::Normal),
+            };
+            cells.push(cell);
+        }
+        rows.push(GridRow { style: RowStyle::Round, cells });
+    }
+    rows.push(GridRow::new(
+        RowStyle::Total,
+        players
+            .iter()
+            .map(|p| final_scores.get(&p.id).unwrap_or(&0).to_string())
+            .collect()
+    ));
+    ScoreGrid { rows }
+}
\ No newline at end of file
@@ -1,2 +1,3 @@
 pub mod game;
-mod html_helper;
\ No newline at end of file
+pub mod html_helper;
+mod image_helper;
\ No newline at end of file
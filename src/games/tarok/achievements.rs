@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use crate::core::{traits::AchievementRule, score_sheet::ScoreSheet, achievements::Achievement};
+
+pub struct MostContractsDeclared {
+    pub declared: HashMap<String, i32>,
+}
+
+// (player id, round) of every won colour valat in the game
+pub struct FirstColourValat {
+    pub won: Vec<(String, i32)>,
+}
+
+impl AchievementRule for MostContractsDeclared {
+    fn detect(&self, _sheet: &ScoreSheet) -> Vec<Achievement> {
+        let most = self.declared.values().max().copied().unwrap_or(0);
+        if most == 0 {
+            return vec![];
+        }
+        let mut players: Vec<&String> = self.declared
+            .iter()
+            .filter(|(_, count)| **count == most)
+            .map(|(id, _)| id)
+            .collect();
+        players.sort();
+        players
+            .into_iter()
+            .map(|id| Achievement {
+                code: "most_declared",
+                player_id: id.clone(),
+                description: format!("Most contracts declared: {}", most),
+                once: false,
+            })
+            .collect()
+    }
+}
+
+impl AchievementRule for FirstColourValat {
+    fn detect(&self, _sheet: &ScoreSheet) -> Vec<Achievement> {
+        let mut awarded: Vec<Achievement> = vec![];
+        for (player_id, round) in self.won.iter() {
+            if awarded.iter().any(|a| &a.player_id == player_id) {
+                continue;
+            }
+            awarded.push(Achievement {
+                code: "first_colour_valat",
+                player_id: player_id.clone(),
+                description: format!("First colour valat (round {})", round),
+                once: true,
+            });
+        }
+        awarded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        core::{score_sheet::ScoreSheet, traits::AchievementRule, achievements::award_achievements, database::test_database::{setup_database, register}},
+        models::user::User,
+    };
+
+    use super::{FirstColourValat, MostContractsDeclared};
+
+    const CHAT_ID: i64 = -1040;
+
+    fn sheet(players: &[User]) -> ScoreSheet {
+        ScoreSheet {
+            game_id: uuid::Uuid::new_v4().to_string(),
+            game_type: "tarok".to_string(),
+            players: players.to_vec(),
+            score: HashMap::new(),
+            rounds: 1,
+            totals: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn most_declared_ties_are_all_awarded() {
+        let declared = HashMap::from([("a".to_string(), 2), ("b".to_string(), 1), ("c".to_string(), 2)]);
+        let awarded = MostContractsDeclared { declared }.detect(&sheet(&[]));
+        assert_eq!(awarded.iter().map(|a| a.player_id.as_str()).collect::<Vec<&str>>(), vec!["a", "c"]);
+        assert_eq!(awarded[0].description, "Most contracts declared: 2");
+        assert!(MostContractsDeclared { declared: HashMap::new() }.detect(&sheet(&[])).is_empty());
+    }
+
+    #[test]
+    fn first_colour_valat_is_awarded_once_per_player() {
+        setup_database();
+        let (ana, bor) = (register(CHAT_ID, "ACHANA"), register(CHAT_ID, "ACHBOR"));
+        let players = vec![ana.clone(), bor.clone()];
+        let award = |won: Vec<(String, i32)>| {
+            let rules: Vec<Box<dyn AchievementRule>> = vec![Box::new(FirstColourValat { won })];
+            award_achievements(CHAT_ID.to_string(), &sheet(&players), rules).expect("Error awarding achievements")
+        };
+
+        // two colour valats in one game are one achievement
+        let first = award(vec![(ana.id.clone(), 2), (ana.id.clone(), 5)]);
+        assert_eq!(first.as_deref(), Some("Highlights:\nACHANA: First colour valat (round 2)"));
+        // later games only award the players who didn't have one yet
+        let second = award(vec![(ana.id.clone(), 1), (bor.id.clone(), 3)]);
+        assert_eq!(second.as_deref(), Some("Highlights:\nACHBOR: First colour valat (round 3)"));
+        assert_eq!(award(vec![(bor.id.clone(), 4)]), None);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::models::user::User;
+
+use super::enums::{TarokGame, TarokPlayerAttibute, TarokPlayerInput};
+
+pub struct RoundBreakdown {
+    pub round: i32,
+    pub contract: Option<TarokGame>,
+    pub base: i32,
+    pub diff: i32,
+    pub bonuses: Vec<(String, i32)>,
+    pub lost_doubling: i32,
+    pub override_reason: Option<String>, // valat or renonce deciding the contract points
+    pub game_points: i32,
+    pub radlc_multiplier: i32,
+    pub personal: Vec<(String, String, i32)>, // (player id, reason, points)
+    pub score_change: Vec<(String, i32)>,
+}
+
+impl RoundBreakdown {
+    pub fn new(round: i32) -> Self {
+        Self {
+            round,
+            contract: None,
+            base: 0,
+            diff: 0,
+            bonuses: vec![],
+            lost_doubling: 0,
+            override_reason: None,
+            game_points: 0,
+            radlc_multiplier: 1,
+            personal: vec![],
+            score_change: vec![],
+        }
+    }
+
+    pub fn add_personal(&mut self, player_id: &str, reason: String, points: i32) {
+        self.personal.push((player_id.to_string(), reason, points));
+    }
+
+    pub fn describe(&self, players: &[User]) -> String {
+        let mut lines = vec![];
+        match self.contract {
+            Some(game) => lines.push(format!("Round {}: {} ({})", self.round, game.code(), game.name())),
+            None => lines.push(format!("Round {}", self.round)),
+        };
+        match &self.override_reason {
+            Some(reason) => lines.push(format!("{}: {}", reason, self.game_points)),
+            None => {
+                lines.push(format!("Contract value: {}", self.base));
+                lines.push(format!("Diff: {}", self.diff));
+                for (bonus, points) in self.bonuses.iter() {
+                    lines.push(format!("Bonus {}: {}", bonus, points));
+                }
+                if self.lost_doubling != 0 {
+                    lines.push(format!("Lost game: {}", self.lost_doubling));
+                }
+                lines.push(format!("Game points: {}", self.game_points));
+            },
+        };
+        if self.radlc_multiplier != 1 {
+            lines.push(format!(
+                "Radlc: x{} -> {}",
+                self.radlc_multiplier,
+                self.game_points * self.radlc_multiplier
+            ));
+        }
+        for (player_id, change) in self.score_change.iter() {
+            lines.push(format!("{}: {}", player_name(players, player_id), change));
+            for (_, reason, points) in self.personal.iter().filter(|(id, _, _)| id == player_id) {
+                lines.push(format!("  {} {}", reason, points));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+pub fn player_input_label(input: &TarokPlayerInput) -> String {
+    match input {
+        TarokPlayerInput::PlayerDiff(_) => "Diff".to_string(),
+        TarokPlayerInput::PlayerAttribute(attr) => match attr {
+            TarokPlayerAttibute::M(_) => "Mond lost".to_string(),
+            TarokPlayerAttibute::Mc(_) => "Mond captured".to_string(),
+            TarokPlayerAttibute::R(_) => "Renonce".to_string(),
+            TarokPlayerAttibute::T => "T".to_string(),
+            TarokPlayerAttibute::Ig => "Declarer".to_string(),
+            TarokPlayerAttibute::Sl => "Supporting".to_string(),
+        },
+    }
+}
+
+fn player_name(players: &[User], id: &String) -> String {
+    match players.iter().find(|p| &p.id == id) {
+        Some(player) => player.name.clone(),
+        None => id.clone(),
+    }
+}
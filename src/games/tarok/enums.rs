@@ -1,3 +1,7 @@
+use serde::{Serialize, Deserialize};
+
+// variants wrap the enums they carry, so they share those enums' names
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug)]
 pub enum TarokGameInput {
     TarokGame(TarokGame),
@@ -11,7 +15,70 @@ pub enum TarokPlayerInput {
     PlayerDiff(i32),
 }
 
-#[derive(Debug, Clone, Copy)]
+impl TarokGameInput {
+    pub fn to_tag(&self) -> String {
+        match self {
+            TarokGameInput::TarokGame(game) => game.code().to_string(),
+            TarokGameInput::TarokGameAttribute(attr) => attr.code().to_string(),
+            TarokGameInput::TarokGameDiff(val) => val.to_string(),
+        }
+    }
+
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        if let Some(game) = TarokGame::from_code(tag) {
+            return Some(TarokGameInput::TarokGame(game));
+        }
+        if let Some(attr) = TarokGameAttribute::from_code(tag) {
+            return Some(TarokGameInput::TarokGameAttribute(attr));
+        }
+        match tag.parse() {
+            Ok(val) => Some(TarokGameInput::TarokGameDiff(val)),
+            Err(_) => None,
+        }
+    }
+}
+
+impl TarokPlayerInput {
+    pub fn to_tag(&self) -> String {
+        match self {
+            TarokPlayerInput::PlayerDiff(val) => val.to_string(),
+            TarokPlayerInput::PlayerAttribute(attr) => match attr {
+                TarokPlayerAttibute::M(None) => "M".to_string(),
+                TarokPlayerAttibute::M(Some(captor)) => format!("M:{}", captor),
+                TarokPlayerAttibute::Mc(loser) => format!("MC:{}", loser),
+                TarokPlayerAttibute::R(None) => "R".to_string(),
+                TarokPlayerAttibute::R(Some(offender)) => format!("R:{}", offender),
+                TarokPlayerAttibute::T => "T".to_string(),
+                TarokPlayerAttibute::Ig => "IG".to_string(),
+                TarokPlayerAttibute::Sl => "SL".to_string(),
+            },
+        }
+    }
+
+    // referenced players (M:, MC:, R:) are resolved to ids with find_player
+    pub fn from_tag(tag: &str, find_player: impl Fn(&str) -> Option<String>) -> Option<Self> {
+        if let Ok(val) = tag.parse() {
+            return Some(TarokPlayerInput::PlayerDiff(val));
+        }
+        let attr = match tag.to_uppercase().split_once(':') {
+            Some(("M", player)) => TarokPlayerAttibute::M(Some(find_player(player)?)),
+            Some(("MC", player)) => TarokPlayerAttibute::Mc(find_player(player)?),
+            Some(("R", player)) => TarokPlayerAttibute::R(Some(find_player(player)?)),
+            Some(_) => return None,
+            None => match tag.to_uppercase().as_str() {
+                "M" => TarokPlayerAttibute::M(None),
+                "R" => TarokPlayerAttibute::R(None),
+                "T" => TarokPlayerAttibute::T,
+                "IG" => TarokPlayerAttibute::Ig,
+                "SL" => TarokPlayerAttibute::Sl,
+                _ => return None,
+            },
+        };
+        Some(TarokPlayerInput::PlayerAttribute(attr))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TarokGame {
     I3,
     I2,
@@ -29,7 +96,90 @@ pub enum TarokGame {
     BVS3,
     BVS2,
     BVS1,
+    #[allow(clippy::upper_case_acronyms)] // score sheet notation
     BVSB,
+    OB,
+    BV,
+    VL,
+}
+
+impl TarokGame {
+    pub fn all() -> &'static [TarokGame] {
+        &[
+            TarokGame::I3, TarokGame::I2, TarokGame::I1, TarokGame::S3, TarokGame::S2, TarokGame::S1, 
+            TarokGame::SB, TarokGame::KL, TarokGame::B, TarokGame::P, TarokGame::BVI3, TarokGame::BVI2, 
+            TarokGame::BVI1, TarokGame::BVS3, TarokGame::BVS2, TarokGame::BVS1, TarokGame::BVSB, 
+            TarokGame::OB, TarokGame::BV, TarokGame::VL,
+        ]
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            TarokGame::I3 => "I3",
+            TarokGame::I2 => "I2",
+            TarokGame::I1 => "I1",
+            TarokGame::S3 => "S3",
+            TarokGame::S2 => "S2",
+            TarokGame::S1 => "S1",
+            TarokGame::SB => "SB",
+            TarokGame::KL => "KL",
+            TarokGame::B => "B",
+            TarokGame::P => "P",
+            TarokGame::BVI3 => "BVI3",
+            TarokGame::BVI2 => "BVI2",
+            TarokGame::BVI1 => "BVI1",
+            TarokGame::BVS3 => "BVS3",
+            TarokGame::BVS2 => "BVS2",
+            TarokGame::BVS1 => "BVS1",
+            TarokGame::BVSB => "BVSB",
+            TarokGame::OB => "OB",
+            TarokGame::BV => "BV",
+            TarokGame::VL => "VL",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TarokGame::I3 => "Three",
+            TarokGame::I2 => "Two",
+            TarokGame::I1 => "One",
+            TarokGame::S3 => "Solo three",
+            TarokGame::S2 => "Solo two",
+            TarokGame::S1 => "Solo one",
+            TarokGame::SB => "Solo without",
+            TarokGame::KL => "Klop",
+            TarokGame::B => "Beggar",
+            TarokGame::P => "Piccolo",
+            TarokGame::BVI3 => "Colour valat three",
+            TarokGame::BVI2 => "Colour valat two",
+            TarokGame::BVI1 => "Colour valat one",
+            TarokGame::BVS3 => "Colour valat solo three",
+            TarokGame::BVS2 => "Colour valat solo two",
+            TarokGame::BVS1 => "Colour valat solo one",
+            TarokGame::BVSB => "Colour valat solo without",
+            TarokGame::OB => "Open beggar",
+            TarokGame::BV => "Colour valat",
+            TarokGame::VL => "Valat",
+        }
+    }
+
+    // family of contracts, used to colour rounds in the chart
+    pub fn group(&self) -> &'static str {
+        match self {
+            TarokGame::I3 | TarokGame::I2 | TarokGame::I1 => "Normal",
+            TarokGame::S3 | TarokGame::S2 | TarokGame::S1 | TarokGame::SB => "Solo",
+            TarokGame::KL => "Klop",
+            TarokGame::B | TarokGame::OB | TarokGame::P => "Beggar",
+            TarokGame::BVI3 | TarokGame::BVI2 | TarokGame::BVI1 | TarokGame::BVS3 | TarokGame::BVS2 
+                | TarokGame::BVS1 | TarokGame::BVSB | TarokGame::BV => "Colour valat",
+            TarokGame::VL => "Valat",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<TarokGame> {
+        let code = code.to_uppercase();
+        TarokGame::all().iter().find(|g| g.code() == code).copied()
+    }
 }
 
 #[derive(Debug)]
@@ -39,17 +189,53 @@ pub enum TarokGameAttribute {
     V,
     T,
     K,
+    #[allow(clippy::upper_case_acronyms)] // score sheet notation
     NZP,
+    #[allow(clippy::upper_case_acronyms)]
     NZK,
     NV,
     NT,
     NK,
 }
 
+impl TarokGameAttribute {
+    pub fn code(&self) -> &'static str {
+        match self {
+            TarokGameAttribute::ZP => "ZP",
+            TarokGameAttribute::ZK => "ZK",
+            TarokGameAttribute::V => "V",
+            TarokGameAttribute::T => "T",
+            TarokGameAttribute::K => "K",
+            TarokGameAttribute::NZP => "NZP",
+            TarokGameAttribute::NZK => "NZK",
+            TarokGameAttribute::NV => "NV",
+            TarokGameAttribute::NT => "NT",
+            TarokGameAttribute::NK => "NK",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<TarokGameAttribute> {
+        match code.to_uppercase().as_str() {
+            "ZP" => Some(TarokGameAttribute::ZP),
+            "ZK" => Some(TarokGameAttribute::ZK),
+            "V" => Some(TarokGameAttribute::V),
+            "T" => Some(TarokGameAttribute::T),
+            "K" => Some(TarokGameAttribute::K),
+            "NZP" => Some(TarokGameAttribute::NZP),
+            "NZK" => Some(TarokGameAttribute::NZK),
+            "NV" => Some(TarokGameAttribute::NV),
+            "NT" => Some(TarokGameAttribute::NT),
+            "NK" => Some(TarokGameAttribute::NK),
+            _ => None
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum TarokPlayerAttibute {
-    M,
-    R,
+    M(Option<String>), // lost the mond, optionally to the player with the given id
+    Mc(String), // captured the mond of the player with the given id
+    R(Option<String>), // made a renonce, or (with an id) the opponent with the given id made one
     T,
     Ig,
     Sl,
@@ -59,4 +245,20 @@ pub enum TarokPlayerAttibute {
 pub enum Radlc {
     Avalible,
     Used,
+}
+impl Radlc {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Radlc::Avalible => "available",
+            Radlc::Used => "used",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Radlc> {
+        match code {
+            "available" => Some(Radlc::Avalible),
+            "used" => Some(Radlc::Used),
+            _ => None,
+        }
+    }
 }
\ No newline at end of file
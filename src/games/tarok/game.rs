@@ -1,59 +1,168 @@
-use std::{collections::HashMap, io::{Error, ErrorKind}};
-use chrono::Utc;
+use std::{collections::HashMap, io::Error};
 
-use crate::{core::{traits::{CheckName, Game}, message_helper::extract_message_text, database::user_operations::get_user_by_name}, models::user::User};
+use uuid::Uuid;
 
-use super::{enums::{TarokGameInput, TarokGame, TarokGameAttribute, TarokPlayerAttibute, TarokPlayerInput, Radlc}, html_helper::build_score_table_html};
+use crate::{core::{traits::{CheckName, Game, AchievementRule}, score_sheet::ScoreSheet, export::{GameExport, RoundDetail}, achievements::generic_achievement_rules, score_image::ScoreGrid, html_template::Theme, message_helper::extract_message_text, database::user_operations::get_user_by_name}, models::{user::User, round::Round}};
+
+use super::{enums::{TarokGameInput, TarokGame, TarokGameAttribute, TarokPlayerAttibute, TarokPlayerInput, Radlc}, html_helper::{build_score_table_html, TarokSheet}, image_helper::build_score_grid, rules::{TarokRules, RenonceMode, load_rules, save_rules}, breakdown::{RoundBreakdown, player_input_label}, achievements::{MostContractsDeclared, FirstColourValat}};
 
 pub struct Tarok {
+    id: String,
+    chat_id: String,
     players: Vec<User>,
     radlci: HashMap<String, Vec<Radlc>>,
     score: HashMap<String, Vec<Option<i32>>>,
     player_attributes: HashMap<String, Vec<Option<Vec<TarokPlayerInput>>>>,
     game_attributes: Vec<Vec<TarokGameInput>>,
     round: i32,
+    rules: TarokRules,
+    breakdowns: Vec<RoundBreakdown>,
+    seating: Vec<User>,
+    dealer: usize,
+    dealers: Vec<Option<String>>,
+    round_rows: Vec<Round>,     // statistic rows not yet handed out by take_round_rows
 }
 
 impl Tarok {
     pub fn new() -> Self {
         Self {
+            id: Uuid::new_v4().to_string(),
+            chat_id: "".to_string(),
             players: vec![],
             radlci: HashMap::new(),
             score: HashMap::new(),
             player_attributes: HashMap::new(),
             game_attributes: Vec::new(),
-            round: 0
+            round: 0,
+            rules: TarokRules::new(),
+            breakdowns: Vec::new(),
+            seating: vec![],
+            dealer: 0,
+            dealers: Vec::new(),
+            round_rows: Vec::new(),
         }
     }
-}
 
-impl CheckName for Tarok {
-    fn get_reserved_terms(&self) -> &'static [&'static str] {
-        &[
-            "I3", "I2", "I1", "S3", "S2", "S1", "SB", "KL", "B", "P", "BVI3", "BVI2", "BVI1", 
-            "BVS3", "BVS2", "BVS1", "BVSB", "ZP", "ZK", "V", "T", "K", "NZP", "NZK", "NV", 
-            "NT", "NK", "M", "R", "T", "Ig", "Sl"
-        ]
+    pub fn seat_players(&mut self, seating: Vec<User>) -> String {
+        handle_new_users(
+            &seating, 
+            &mut self.players, 
+            &mut self.score, 
+            &mut self.player_attributes,
+            &self.round,
+            &mut self.radlci,
+        );
+        self.seating = seating;
+        self.dealer = 0;
+        format!(
+            "Seating: {}\n{}", 
+            self.seating.iter().map(|u| u.name.clone()).collect::<Vec<String>>().join(", "),
+            dealer_status(&self.seating, self.dealer),
+        )
+    }
+
+    // Rebuilds a game from an export whose player ids already match the given players.
+    // Breakdowns of the imported rounds are not known, only the sheets are restored.
+    pub fn import(chat_id: String, export: &GameExport, players: Vec<User>) -> Result<Self, std::io::Error> {
+        let mut game = Tarok::new();
+        // exports from before the rules were kept are continued with the rules of the chat
+        game.rules = match &export.rules {
+            Some(rules) => rules.clone(),
+            None => load_rules(chat_id.clone())?,
+        };
+        game.chat_id = chat_id;
+        game.round = export.rounds;
+        let find_player = |reference: &str| players
+            .iter()
+            .find(|p| p.name == reference.to_uppercase() || p.id == reference)
+            .map(|p| p.id.clone());
+        for (index, tags) in export.game_attributes.iter().enumerate() {
+            let mut inputs = vec![];
+            for tag in tags.iter() {
+                match TarokGameInput::from_tag(tag) {
+                    Some(input) => inputs.push(input),
+                    None => return Err(Error::other(format!("Unknown game attribute {} in round {}", tag, index + 1))),
+                };
+            }
+            game.game_attributes.push(inputs);
+        }
+        for player in players.iter() {
+            let mut score = export.score.get(&player.id).cloned().unwrap_or_default();
+            score.resize(export.rounds as usize, None);
+            game.score.insert(player.id.clone(), score);
+
+            let mut attributes = vec![];
+            for (index, tags) in export.player_attributes.get(&player.id).into_iter().flatten().enumerate() {
+                let tags = match tags {
+                    Some(tags) => tags,
+                    None => {
+                        attributes.push(None);
+                        continue;
+                    },
+                };
+                let mut inputs = vec![];
+                for tag in tags.iter() {
+                    match TarokPlayerInput::from_tag(tag, find_player) {
+                        Some(input) => inputs.push(input),
+                        None => return Err(Error::other(format!("Unknown attribute {} of {} in round {}", tag, player.name, index + 1))),
+                    };
+                }
+                attributes.push(Some(inputs));
+            }
+            fill_gaps_until_round(&mut attributes, &(export.rounds + 1));
+            game.player_attributes.insert(player.id.clone(), attributes);
+
+            let mut radlci = vec![];
+            for code in export.radlci.get(&player.id).into_iter().flatten() {
+                match Radlc::from_code(code) {
+                    Some(radlc) => radlci.push(radlc),
+                    None => return Err(Error::other(format!("Unknown radlc {} of {}", code, player.name))),
+                };
+            }
+            game.radlci.insert(player.id.clone(), radlci);
+        }
+        game.dealers = export.dealers.clone();
+        game.dealers.resize(export.rounds as usize, None);
+        // exports without a seating (csv, older games) leave the table unseated until /seat
+        if let (Some(seating), Some(dealer)) = (&export.seating, export.dealer) {
+            let seated: Vec<User> = seating
+                .iter()
+                .filter_map(|id| players.iter().find(|p| &p.id == id).cloned())
+                .collect();
+            if !seated.is_empty() && seated.len() == seating.len() {
+                game.dealer = dealer % seated.len();
+                game.seating = seated;
+            }
+        }
+        game.players = players;
+        Ok(game)
     }
-}
 
+    // round in the /round syntax: /round I3,15,T JAN,IG ANA,SL
+    pub fn play_round(&mut self, chat_id: String, text: String) -> Result<String, std::io::Error> {
+        self.chat_id = chat_id;
 
-impl Game for Tarok {
-    fn start_game(&mut self) -> Result<String, std::io::Error> {
-        Ok("Started game of Tarok!".to_string())
-    }
+        let users = extract_round_users(&text)?;
 
-    fn handle_round(&mut self, message: teloxide::types::Message) -> Result<String, std::io::Error> {
-        let text = match extract_message_text(&message) {
-            Some(text) => text,
-            None => return Err(Error::new(ErrorKind::Other, "Failed to extract message text".to_string()))
+        // opponents referenced by the team (mond captors, renonce offenders) 
+        // are not part of the playing team, but still need a column on the sheet
+        let referenced_users = extract_round_referenced_users(&text)?;
+        
+        let game_attributes: Vec<TarokGameInput> = match extract_game_attributes(&text) {
+            Ok(attr) => attr,
+            Err(e) => return Err(Error::other(format!("Failed to extract game attributes: {}", e)))
         };
 
-        let users = match extract_round_users(&text) {
-            Ok(users) => users,
-            Err(e) => return Err(e),
+        let mut player_attributes: HashMap<String, Vec<TarokPlayerInput>> = match extract_player_attributes(&text) {
+            Ok(attr) => attr,
+            Err(e) => return Err(Error::other(format!("Failed to extract player attributes: {}", e)))
         };
 
+        // a rejected round must not add players or use up radlci, so everything is checked first
+        if let Err(e) = round_validity_check(&users, &game_attributes, &player_attributes) {
+            return Err(Error::other(format!("{}", e)));
+        }
+
         handle_new_users(
             &users, 
             &mut self.players, 
@@ -62,28 +171,34 @@ impl Game for Tarok {
             &self.round,
             &mut self.radlci,
         );
-        
-        let mut game_attributes: Vec<TarokGameInput> = match extract_game_attributes(&text) {
-            Ok(attr) => attr,
-            Err(e) => return Err(Error::new(ErrorKind::Other, format!("Failed to extract game attributes: {}", e)))
-        };
 
-        let mut player_attributes: HashMap<String, Vec<TarokPlayerInput>> = match extract_player_attributes(&text) {
-            Ok(attr) => attr,
-            Err(e) => return Err(Error::new(ErrorKind::Other, format!("Failed to extract player attributes: {}", e)))
-        };
+        handle_new_users(
+            &referenced_users, 
+            &mut self.players, 
+            &mut self.score, 
+            &mut self.player_attributes,
+            &self.round,
+            &mut self.radlci,
+        );
 
-        let score_change = match handle_game(
+        let mut breakdown = RoundBreakdown::new(self.round + 1);
+        let (score_change, notes) = match handle_game(
             &users,
-            &mut self.score, 
+            &self.players,
             &mut self.radlci,
             &mut player_attributes,
-            &mut game_attributes,
+            &game_attributes,
+            &self.rules,
+            &mut breakdown,
         ) {
             Ok(st) => st,
-            Err(e) => return Err(Error::new(ErrorKind::Other, format!("Failed to calculate round: {}", e)))
+            Err(e) => return Err(Error::other(format!("Failed to calculate round: {}", e)))
         };
         increment_round(&mut self.round);
+        // remember who dealt this round and pass the cards on
+        self.dealers.push(self.seating.get(self.dealer).map(|u| u.id.clone()));
+        advance_dealer(&self.seating, &mut self.dealer, breakdown.contract, &self.rules);
+        self.breakdowns.push(breakdown);
         // save game attributes and player attributes to global sheets
         if let Err(e) = save_round_to_sheets(
             player_attributes, 
@@ -95,52 +210,360 @@ impl Game for Tarok {
             &mut self.score,
 
         ) {
-            return Err(Error::new(ErrorKind::Other, format!("Error saving round: {}", e)))
+            return Err(Error::other(format!("Error saving round: {}", e)))
+        };
+        // keep the round for statistics, the caller persists it (see take_round_rows)
+        let participants: Vec<User> = users.iter().chain(referenced_users.iter()).cloned().collect();
+        let rows = self.round_to_rows(&participants);
+        self.round_rows.extend(rows);
+        let mut response = format!(
+            "{}\n{}", 
+            generate_response(&self.players, score_change, notes), 
+            radlci_status(&self.players, &self.radlci)
+        );
+        if !self.seating.is_empty() {
+            response = format!("{}\n{}", response, dealer_status(&self.seating, self.dealer));
+        }
+        Ok(response)
+    }
+
+    // one row per player of the latest round, for statistics,
+    // participants are the players named in the round
+    fn round_to_rows(&self, participants: &[User]) -> Vec<Round> {
+        let index = (self.round - 1) as usize;
+        let game_attributes = match self.game_attributes.get(index) {
+            Some(attrs) => attrs.iter().map(|a| a.to_tag()).collect::<Vec<String>>().join(","),
+            None => "".to_string(),
+        };
+        let (game, won, radlc_multiplier) = match self.breakdowns.last() {
+            Some(b) => (b.contract, b.game_points >= 0, b.radlc_multiplier),
+            None => (None, true, 1),
+        };
+        let radlc_gained = match game {
+            Some(g) => self.rules.radlc_contracts.contains(&g),
+            None => false,
+        };
+        let mut rows = vec![];
+        for player in self.players.iter() {
+            // the defenders played the round as well, so everyone at the table did
+            // (without a seating every player of the game is taken to be at the table)
+            let played = self.seating.is_empty() || self.seating.contains(player) || participants.contains(player);
+            let player_attributes: Vec<String> = match self.player_attributes.get(&player.id).and_then(|a| a.get(index)) {
+                Some(Some(attrs)) => attrs.iter().map(|a| a.to_tag()).collect(),
+                _ => vec![],
+            };
+            let player_score = match self.score.get(&player.id).and_then(|s| s.get(index)) {
+                Some(Some(val)) => val.to_string(),
+                _ => "".to_string(),
+            };
+            let declarer = player_attributes.contains(&"IG".to_string());
+            let tags = [
+                format!("game={}", game.map(|g| g.code()).unwrap_or("")),
+                format!("attrs={}", game_attributes),
+                format!("player={}", player_attributes.join(",")),
+                format!("score={}", player_score),
+                format!("won={}", won as i32),
+                format!("radlc_gained={}", radlc_gained as i32),
+                format!("radlc_used={}", (declarer && radlc_multiplier > 1) as i32),
+                format!("played={}", played as i32),
+            ].join(";");
+            rows.push(Round::from(self.chat_id.clone(), self.round.to_string(), self.id.clone(), player.id.clone(), tags));
+        }
+        rows
+    }
+
+    fn sheet<'a>(&'a self, sum_by_player: &'a HashMap<String, (i32, i32, i32)>) -> TarokSheet<'a> {
+        TarokSheet {
+            players: &self.players,
+            score: &self.score,
+            rounds: self.round,
+            sum_by_player,
+            radlci: &self.radlci,
+            dealers: &self.dealers,
+            player_attributes: &self.player_attributes,
+            rules: &self.rules,
+        }
+    }
+}
+
+impl CheckName for Tarok {
+    fn get_reserved_terms(&self) -> &'static [&'static str] {
+        &[
+            "I3", "I2", "I1", "S3", "S2", "S1", "SB", "KL", "B", "P", "BVI3", "BVI2", "BVI1", 
+            "BVS3", "BVS2", "BVS1", "BVSB", "OB", "BV", "VL", "ZP", "ZK", "V", "T", "K", "NZP", "NZK", "NV", 
+            "NT", "NK", "M", "R", "T", "Ig", "Sl"
+        ]
+    }
+}
+
+
+impl Game for Tarok {
+    fn start_game(&mut self, message: teloxide::types::Message) -> Result<String, std::io::Error> {
+        self.chat_id = message.chat.id.to_string();
+        self.rules = load_rules(self.chat_id.clone())?;
+        let text = match extract_message_text(&message) {
+            Some(text) => text,
+            None => return Ok("Started game of Tarok!".to_string()),
+        };
+        // optional seating order: /newgame JAN ANA MIHA PETER
+        let seating = parse_seating(&text)?;
+        if seating.is_empty() {
+            return Ok("Started game of Tarok!".to_string());
+        }
+        Ok(format!("Started game of Tarok!\n{}", self.seat_players(seating)))
+    }
+
+    fn handle_round(&mut self, message: teloxide::types::Message) -> Result<String, std::io::Error> {
+        let text = match extract_message_text(&message) {
+            Some(text) => text,
+            None => return Err(Error::other("Failed to extract message text".to_string()))
         };
-        Ok(generate_response(&users, score_change))
+        self.play_round(message.chat.id.to_string(), text)
     }
 
-    fn end_game(mut self: Box<Self>) -> Result<String, std::io::Error> {
+    fn end_game(mut self: Box<Self>, theme: &Theme) -> Result<String, std::io::Error> {
         for player in self.players.iter() {
             if let Some(score) = self.score.get_mut(&player.id.to_string()) {
                 fill_gaps_until_round(score, &(self.round + 1));
             } else {
-                return Err(Error::new(ErrorKind::Other, format!("Something went wrong on entering user {} score for the missing rounds", player.name)))
+                return Err(Error::other(format!("Something went wrong on entering user {} score for the missing rounds", player.name)))
             };
         }
-        let sum_by_player: HashMap<String, (i32, i32, i32)> = sum_score_by_players(&self.score, &self.players, &mut self.radlci);
-        Ok(build_score_table_html(
-            &self.players, 
-            &self.score, 
-            self.round, 
-            sum_by_player, 
-            &self.radlci,
-            &mut self.player_attributes, 
-            &mut self.game_attributes,
-        ))
+        let sum_by_player: HashMap<String, (i32, i32, i32)> = sum_score_by_players(&self.score, &self.players, &mut self.radlci, &self.rules);
+        Ok(build_score_table_html(&self.sheet(&sum_by_player), theme))
     }
 
-    fn get_state(&mut self) -> Result<String, std::io::Error> {
+    fn get_state(&mut self, theme: &Theme) -> Result<String, std::io::Error> {
         for player in self.players.iter() {
             if let Some(score) = self.score.get_mut(&player.id.to_string()) {
                 fill_gaps_until_round(score, &(self.round + 1));
             } else {
-                return Err(Error::new(ErrorKind::Other, format!("Something went wrong on entering user {} score for the missing rounds", player.name)))
+                return Err(Error::other(format!("Something went wrong on entering user {} score for the missing rounds", player.name)))
             };
         }
-        let sum_by_player: HashMap<String, (i32, i32, i32)> = sum_score_by_players(&self.score, &self.players, &mut self.radlci);
-        Ok(build_score_table_html(
-            &self.players, 
-            &self.score, 
-            self.round, 
-            sum_by_player, 
-            &self.radlci,
-            &mut self.player_attributes, 
-            &mut self.game_attributes,
-        ))
+        let sum_by_player: HashMap<String, (i32, i32, i32)> = sum_score_by_players(&self.score, &self.players, &mut self.radlci, &self.rules);
+        Ok(build_score_table_html(&self.sheet(&sum_by_player), theme))
+    }
+
+    fn score_grid(&mut self) -> Result<ScoreGrid, std::io::Error> {
+        for player in self.players.iter() {
+            if let Some(score) = self.score.get_mut(&player.id.to_string()) {
+                fill_gaps_until_round(score, &(self.round + 1));
+            } else {
+                return Err(Error::other(format!("Something went wrong on entering user {} score for the missing rounds", player.name)))
+            };
+        }
+        let sum_by_player: HashMap<String, (i32, i32, i32)> = sum_score_by_players(&self.score, &self.players, &mut self.radlci, &self.rules);
+        Ok(build_score_grid(&self.sheet(&sum_by_player)))
+    }
+
+    fn score_sheet(&mut self) -> Result<ScoreSheet, std::io::Error> {
+        for player in self.players.iter() {
+            if let Some(score) = self.score.get_mut(&player.id.to_string()) {
+                fill_gaps_until_round(score, &(self.round + 1));
+            } else {
+                return Err(Error::other(format!("Something went wrong on entering user {} score for the missing rounds", player.name)))
+            };
+        }
+        let sum_by_player = sum_score_by_players(&self.score, &self.players, &mut self.radlci, &self.rules);
+        Ok(ScoreSheet {
+            game_id: self.id.clone(),
+            game_type: "tarok".to_string(),
+            players: self.players.clone(),
+            score: self.score.clone(),
+            rounds: self.round,
+            totals: sum_by_player
+                .iter()
+                .map(|(id, (sum, _, _))| (id.clone(), *sum))
+                .collect(),
+        })
+    }
+
+    fn export(&mut self) -> Result<GameExport, std::io::Error> {
+        let mut export = GameExport::from_sheet(self.score_sheet()?);
+        export.game_attributes = self.game_attributes
+            .iter()
+            .map(|round| round.iter().map(|a| a.to_tag()).collect())
+            .collect();
+        export.player_attributes = self.player_attributes
+            .iter()
+            .map(|(id, rounds)| (id.clone(), rounds
+                .iter()
+                .map(|round| round.as_ref().map(|atrs| atrs.iter().map(|a| tag_with_names(a.to_tag(), &self.players)).collect()))
+                .collect()
+            ))
+            .collect();
+        export.radlci = self.radlci
+            .iter()
+            .map(|(id, radlci)| (id.clone(), radlci.iter().map(|r| r.code().to_string()).collect()))
+            .collect();
+        export.dealers = self.dealers.clone();
+        if !self.seating.is_empty() {
+            export.seating = Some(self.seating.iter().map(|u| u.id.clone()).collect());
+            export.dealer = Some(self.dealer);
+        }
+        export.rules = Some(self.rules.clone());
+        export.details = self.breakdowns
+            .iter()
+            .map(|b| RoundDetail {
+                round: b.round,
+                contract: b.contract.map_or("".to_string(), |c| c.code().to_string()),
+                base: b.base,
+                diff: b.diff,
+                bonuses: b.bonuses.clone(),
+                game_points: b.game_points,
+            })
+            .collect();
+        Ok(export)
+    }
+
+    fn take_round_rows(&mut self) -> Vec<Round> {
+        std::mem::take(&mut self.round_rows)
+    }
+
+    // final totals and forfeited radlci are kept next to the rounds
+    fn end_of_game_rows(&mut self) -> Vec<Round> {
+        if self.round == 0 {
+            return vec![];
+        }
+        let sum_by_player = sum_score_by_players(&self.score, &self.players, &mut self.radlci, &self.rules);
+        end_of_game_rows(&self.chat_id, &self.id, &self.players, &sum_by_player, &self.radlci)
+    }
+
+    fn seat(&mut self, message: teloxide::types::Message) -> Result<String, std::io::Error> {
+        let text = match extract_message_text(&message) {
+            Some(text) => text,
+            None => return Err(Error::other("Failed to extract message text".to_string()))
+        };
+        // /seat JAN ANA MIHA PETER, the first player deals the next round
+        let seating = parse_seating(&text)?;
+        if seating.is_empty() {
+            return match self.seating.is_empty() {
+                true => Err(Error::other("Usage: /seat <players in seating order>".to_string())),
+                false => Ok(format!(
+                    "Seating: {}\n{}",
+                    self.seating.iter().map(|u| u.name.clone()).collect::<Vec<String>>().join(", "),
+                    dealer_status(&self.seating, self.dealer),
+                )),
+            };
+        }
+        Ok(self.seat_players(seating))
+    }
+
+    fn handle_rules(&mut self, message: teloxide::types::Message) -> Result<String, std::io::Error> {
+        let text = match extract_message_text(&message) {
+            Some(text) => text,
+            None => return Err(Error::other("Failed to extract message text".to_string()))
+        };
+        let fragments: Vec<&str> = text.split_whitespace().skip(1).collect();
+        match fragments.len() {
+            0 => Ok(format!("Current rules:\n{}", self.rules.describe())),
+            2 => {
+                self.rules.set(fragments[0], fragments[1])?;
+                // kept for the next games of the chat as well
+                save_rules(message.chat.id.to_string(), &self.rules)?;
+                Ok(format!("Rule {} set to {}", fragments[0], fragments[1]))
+            },
+            _ => Err(Error::other("Usage: /rules or /rules <rule> <value>".to_string())),
+        }
+    }
+
+    fn manage_radlci(&mut self, message: teloxide::types::Message) -> Result<String, std::io::Error> {
+        let text = match extract_message_text(&message) {
+            Some(text) => text,
+            None => return Err(Error::other("Failed to extract message text".to_string()))
+        };
+        let fragments: Vec<&str> = text.split_whitespace().skip(1).collect();
+        if fragments.is_empty() {
+            return Ok(radlci_status(&self.players, &self.radlci));
+        }
+        if fragments.len() != 2 {
+            return Err(Error::other("Usage: /radlc or /radlc add|remove <player>".to_string()));
+        }
+        let user = parse_user_from_fragment(&fragments[1].to_string())?;
+        handle_new_users(
+            std::slice::from_ref(&user), 
+            &mut self.players, 
+            &mut self.score, 
+            &mut self.player_attributes,
+            &self.round,
+            &mut self.radlci,
+        );
+        let player_radlci = match self.radlci.get_mut(&user.id) {
+            Some(radlci) => radlci,
+            None => return Err(Error::other("Player does not have a radlc vector!".to_string())),
+        };
+        match fragments[0].to_lowercase().as_str() {
+            "add" => player_radlci.push(Radlc::Avalible),
+            "remove" => match player_radlci.iter().rposition(|r| matches!(r, Radlc::Avalible)) {
+                Some(index) => { player_radlci.remove(index); },
+                None => return Err(Error::other(format!("{} has no unused radlc", user.name))),
+            },
+            _ => return Err(Error::other("Usage: /radlc or /radlc add|remove <player>".to_string())),
+        };
+        Ok(radlci_status(&self.players, &self.radlci))
     }
 
-    fn generate_file_name(&self) -> String { format!("{}_tarok.html", Utc::now()) }
+    fn explain_round(&self, message: teloxide::types::Message) -> Result<String, std::io::Error> {
+        let text = match extract_message_text(&message) {
+            Some(text) => text,
+            None => return Err(Error::other("Failed to extract message text".to_string()))
+        };
+        let round: usize = match text.split_whitespace().nth(1).map(|r| r.parse()) {
+            Some(Ok(round)) => round,
+            _ => return Err(Error::other("Usage: /explain <round>".to_string())),
+        };
+        // imported rounds have no breakdown, so they are looked up by their number
+        match self.breakdowns.iter().find(|b| b.round as usize == round) {
+            Some(breakdown) => Ok(breakdown.describe(&self.players)),
+            None => Err(Error::other(format!("No round {} to explain", round))),
+        }
+    }
+
+    fn achievement_rules(&self) -> Vec<Box<dyn AchievementRule>> {
+        let mut declared: HashMap<String, i32> = HashMap::new();
+        let mut colour_valats = vec![];
+        for (player_id, rounds) in self.player_attributes.iter() {
+            for (index, attributes) in rounds.iter().enumerate() {
+                let declarer = match attributes {
+                    Some(attributes) => attributes
+                        .iter()
+                        .any(|a| matches!(a, TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::Ig))),
+                    None => false,
+                };
+                // klop has no declarer, its first player is only tagged as one
+                let klop = self.game_attributes
+                    .get(index)
+                    .and_then(|attributes| find_tarok_game(attributes)) == Some(TarokGame::KL);
+                if !declarer || klop {
+                    continue;
+                }
+                *declared.entry(player_id.clone()).or_insert(0) += 1;
+                if let Some(breakdown) = self.breakdowns.iter().find(|b| b.round as usize == index + 1) {
+                    if breakdown.contract.is_some_and(is_colour_valat) && breakdown.game_points > 0 {
+                        colour_valats.push((player_id.clone(), breakdown.round));
+                    }
+                }
+            }
+        }
+        colour_valats.sort_by_key(|(_, round)| *round);
+        let mut rules = generic_achievement_rules();
+        rules.push(Box::new(MostContractsDeclared { declared }));
+        rules.push(Box::new(FirstColourValat { won: colour_valats }));
+        rules
+    }
+
+    fn round_groups(&self) -> Vec<Option<String>> {
+        self.game_attributes
+            .iter()
+            .map(|round| round.iter().find_map(|a| match a {
+                TarokGameInput::TarokGame(game) => Some(game.group().to_string()),
+                _ => None,
+            }))
+            .collect()
+    }
+
+    fn generate_file_name(&self) -> String { "tarok.html".to_string() }
 }
 
 
@@ -149,6 +572,7 @@ fn sum_score_by_players(
     score: &HashMap<String, Vec<Option<i32>>>, 
     players: &[User], 
     radlci: &mut HashMap<String, Vec<Radlc>>,
+    rules: &TarokRules,
 ) -> HashMap<String, (i32, i32, i32)> {
     let mut totals = HashMap::new();
     for player in players.iter() {
@@ -180,7 +604,7 @@ fn sum_score_by_players(
                 for radl in v.iter() {
                     if let Radlc::Avalible = radl { unused_radlci += 1; }
                 }
-                sum -= 100 * unused_radlci;
+                sum -= rules.radlc_penalty * unused_radlci;
             }
             
             totals.insert(player.id.clone(), (sum, min, max));
@@ -191,7 +615,7 @@ fn sum_score_by_players(
     totals
 }
 
-fn generate_response(players: &[User], status: HashMap<String, i32>) -> String {
+fn generate_response(players: &[User], status: HashMap<String, i32>, notes: Vec<String>) -> String {
     let mut out = "".to_string();
     for (player, score) in status.into_iter() {
         let user = match extract_user_by_id(players, player) {
@@ -200,41 +624,118 @@ fn generate_response(players: &[User], status: HashMap<String, i32>) -> String {
         };
         out = format!("{}\n{} -> {}", out, user.name, score);
     }
+    for note in notes.iter() {
+        out = format!("{}\n{}", out, note);
+    }
     out
 }
 
-fn extract_user_by_id(users: &[User], id: String) -> Option<&User> {
-    for user in users.iter() {
-        if user.id == id {
-            return Some(user);
-        }
+fn end_of_game_rows(
+    chat_id: &str,
+    game_id: &str,
+    players: &[User],
+    sum_by_player: &HashMap<String, (i32, i32, i32)>,
+    radlci: &HashMap<String, Vec<Radlc>>,
+) -> Vec<Round> {
+    let mut rows = vec![];
+    for player in players.iter() {
+        let total = match sum_by_player.get(&player.id) {
+            Some((sum, _, _)) => *sum,
+            None => 0,
+        };
+        let (unused, _) = match radlci.get(&player.id) {
+            Some(radlci) => count_radlci(radlci),
+            None => (0, 0),
+        };
+        let tags = format!("total={};radlc_forfeited={}", total, unused);
+        rows.push(Round::from(chat_id.to_string(), "end".to_string(), game_id.to_string(), player.id.clone(), tags));
     }
-    None
+    rows
+}
+
+// M:<id> -> M:JAN, so exported tags read like the /round syntax
+fn tag_with_names(tag: String, players: &[User]) -> String {
+    match tag.split_once(':') {
+        Some((attribute, id)) => format!("{}:{}", attribute, player_name_by_id(players, id)),
+        None => tag,
+    }
+}
+
+// names after the command in seating order
+fn parse_seating(text: &str) -> Result<Vec<User>, Error> {
+    let mut seating = vec![];
+    for name in text.split_whitespace().skip(1) {
+        match parse_user_from_fragment(&name.to_string()) {
+            Ok(user) if seating.contains(&user) => return Err(Error::other(format!("{} is seated twice", user.name))),
+            Ok(user) => seating.push(user),
+            Err(e) => return Err(Error::other(format!("Failed parsing seating: {}", e))),
+        };
+    }
+    Ok(seating)
+}
+
+fn dealer_status(seating: &[User], dealer: usize) -> String {
+    if seating.is_empty() {
+        return "".to_string();
+    }
+    let dealer_name = &seating[dealer % seating.len()].name;
+    let forehand_name = &seating[(dealer + 1) % seating.len()].name;
+    format!("{} deals next, {} is forehand", dealer_name, forehand_name)
+}
+
+fn advance_dealer(seating: &[User], dealer: &mut usize, game: Option<TarokGame>, rules: &TarokRules) {
+    if seating.is_empty() {
+        return;
+    }
+    // some groups let the same dealer deal again after a klop
+    if game == Some(TarokGame::KL) && rules.klop_dealer_repeats {
+        return;
+    }
+    *dealer = (*dealer + 1) % seating.len();
+}
+
+fn radlci_status(players: &[User], radlci: &HashMap<String, Vec<Radlc>>) -> String {
+    let mut out = "Radlci:".to_string();
+    for player in players.iter() {
+        let (unused, used) = match radlci.get(&player.id) {
+            Some(radlci) => count_radlci(radlci),
+            None => (0, 0),
+        };
+        out = format!("{}\n{}: {} unused, {} used", out, player.name, unused, used);
+    }
+    out
+}
+
+fn count_radlci(radlci: &[Radlc]) -> (i32, i32) {
+    let unused = radlci.iter().filter(|r| matches!(r, Radlc::Avalible)).count() as i32;
+    (unused, radlci.len() as i32 - unused)
+}
+
+fn extract_user_by_id(users: &[User], id: String) -> Option<&User> {
+    users.iter().find(|&user| user.id == id).map(|v| v as _)
 }
 
-fn extract_player_attributes(text: &String) -> Result<HashMap<String, Vec<TarokPlayerInput>>, Error> {
-    let fragment = match extract_round_player_fragment(&text) {
+fn extract_player_attributes(text: &str) -> Result<HashMap<String, Vec<TarokPlayerInput>>, Error> {
+    let fragment = match extract_round_player_fragment(text) {
         Some(frag) => frag,
-        None => return Err(Error::new(ErrorKind::Other, "Failed to locate player fragment".to_string())),
+        None => return Err(Error::other("Failed to locate player fragment".to_string())),
     };
     let mut out = HashMap::new();
     for player_fragment in fragment.split(' ') {
         let mut inputs = vec![];
         let user = match parse_user_from_fragment(&player_fragment.to_string()) {
             Ok(user) => user,
-            Err(e) => return Err(Error::new(ErrorKind::Other, format!("Failed parsing player from fragment: {}", e))),
+            Err(e) => return Err(Error::other(format!("Failed parsing player from fragment: {}", e))),
         };
         for player_partial in player_fragment.split(',').skip(1) {
-            let attr_option = match parse_player_attribute_fragment(player_partial) {
-                Some(val) => Some(TarokPlayerInput::PlayerAttribute(val)),
-                None => None,
-            };
-            let diff_option = match parse_diff_option_fragment(player_partial) {
-                Some(val) => Some(TarokPlayerInput::PlayerDiff(val)),
-                None => None,
+            let reference_option = match parse_player_reference_fragment(player_partial) {
+                Ok(val) => val,
+                Err(e) => return Err(Error::other(format!("Failed parsing referenced player: {}", e))),
             };
+            let attr_option = reference_option.or_else(|| parse_player_attribute_fragment(player_partial)).map(TarokPlayerInput::PlayerAttribute);
+            let diff_option = parse_diff_option_fragment(player_partial).map(TarokPlayerInput::PlayerDiff);
             match (attr_option, diff_option) {
-                (None, None) => return Err(Error::new(ErrorKind::Other, format!("Could not recognize player attribute: {}", player_partial))),
+                (None, None) => return Err(Error::other(format!("Could not recognize player attribute: {}", player_partial))),
                 (_, Some(diff)) => inputs.push(diff),
                 (Some(attr), _) => inputs.push(attr),
             }
@@ -244,27 +745,18 @@ fn extract_player_attributes(text: &String) -> Result<HashMap<String, Vec<TarokP
     Ok(out)
 }
 
-fn extract_game_attributes(text: &String) -> Result<Vec<TarokGameInput>, Error> {
+fn extract_game_attributes(text: &str) -> Result<Vec<TarokGameInput>, Error> {
     let fragment = match extract_round_game_fragment(text) {
         Some(fr) => fr,
-        None => return Err(Error::new(ErrorKind::Other, "Failed to locate game fragment".to_string())),
+        None => return Err(Error::other("Failed to locate game fragment".to_string())),
     };
     let mut inputs = vec![];
     let mut game_found = false; // only one fragment can be a game input
     let mut game_diff_found = false; // only one fragment can be a game diff input
     for partial_fragment in fragment.split(',') {
-        let mut game_option = match parse_game_option_fragment(partial_fragment) {
-            Some(val) => Some(TarokGameInput::TarokGame(val)),
-            None => None,
-        };
-        let attribute_option = match parse_attribute_option_fragment(partial_fragment) {
-            Some(val) => Some(TarokGameInput::TarokGameAttribute(val)),
-            None => None,
-        };
-        let mut diff_option = match parse_diff_option_fragment(partial_fragment) {
-            Some(val) => Some(TarokGameInput::TarokGameDiff(val)),
-            None => None,
-        };
+        let mut game_option = parse_game_option_fragment(partial_fragment).map(TarokGameInput::TarokGame);
+        let attribute_option = parse_attribute_option_fragment(partial_fragment).map(TarokGameInput::TarokGameAttribute);
+        let mut diff_option = parse_diff_option_fragment(partial_fragment).map(TarokGameInput::TarokGameDiff);
         // just to make sure only one game can be defined
         // allows us to have attibutes with same name after the game 
         // has beed specified
@@ -282,7 +774,7 @@ fn extract_game_attributes(text: &String) -> Result<Vec<TarokGameInput>, Error>
             game_diff_found = true;
         }
         match (game_option, attribute_option, diff_option) {
-            (None, None, None) => return Err(Error::new(ErrorKind::Other, format!("Could not recognize game attribute: {}", partial_fragment))),
+            (None, None, None) => return Err(Error::other(format!("Could not recognize game attribute: {}", partial_fragment))),
             (Some(val), _, _) => inputs.push(val),
             (_, Some(val), _) => inputs.push(val),
             (_, _, Some(val)) => inputs.push(val),
@@ -291,68 +783,58 @@ fn extract_game_attributes(text: &String) -> Result<Vec<TarokGameInput>, Error>
 
     match game_found {
         true => Ok(inputs),
-        false => return Err(Error::new(ErrorKind::Other, format!("No game specified."))),
+        false => Err(Error::other("No game specified.".to_string())),
     }
     
 }
 
 fn parse_player_attribute_fragment(partial_fragment: &str) -> Option<TarokPlayerAttibute> {
     match partial_fragment.to_uppercase().as_str() {
-        "M" => Some(TarokPlayerAttibute::M),
-        "R" => Some(TarokPlayerAttibute::R),
+        "M" => Some(TarokPlayerAttibute::M(None)),
+        "R" => Some(TarokPlayerAttibute::R(None)),
         "T" => Some(TarokPlayerAttibute::T),
         _ => None,
     }
 }
 
-fn parse_diff_option_fragment(partial_fragment: &str) -> Option<i32> {
-    match partial_fragment.parse() {
-        Ok(val) => Some(val),
-        Err(_) => None,
+fn parse_player_reference_fragment(partial_fragment: &str) -> Result<Option<TarokPlayerAttibute>, Error> {
+    // M:JAN -> mond lost to JAN
+    // R:JAN -> renonce made by JAN (opponent)
+    let (attribute, name) = match split_player_reference(partial_fragment) {
+        Some(reference) => reference,
+        None => return Ok(None),
+    };
+    let user = parse_user_from_fragment(&name)?;
+    match attribute.as_str() {
+        "M" => Ok(Some(TarokPlayerAttibute::M(Some(user.id)))),
+        "R" => Ok(Some(TarokPlayerAttibute::R(Some(user.id)))),
+        _ => Ok(None),
     }
 }
 
-fn parse_attribute_option_fragment(partial_fragment: &str) -> Option<TarokGameAttribute> {
-    match partial_fragment.to_uppercase().as_str() {
-        "ZP" => Some(TarokGameAttribute::ZP),
-        "ZK" => Some(TarokGameAttribute::ZK),
-        "V" => Some(TarokGameAttribute::V),
-        "T" => Some(TarokGameAttribute::T),
-        "K" => Some(TarokGameAttribute::K),
-        "NZP" => Some(TarokGameAttribute::NZP),
-        "NZK" => Some(TarokGameAttribute::NZK),
-        "NV" => Some(TarokGameAttribute::NV),
-        "NT" => Some(TarokGameAttribute::NT),
-        "NK" => Some(TarokGameAttribute::NK),
-        _ => None
+fn split_player_reference(partial_fragment: &str) -> Option<(String, String)> {
+    let upper = partial_fragment.to_uppercase();
+    let (attribute, name) = upper.split_once(':')?;
+    match attribute {
+        "M" | "R" => Some((attribute.to_string(), name.to_string())),
+        _ => None,
     }
 }
 
+fn parse_diff_option_fragment(partial_fragment: &str) -> Option<i32> {
+    partial_fragment.parse().ok()
+}
+
+fn parse_attribute_option_fragment(partial_fragment: &str) -> Option<TarokGameAttribute> {
+    TarokGameAttribute::from_code(partial_fragment)
+}
+
 fn parse_game_option_fragment(partial_fragment: &str) -> Option<TarokGame> {
-    match partial_fragment.to_uppercase().as_str() {
-        "I3" => Some(TarokGame::I3),
-        "I2" => Some(TarokGame::I2),
-        "I1" => Some(TarokGame::I1),
-        "S3" => Some(TarokGame::S3),
-        "S2" => Some(TarokGame::S2),
-        "S1" => Some(TarokGame::S1),
-        "SB" => Some(TarokGame::SB),
-        "KL" => Some(TarokGame::KL),
-        "B" => Some(TarokGame::B),
-        "P" => Some(TarokGame::P),
-        "BVI3" => Some(TarokGame::BVI3),
-        "BVI2" => Some(TarokGame::BVI2),
-        "BVI1" => Some(TarokGame::BVI1),
-        "BVS3" => Some(TarokGame::BVS3),
-        "BVS2" => Some(TarokGame::BVS2),
-        "BVS1" => Some(TarokGame::BVS1),
-        "BVSB" => Some(TarokGame::BVSB),
-        _ => None
-    }
+    TarokGame::from_code(partial_fragment)
 }
 
 fn handle_new_users(
-    users: &Vec<User>, 
+    users: &[User], 
     players: &mut Vec<User>, 
     score: &mut HashMap<String, Vec<Option<i32>>>, 
     global_player_attributes: &mut HashMap<String, Vec<Option<Vec<TarokPlayerInput>>>>,
@@ -378,637 +860,338 @@ fn fill_gaps_until_round<T>(score: &mut Vec<Option<T>>, round: &i32) {
     if score.len() < (*round) as usize {
         for _ in score.len()..(*round - 1) as usize {
             score.push(None);
-        }
-    }
-}
-
-fn extract_round_users(message_text: &String) -> Result<Vec<User>, Error> {
-    let fragment = match extract_round_player_fragment(message_text) {
-        Some(fragment) => fragment,
-        None => return Err(Error::new(ErrorKind::Other, "Can't find any users to parse :(".to_string())),
-    };
-    match parse_users_from_fragment(&fragment) {
-        Ok(users) => Ok(users),
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Failed parsing players: {}", e))),
-    }
-}
-
-fn parse_users_from_fragment(fragment: &String) -> Result<Vec<User>, Error> {
-    let mut users = vec![];
-    for user_framgent in fragment.split(' ') {
-        // extract name from user fragment (JAN,M -> JAN)
-        match parse_user_from_fragment(&user_framgent.to_string()) {
-            Ok(user) => users.push(user),
-            Err(e) => return Err(Error::new(ErrorKind::Other, format!("Failed parsing player: {}", e))),
-        };
-    }
-    Ok(users)
-}
-
-fn parse_user_from_fragment(fragment: &String) -> Result<User, Error> {
-    let user_name = match fragment.split(',').nth(0) {
-        Some(name) => name,
-        None => return Err(Error::new(ErrorKind::Other, format!("Can't parse a users :( {}", fragment))),
-    };
-    // try to find user in database
-    let user_option = match get_user_by_name(user_name.to_uppercase()) {
-        Ok(data) => data,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error fetching user from DB: {}", e))),
-    };
-    // check if user found in database
-    match user_option {
-        Some(user) => Ok(user),
-        None => return Err(Error::new(ErrorKind::Other, "Error fetching user from DB".to_string())),
-    }
-}
-
-fn extract_round_game_fragment(message_text: &String) -> Option<String> {
-    let fragment = message_text
-        .split(' ')
-        .nth(1);
-    match fragment {
-        Some(fr) => Some(fr.to_string()),
-        None => None,
-    }
-}
-
-fn extract_round_player_fragment(message_text: &String) -> Option<String> {
-    let fragments: Vec<&str> = message_text
-        .split(' ')
-        .skip(2)
-        .collect();
-    if fragments.is_empty() {
-        None
-    } else {
-        Some(fragments.join(" "))
-    }
-}
-
-fn handle_game(
-    round_players: &[User], 
-    score: &mut HashMap<String, Vec<Option<i32>>>, 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>,
-    round_game_attributes: &mut Vec<TarokGameInput>,
-) -> Result<HashMap<String, i32>, Error> {
-    // find what game we are playing
-    let game: TarokGame = match find_tarok_game(&round_game_attributes) {
-        Some(game) => game,
-        None => return Err(Error::new(ErrorKind::Other, "No game specified".to_string())),
-    };
-    match game {
-        TarokGame::I3 => play_I3(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::I2 => play_I2(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::I1 => play_I1(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::S3 => play_S3(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::S2 => play_S2(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::S1 => play_S1(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::SB => play_SB(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::KL => play_KL(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::B => play_B(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::P => play_P(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::BVI3 => play_BVI3(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::BVI2 => play_BVI2(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::BVI1 => play_BVI1(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::BVS3 => play_BVS3(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::BVS2 => play_BVS2(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::BVS1 => play_BVS1(round_players, radlci, round_player_attributes, round_game_attributes),
-        TarokGame::BVSB => play_BVSB(round_players, radlci, round_player_attributes, round_game_attributes),
-    }
-}
-
-fn play_BVSB(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_BVS1(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_BVS2(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_BVS3(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_BVI1(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // add attribute of "supporting player" to other players
-    if let Err(e) = add_supporting_attribute_to_players(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_BVI2(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // add attribute of "supporting player" to other players
-    if let Err(e) = add_supporting_attribute_to_players(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_BVI3(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // add attribute of "supporting player" to other players
-    if let Err(e) = add_supporting_attribute_to_players(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_P(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_B(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_KL(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-
-    let changes = match score_player_only(round_players, &round_player_attributes) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
-}
-
-fn play_SB(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+        }
     }
+}
 
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
+fn extract_round_users(message_text: &str) -> Result<Vec<User>, Error> {
+    let fragment = match extract_round_player_fragment(message_text) {
+        Some(fragment) => fragment,
+        None => return Err(Error::other("Can't find any users to parse :(".to_string())),
     };
-    // add radlc to all players
-    add_radlc(radlci);
-    Ok(changes)
+    match parse_users_from_fragment(&fragment) {
+        Ok(users) => Ok(users),
+        Err(e) => Err(Error::other(format!("Failed parsing players: {}", e))),
+    }
 }
 
-fn play_S1(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+fn extract_round_referenced_users(message_text: &str) -> Result<Vec<User>, Error> {
+    let fragment = match extract_round_player_fragment(message_text) {
+        Some(fragment) => fragment,
+        None => return Ok(vec![]),
+    };
+    let mut referenced = vec![];
+    for partial in fragment.split(' ').flat_map(|f| f.split(',').skip(1)) {
+        if let Some((_, name)) = split_player_reference(partial) {
+            match parse_user_from_fragment(&name) {
+                Ok(user) => referenced.push(user),
+                Err(e) => return Err(Error::other(format!("Failed parsing referenced player: {}", e))),
+            };
+        }
     }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+    Ok(referenced)
+}
+
+fn parse_users_from_fragment(fragment: &str) -> Result<Vec<User>, Error> {
+    let mut users = vec![];
+    for user_framgent in fragment.split(' ') {
+        // extract name from user fragment (JAN,M -> JAN)
+        match parse_user_from_fragment(&user_framgent.to_string()) {
+            Ok(user) => users.push(user),
+            Err(e) => return Err(Error::other(format!("Failed parsing player: {}", e))),
+        };
     }
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    Ok(changes)
+    Ok(users)
 }
 
-fn play_S2(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+fn parse_user_from_fragment(fragment: &String) -> Result<User, Error> {
+    let user_name = match fragment.split(',').next() {
+        Some(name) => name,
+        None => return Err(Error::other(format!("Can't parse a users :( {}", fragment))),
+    };
+    // try to find user in database
+    let user_option = match get_user_by_name(user_name.to_uppercase()) {
+        Ok(data) => data,
+        Err(e) => return Err(Error::other(format!("Error fetching user from DB: {}", e))),
+    };
+    // check if user found in database
+    match user_option {
+        Some(user) => Ok(user),
+        None => Err(Error::other("Error fetching user from DB".to_string())),
     }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+}
+
+fn extract_round_game_fragment(message_text: &str) -> Option<String> {
+    let fragment = message_text
+        .split(' ')
+        .nth(1);
+    fragment.map(|fr| fr.to_string())
+}
+
+fn extract_round_player_fragment(message_text: &str) -> Option<String> {
+    let fragments: Vec<&str> = message_text
+        .split(' ')
+        .skip(2)
+        .collect();
+    if fragments.is_empty() {
+        None
+    } else {
+        Some(fragments.join(" "))
     }
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    Ok(changes)
 }
 
-fn play_S3(
+fn handle_game(
     round_players: &[User], 
+    players: &[User],
     radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>,
+    round_game_attributes: &[TarokGameInput],
+    rules: &TarokRules,
+    breakdown: &mut RoundBreakdown,
+) -> Result<(HashMap<String, i32>, Vec<String>), Error> {
+    let mut notes: Vec<String> = vec![];
+    // find what game we are playing
+    let game: TarokGame = match find_tarok_game(round_game_attributes) {
+        Some(game) => game,
+        None => return Err(Error::other("No game specified".to_string())),
+    };
+    breakdown.contract = Some(game);
+    let mut changes = play_contract(game, round_players, radlci, round_player_attributes, round_game_attributes, rules, breakdown)?;
+    // mond is a personal penalty, so it is settled after the game itself is scored
+    if let Err(e) = handle_mond(game, rules, players, round_player_attributes, &mut changes, &mut notes, breakdown) {
+        return Err(Error::other(format!("Error handling mond: {}", e)));
     }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+    if let Err(e) = handle_renonce(game, rules, players, round_player_attributes, &mut changes, &mut notes, breakdown) {
+        return Err(Error::other(format!("Error handling renonce: {}", e)));
     }
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    Ok(changes)
+    // add radlc to all players
+    if rules.radlc_contracts.contains(&game) {
+        add_radlc(radlci);
+        notes.push("Radlc added to all players".to_string());
+    }
+    breakdown.score_change = players
+        .iter()
+        .filter_map(|p| changes.get(&p.id).map(|change| (p.id.clone(), *change)))
+        .collect();
+    Ok((changes, notes))
 }
 
-fn play_I1(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+fn handle_renonce(
+    game: TarokGame,
+    rules: &TarokRules,
+    players: &[User],
+    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>,
+    score_change: &mut HashMap<String, i32>,
+    notes: &mut Vec<String>,
+    breakdown: &mut RoundBreakdown,
+) -> Result<(), Error> {
+    // find who made a renonce this round
+    let mut offenders: Vec<(String, bool)> = vec![]; // (player id, is opponent)
+    for (player_id, attrs) in round_player_attributes.iter() {
+        for attr in attrs.iter() {
+            match attr {
+                TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::R(None)) => offenders.push((player_id.clone(), false)),
+                TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::R(Some(offender))) => offenders.push((offender.clone(), true)),
+                _ => (),
+            }
+        }
     }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+    let contract_value = contract_worth(game, rules);
+    for (offender, is_opponent) in offenders.into_iter() {
+        let offender_name = player_name_by_id(players, &offender);
+        if is_opponent {
+            // mark the opponent on the sheet as well
+            round_player_attributes
+                .entry(offender.clone())
+                .or_default()
+                .push(TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::R(None)));
+        }
+        let penalty = match rules.renonce_mode {
+            RenonceMode::Off => 0,
+            RenonceMode::Fixed => rules.renonce_penalty,
+            RenonceMode::Offender | RenonceMode::Contract if contract_value == 0 => rules.renonce_penalty,
+            RenonceMode::Offender => contract_value,
+            RenonceMode::Contract => {
+                // already settled in the game points (see renonce_contract_points)
+                match is_opponent {
+                    true => notes.push(format!("Renonce by {}: contract awarded to the playing team (+{})", offender_name, contract_value)),
+                    false => notes.push(format!("Renonce by {}: contract awarded to the opponents (-{})", offender_name, contract_value)),
+                };
+                score_change.entry(offender).or_insert(0);
+                continue;
+            },
+        };
+        breakdown.add_personal(&offender, "Renonce".to_string(), -penalty);
+        *score_change.entry(offender).or_insert(0) -= penalty;
+        notes.push(format!("Renonce by {} ({})", offender_name, -penalty));
     }
+    Ok(())
+}
 
-    // add attribute of "supporting player" to other players
-    if let Err(e) = add_supporting_attribute_to_players(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+fn handle_mond(
+    game: TarokGame,
+    rules: &TarokRules,
+    players: &[User],
+    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>,
+    score_change: &mut HashMap<String, i32>,
+    notes: &mut Vec<String>,
+    breakdown: &mut RoundBreakdown,
+) -> Result<(), Error> {
+    // find who lost the mond this round (and to whom)
+    let mut monds: Vec<(String, Option<String>)> = vec![];
+    for (player_id, attrs) in round_player_attributes.iter() {
+        for attr in attrs.iter() {
+            if let TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::M(captor)) = attr {
+                monds.push((player_id.clone(), captor.clone()));
+            }
+        }
     }
+    // validated in player_references_validity_check before the round was played
+    let (loser, captor) = match monds.pop() {
+        Some(mond) => mond,
+        None => return Ok(()),
+    };
+    let waived = match game {
+        TarokGame::KL => rules.mond_waived_in_klop,
+        _ => is_colour_valat(game) && rules.mond_waived_in_colour_valat,
+    };
 
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
+    let loser_name = player_name_by_id(players, &loser);
+    let (penalty, credit) = match waived {
+        true => (0, 0),
+        false => (rules.mond_penalty, rules.mond_capture_credit),
     };
-    Ok(changes)
+    *score_change.entry(loser.clone()).or_insert(0) -= penalty;
+    breakdown.add_personal(&loser, "Mond lost".to_string(), -penalty);
+
+    match captor {
+        Some(captor) => {
+            *score_change.entry(captor.clone()).or_insert(0) += credit;
+            breakdown.add_personal(&captor, "Mond captured".to_string(), credit);
+            // mark the captor on the sheet as well
+            round_player_attributes
+                .entry(captor.clone())
+                .or_default()
+                .push(TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::Mc(loser)));
+            notes.push(format!(
+                "{} lost the mond to {} ({} / +{})", 
+                loser_name, 
+                player_name_by_id(players, &captor), 
+                -penalty, 
+                credit
+            ));
+        },
+        None => notes.push(format!("{} lost the mond ({})", loser_name, -penalty)),
+    };
+    if waived {
+        notes.push("Mond penalty is waived for this game".to_string());
+    }
+    Ok(())
 }
 
-fn play_I2(
-    round_players: &[User], 
-    radlci: &mut HashMap<String, Vec<Radlc>>,
-    round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
-) -> Result<HashMap<String, i32>, Error> {
-    // check if at least one player exists
-    if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
-    // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
-    
-    // add the attribute of "playing player" to the first player
-    if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+fn player_name_by_id(players: &[User], id: &str) -> String {
+    match extract_user_by_id(players, id.to_string()) {
+        Some(user) => user.name.clone(),
+        None => id.to_string(),
     }
+}
 
-    // add attribute of "supporting player" to other players
-    if let Err(e) = add_supporting_attribute_to_players(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
-    }
+fn is_colour_valat(game: TarokGame) -> bool {
+    matches!(
+        game, 
+        TarokGame::BVI3 | TarokGame::BVI2 | TarokGame::BVI1 | TarokGame::BVS3 | TarokGame::BVS2 | TarokGame::BVS1 | TarokGame::BVSB
+        | TarokGame::BV
+    )
+}
 
-    // check if player that is playing the round (should be first) has a radlc avalible
-    // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
-    
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
-        Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
-    };
-    Ok(changes)
+// how a contract is played: who is in the playing team and whether it is scored as a game at all
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContractTeam {
+    Solo,       // the declarer plays alone
+    Partnered,  // the declarer and at most one partner (marked as supporting)
+    Everyone,   // klop: everyone plays for themselves, only personal points count
+}
+
+struct ContractRule {
+    worth: i32,
+    team: ContractTeam,
+    radlc_doubles: bool,    // an available radlc of the declarer doubles the game
 }
 
+fn contract_rule(game: TarokGame) -> ContractRule {
+    let (worth, team, radlc_doubles) = match game {
+        TarokGame::I3 => (10, ContractTeam::Partnered, true),
+        TarokGame::I2 => (20, ContractTeam::Partnered, true),
+        TarokGame::I1 => (30, ContractTeam::Partnered, true),
+        TarokGame::S3 => (40, ContractTeam::Solo, true),
+        TarokGame::S2 => (50, ContractTeam::Solo, true),
+        TarokGame::S1 => (60, ContractTeam::Solo, true),
+        TarokGame::SB => (80, ContractTeam::Solo, true),
+        TarokGame::KL => (0, ContractTeam::Everyone, false),
+        TarokGame::B => (70, ContractTeam::Solo, true),
+        TarokGame::P => (60, ContractTeam::Solo, true),
+        TarokGame::BVI3 => (90, ContractTeam::Partnered, true),
+        TarokGame::BVI2 => (100, ContractTeam::Partnered, true),
+        TarokGame::BVI1 => (110, ContractTeam::Partnered, true),
+        TarokGame::BVS3 => (120, ContractTeam::Solo, true),
+        TarokGame::BVS2 => (130, ContractTeam::Solo, true),
+        TarokGame::BVS1 => (140, ContractTeam::Solo, true),
+        TarokGame::BVSB => (150, ContractTeam::Solo, true),
+        TarokGame::OB => (90, ContractTeam::Solo, true),
+        TarokGame::BV => (125, ContractTeam::Solo, true),
+        TarokGame::VL => (500, ContractTeam::Solo, true),
+    };
+    ContractRule { worth, team, radlc_doubles }
+}
 
-fn play_I3(
+fn play_contract(
+    game: TarokGame,
     round_players: &[User], 
     radlci: &mut HashMap<String, Vec<Radlc>>,
     round_player_attributes: &mut HashMap<String, Vec<TarokPlayerInput>>, 
-    round_game_attributes: &mut Vec<TarokGameInput>
+    round_game_attributes: &[TarokGameInput],
+    rules: &TarokRules,
+    breakdown: &mut RoundBreakdown,
 ) -> Result<HashMap<String, i32>, Error> {
+    let contract = contract_rule(game);
     // check if at least one player exists
     if let Err(e) = players_validity_check(round_players) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+        return Err(Error::other(format!("{}", e)));
+    }
+    // klop has no game points, everyone keeps their own
+    if contract.team == ContractTeam::Everyone {
+        if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
+            return Err(Error::other(format!("{}", e)));
+        }
+        return match score_player_only(round_players, round_player_attributes, breakdown) {
+            Ok(hm) => Ok(hm),
+            Err(e) => Err(Error::other(format!("Error caluclating score: {}", e))),
+        };
     }
     // get points of the game
-    let mut game_points = calculate_base_game_points(&round_game_attributes);
+    let mut game_points = calculate_base_game_points(round_game_attributes, round_player_attributes, rules, breakdown);
     
     // add the attribute of "playing player" to the first player
     if let Err(e) = add_playing_attribute_to_first_player(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+        return Err(Error::other(format!("{}", e)));
     }
 
     // add attribute of "supporting player" to other players
-    if let Err(e) = add_supporting_attribute_to_players(round_players, round_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("{}", e)));
+    if contract.team == ContractTeam::Partnered {
+        if let Err(e) = add_supporting_attribute_to_players(round_players, round_player_attributes) {
+            return Err(Error::other(format!("{}", e)));
+        }
     }
 
     // check if player that is playing the round (should be first) has a radlc avalible
     // if yes double game points
-    handle_radlc(round_players, radlci, &mut game_points);
+    if contract.radlc_doubles {
+        handle_radlc(round_players, radlci, &mut game_points, breakdown);
+    }
     
-    let changes = match score_game_and_player(round_players, &round_player_attributes, &game_points) {
+    let changes = match score_game_and_player(round_players, round_player_attributes, &game_points, breakdown) {
         Ok(hm) => hm,
-        Err(e) => return Err(Error::new(ErrorKind::Other, format!("Error caluclating score: {}", e))),
+        Err(e) => return Err(Error::other(format!("Error caluclating score: {}", e))),
     };
     Ok(changes)
 }
@@ -1016,19 +1199,24 @@ fn play_I3(
 fn score_game_and_player(
     players: &[User],
     round_player_attributes: &HashMap<String, Vec<TarokPlayerInput>>,
-    game_points: &i32
+    game_points: &i32,
+    breakdown: &mut RoundBreakdown,
 ) -> Result<HashMap<String, i32>, Error>{
     let mut score_change = HashMap::new();
     for player in players.iter() {
         // get player attributes
         let attrs = match round_player_attributes.get(&player.id) {
             Some(att) => att,
-            None => return Err(Error::new(ErrorKind::Other, format!("Player does not have attribute vector!"))),
+            None => return Err(Error::other("Player does not have attribute vector!".to_string())),
         };
         // calc player personal score modifiers (lost mond, support,...)
         let mut personal_points = 0;
         for p_attr in attrs.iter() {
-            personal_points += player_attribute_worth(p_attr)
+            let worth = player_attribute_worth(p_attr);
+            if worth != 0 {
+                breakdown.add_personal(&player.id, player_input_label(p_attr), worth);
+            }
+            personal_points += worth
         }
 
         // save player score to the game score sheet
@@ -1040,18 +1228,23 @@ fn score_game_and_player(
 fn score_player_only(
     players: &[User],
     round_player_attributes: &HashMap<String, Vec<TarokPlayerInput>>,
+    breakdown: &mut RoundBreakdown,
 ) -> Result<HashMap<String, i32>, Error>{
     let mut score_change = HashMap::new();
     for player in players.iter() {
         // get player attributes
         let attrs = match round_player_attributes.get(&player.id) {
             Some(att) => att,
-            None => return Err(Error::new(ErrorKind::Other, format!("Player does not have attribute vector!"))),
+            None => return Err(Error::other("Player does not have attribute vector!".to_string())),
         };
         // calc player personal score modifiers (lost mond, support,...)
         let mut personal_points = 0;
         for p_attr in attrs.iter() {
-            personal_points += player_attribute_worth(p_attr)
+            let worth = player_attribute_worth(p_attr);
+            if worth != 0 {
+                breakdown.add_personal(&player.id, player_input_label(p_attr), worth);
+            }
+            personal_points += worth
         }
         score_change.insert(player.id.clone(), personal_points);
     }
@@ -1066,10 +1259,12 @@ fn increment_round(round: &mut i32) {
 fn handle_radlc(
     players: &[User],
     radlci: &mut HashMap<String, Vec<Radlc>>,
-    game_points: &mut i32
+    game_points: &mut i32,
+    breakdown: &mut RoundBreakdown,
 ) {
-    if let true = player_has_avalible_radlc(&players[0].id, radlci) {
+    if player_has_avalible_radlc(&players[0].id, radlci) {
         *game_points *= 2;
+        breakdown.radlc_multiplier = 2;
         consume_player_radlc(&players[0].id, radlci);
     }
 }
@@ -1090,7 +1285,7 @@ fn add_supporting_attribute_to_players(
     for player in round_players.iter().skip(1) {
         let attr = match round_player_attributes.get_mut(&player.id) {
             Some(att) => att,
-            None => return Err(Error::new(ErrorKind::Other, format!("Player does not have attribute vector!"))),
+            None => return Err(Error::other("Player does not have attribute vector!".to_string())),
         };
         attr.push(TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::Sl));
     }
@@ -1103,12 +1298,33 @@ fn add_playing_attribute_to_first_player(
 ) -> Result<(), Error> {
     // add the attribute of "playing player" to the first player
     match round_player_attributes.get_mut(&players[0].id) {
-        Some(att) => Ok(att.push(TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::Ig))),
-        None => return Err(Error::new(ErrorKind::Other, format!("Player does not have attribute vector!"))),
+        Some(att) => {
+            let _: () = att.push(TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::Ig));
+            Ok(())
+        },
+        None => Err(Error::other("Player does not have attribute vector!".to_string())),
     }
 }
 
-fn calculate_base_game_points(round_game_attributes: &Vec<TarokGameInput>) -> i32 {
+fn calculate_base_game_points(
+    round_game_attributes: &[TarokGameInput],
+    round_player_attributes: &HashMap<String, Vec<TarokPlayerInput>>,
+    rules: &TarokRules,
+    breakdown: &mut RoundBreakdown,
+) -> i32 {
+    breakdown.contract = find_tarok_game(round_game_attributes);
+    // a renonce may decide the contract regardless of the played points
+    if let Some(points) = renonce_contract_points(round_game_attributes, round_player_attributes, rules) {
+        breakdown.override_reason = Some("Contract decided by renonce".to_string());
+        breakdown.game_points = points;
+        return points;
+    }
+    // a valat replaces the contract score entirely
+    if let Some(points) = valat_points(round_game_attributes, rules) {
+        breakdown.override_reason = Some("Valat".to_string());
+        breakdown.game_points = points;
+        return points;
+    }
     // get points of the game
     let mut base_score = 0;
     let mut game_points = 0;
@@ -1118,21 +1334,146 @@ fn calculate_base_game_points(round_game_attributes: &Vec<TarokGameInput>) -> i3
         
         if let TarokGameInput::TarokGameDiff(val) = g_attr {
             if *val < 0 { lost = true ; }
+            breakdown.diff = *val;
         }
         if let TarokGameInput::TarokGame(g) = g_attr {
             base_score = game_worth(*g);
         }
+        if let TarokGameInput::TarokGameAttribute(att) = g_attr {
+            breakdown.bonuses.push((att.code().to_string(), attribute_worth(g_attr)));
+        }
     }
+    breakdown.base = base_score;
     if lost {
         game_points -= 2 * base_score;
+        breakdown.lost_doubling = -2 * base_score;
     }
+    breakdown.game_points = game_points;
     game_points
 }
 
+fn valat_points(round_game_attributes: &[TarokGameInput], rules: &TarokRules) -> Option<i32> {
+    let mut valat: Option<bool> = None; // Some(announced)
+    let mut lost = false;
+    let mut colour = false;
+    for g_attr in round_game_attributes.iter() {
+        match g_attr {
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::V) => valat = valat.or(Some(false)),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::NV) => valat = Some(true),
+            TarokGameInput::TarokGameDiff(val) => lost = *val < 0,
+            TarokGameInput::TarokGame(g) => {
+                colour = is_colour_valat(*g);
+                // valat contracts are a valat by definition
+                match g {
+                    TarokGame::VL => valat = Some(true),
+                    TarokGame::BV => valat = valat.or(Some(false)),
+                    _ => (),
+                }
+            },
+            _ => (),
+        }
+    }
+    let value = match (valat?, colour) {
+        (false, false) => rules.valat,
+        (true, false) => rules.announced_valat,
+        (false, true) => rules.colour_valat,
+        (true, true) => rules.announced_colour_valat,
+    };
+    match lost {
+        true => Some(-value),
+        false => Some(value),
+    }
+}
+
+fn renonce_contract_points(
+    round_game_attributes: &[TarokGameInput],
+    round_player_attributes: &HashMap<String, Vec<TarokPlayerInput>>,
+    rules: &TarokRules,
+) -> Option<i32> {
+    if rules.renonce_mode != RenonceMode::Contract {
+        return None;
+    }
+    let contract_value = match find_tarok_game(round_game_attributes) {
+        Some(game) => contract_worth(game, rules),
+        None => return None,
+    };
+    // contracts without value (klop) fall back to the offender paying
+    if contract_value == 0 {
+        return None;
+    }
+    for attrs in round_player_attributes.values() {
+        for attr in attrs.iter() {
+            match attr {
+                // renonce in the playing team -> contract goes to the opponents
+                TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::R(None)) => return Some(-contract_value),
+                // renonce by an opponent -> contract goes to the playing team
+                TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::R(Some(_))) => return Some(contract_value),
+                _ => (),
+            }
+        }
+    }
+    None
+}
+
+// everything that can reject a round, checked before the round touches the game
+fn round_validity_check(
+    round_players: &[User],
+    round_game_attributes: &[TarokGameInput],
+    round_player_attributes: &HashMap<String, Vec<TarokPlayerInput>>,
+) -> Result<(), Error> {
+    players_validity_check(round_players)?;
+    let game = match find_tarok_game(round_game_attributes) {
+        Some(game) => game,
+        None => return Err(Error::other("No game specified".to_string())),
+    };
+    contract_validity_check(game, round_players)?;
+    player_references_validity_check(round_player_attributes)
+}
+
+fn contract_validity_check(game: TarokGame, round_players: &[User]) -> Result<(), Error> {
+    // how many players can be in the playing team of the contract
+    let team_size = match contract_rule(game).team {
+        ContractTeam::Solo => 1,
+        ContractTeam::Partnered => 2,
+        ContractTeam::Everyone => return Ok(()),
+    };
+    if round_players.len() > team_size {
+        return Err(Error::other(
+            format!("Contract {} is played by at most {} player(s)", game.code(), team_size)
+        ));
+    }
+    Ok(())
+}
+
+// mond and renonce inputs that can't be scored (M, M:, R:)
+fn player_references_validity_check(round_player_attributes: &HashMap<String, Vec<TarokPlayerInput>>) -> Result<(), Error> {
+    let mut monds = 0;
+    for (player_id, attrs) in round_player_attributes.iter() {
+        for attr in attrs.iter() {
+            match attr {
+                TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::M(captor)) => {
+                    if captor.as_ref() == Some(player_id) {
+                        return Err(Error::other("Player can't capture their own mond".to_string()));
+                    }
+                    monds += 1;
+                },
+                TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::R(Some(offender))) if offender == player_id => {
+                    return Err(Error::other("Player can't be their own renonce opponent, use R".to_string()));
+                },
+                _ => (),
+            }
+        }
+    }
+    if monds > 1 {
+        return Err(Error::other("Only one mond can be lost in a round".to_string()));
+    }
+    Ok(())
+}
+
 fn players_validity_check(players: &[User]) -> Result<(), Error> {
     // check if at least one player exists
     if players.is_empty() {
-        return Err(Error::new(ErrorKind::Other, format!("No players specified!")));
+        return Err(Error::other("No players specified!".to_string()));
     }
     Ok(())
 }
@@ -1148,17 +1489,17 @@ fn save_round_to_sheets(
 ) -> Result<(), Error> {
     // save game attributes to global sheet
     if let Err(e) = save_game_attributes(round_game_attributes, global_game_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("Failed saving game attributes to sheet: {}", e)))
+        return Err(Error::other(format!("Failed saving game attributes to sheet: {}", e)))
     }
 
     // save player attributes to global sheet
     if let Err(e) = save_player_attributes(round_player_attributes, round, global_player_attributes) {
-        return Err(Error::new(ErrorKind::Other, format!("Failed saving player attributes to sheet: {}", e)))
+        return Err(Error::other(format!("Failed saving player attributes to sheet: {}", e)))
     }
 
     // save player attributes to global sheet
     if let Err(e) = save_score(score_change, round, global_score) {
-        return Err(Error::new(ErrorKind::Other, format!("Failed saving player attributes to sheet: {}", e)))
+        return Err(Error::other(format!("Failed saving player attributes to sheet: {}", e)))
     }
 
     Ok(())
@@ -1176,7 +1517,7 @@ fn save_score(
                 fill_gaps_until_round(sc, round);
                 sc.push(Some(*change))
             },
-            None => return Err(Error::new(ErrorKind::Other, format!("Player does not have a score vector!"))),
+            None => return Err(Error::other("Player does not have a score vector!".to_string())),
         };
     }
     Ok(())
@@ -1193,7 +1534,7 @@ fn save_player_attributes(
                 fill_gaps_until_round(sh, round);
                 sh.push(Some(attributes))
             },
-            None => return Err(Error::new(ErrorKind::Other, format!("Player attribute sheet missing"))),
+            None => return Err(Error::other("Player attribute sheet missing".to_string())),
         }
     }
     Ok(())
@@ -1210,8 +1551,9 @@ fn save_game_attributes(
 fn player_attribute_worth(attr: &TarokPlayerInput) -> i32 {
     match attr {
         TarokPlayerInput::PlayerAttribute(at) => match at {
-            TarokPlayerAttibute::M => -20,
-            TarokPlayerAttibute::R => 0,
+            TarokPlayerAttibute::M(_) => 0, // settled in handle_mond
+            TarokPlayerAttibute::Mc(_) => 0,
+            TarokPlayerAttibute::R(_) => 0, // settled in handle_renonce
             TarokPlayerAttibute::T => 0,
             TarokPlayerAttibute::Ig => 0,
             TarokPlayerAttibute::Sl => -20,
@@ -1227,12 +1569,12 @@ fn attribute_worth(g_attr: &TarokGameInput) -> i32 {
         TarokGameInput::TarokGameAttribute(att) => match att {
             TarokGameAttribute::ZP => 10,
             TarokGameAttribute::ZK => 10,
-            TarokGameAttribute::V => 150,
+            TarokGameAttribute::V => 0, // scored in valat_points
             TarokGameAttribute::T => 15,
             TarokGameAttribute::K => 15,
             TarokGameAttribute::NZP => 20,
             TarokGameAttribute::NZK => 20,
-            TarokGameAttribute::NV => 250,
+            TarokGameAttribute::NV => 0, // scored in valat_points
             TarokGameAttribute::NT => 30,
             TarokGameAttribute::NK => 30,
         },
@@ -1242,31 +1584,27 @@ fn attribute_worth(g_attr: &TarokGameInput) -> i32 {
 fn find_tarok_game(round_game_attributes: &[TarokGameInput]) -> Option<TarokGame> {
     for input in round_game_attributes.iter() {
         if let TarokGameInput::TarokGame(game) = input {
-            return Some(game.clone());
+            return Some(*game);
         }
     }
     None
 }
 
+// code, name and value of every contract under the rules, for the legends
+pub fn contract_values(rules: &TarokRules) -> Vec<(&'static str, &'static str, i32)> {
+    TarokGame::all().iter().map(|g| (g.code(), g.name(), contract_worth(*g, rules))).collect()
+}
+
 fn game_worth(g: TarokGame) -> i32 {
+    contract_rule(g).worth
+}
+
+// what the contract is scored as, the valat contracts are worth what /rules sets for valats (see valat_points)
+fn contract_worth(g: TarokGame, rules: &TarokRules) -> i32 {
     match g {
-        TarokGame::I3 => 10,
-        TarokGame::I2 => 20,
-        TarokGame::I1 => 30,
-        TarokGame::S3 => 40,
-        TarokGame::S2 => 50,
-        TarokGame::S1 => 60,
-        TarokGame::SB => 80,
-        TarokGame::KL => 0,
-        TarokGame::B => 70,
-        TarokGame::P => 60,
-        TarokGame::BVI3 => 90,
-        TarokGame::BVI2 => 100,
-        TarokGame::BVI1 => 110,
-        TarokGame::BVS3 => 120,
-        TarokGame::BVS2 => 130,
-        TarokGame::BVS1 => 140,
-        TarokGame::BVSB => 150,
+        TarokGame::VL => rules.announced_valat,
+        TarokGame::BV => rules.colour_valat,
+        _ => game_worth(g),
     }
 }
 
@@ -1302,4 +1640,463 @@ fn consume_player_radlc(player_id: &String, radlci: &mut HashMap<String, Vec<Rad
         }
     }
     false
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{calculate_base_game_points, contract_validity_check, handle_game, Tarok};
+    use crate::{
+        core::{traits::Game, export::GameExport, html_template::Theme, database::test_database::{setup_database, user, register, message}},
+        games::tarok::{enums::{TarokGame, TarokGameAttribute, TarokGameInput, TarokPlayerAttibute, TarokPlayerInput, Radlc}, rules::{TarokRules, RenonceMode}, breakdown::RoundBreakdown},
+        models::user::User,
+    };
+
+    const CHAT_ID: i64 = -1026;
+
+    fn base_points(round_game_attributes: Vec<TarokGameInput>) -> i32 {
+        calculate_base_game_points(&round_game_attributes, &HashMap::new(), &TarokRules::new(), &mut RoundBreakdown::new(1))
+    }
+
+    #[test]
+    fn unannounced_valat_won() {
+        let points = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::I3),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::V),
+        ]);
+        assert_eq!(points, 250);
+    }
+
+    #[test]
+    fn unannounced_valat_lost() {
+        let points = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::I3),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::V),
+            TarokGameInput::TarokGameDiff(-1),
+        ]);
+        assert_eq!(points, -250);
+    }
+
+    #[test]
+    fn announced_valat_won() {
+        let points = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::S1),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::NV),
+        ]);
+        assert_eq!(points, 500);
+    }
+
+    #[test]
+    fn announced_valat_lost() {
+        let points = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::S1),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::NV),
+            TarokGameInput::TarokGameDiff(-5),
+        ]);
+        assert_eq!(points, -500);
+    }
+
+    #[test]
+    fn unannounced_colour_valat_won() {
+        let points = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::BVI3),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::V),
+        ]);
+        assert_eq!(points, 125);
+    }
+
+    #[test]
+    fn unannounced_colour_valat_lost() {
+        let points = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::BVI3),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::V),
+            TarokGameInput::TarokGameDiff(-1),
+        ]);
+        assert_eq!(points, -125);
+    }
+
+    #[test]
+    fn announced_colour_valat_won() {
+        let points = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::BVSB),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::NV),
+        ]);
+        assert_eq!(points, 250);
+    }
+
+    #[test]
+    fn announced_colour_valat_lost() {
+        let points = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::BVSB),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::NV),
+            TarokGameInput::TarokGameDiff(-1),
+        ]);
+        assert_eq!(points, -250);
+    }
+
+    #[test]
+    fn valat_overrides_diff_and_bonuses() {
+        let points = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::I2),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::T),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::NK),
+            TarokGameInput::TarokGameDiff(35),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::V),
+        ]);
+        assert_eq!(points, 250);
+    }
+
+    #[test]
+    fn valat_uses_configured_values() {
+        let mut rules = TarokRules::new();
+        rules.set("announced_valat", "1000").unwrap();
+        let round_game_attributes = vec![
+            TarokGameInput::TarokGame(TarokGame::I1),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::NV),
+        ];
+        assert_eq!(calculate_base_game_points(&round_game_attributes, &HashMap::new(), &rules, &mut RoundBreakdown::new(1)), 1000);
+    }
+
+    #[test]
+    fn valat_contracts_are_scored_as_valat() {
+        let valat = base_points(vec![TarokGameInput::TarokGame(TarokGame::VL)]);
+        assert_eq!(valat, 500);
+        let colour_valat_lost = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::BV),
+            TarokGameInput::TarokGameDiff(-1),
+        ]);
+        assert_eq!(colour_valat_lost, -125);
+    }
+
+    #[test]
+    fn game_without_valat_is_scored_normally() {
+        let won = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::I3),
+            TarokGameInput::TarokGameDiff(30),
+            TarokGameInput::TarokGameAttribute(TarokGameAttribute::T),
+        ]);
+        assert_eq!(won, 55);
+        let lost = base_points(vec![
+            TarokGameInput::TarokGame(TarokGame::I3),
+            TarokGameInput::TarokGameDiff(-10),
+        ]);
+        assert_eq!(lost, -20);
+    }
+
+    fn play(
+        game: TarokGame,
+        round_players: &[User],
+        players: &[User],
+        radlci: &mut HashMap<String, Vec<Radlc>>,
+    ) -> HashMap<String, i32> {
+        let mut round_player_attributes = round_players.iter().map(|p| (p.id.clone(), vec![])).collect();
+        let round_game_attributes = vec![TarokGameInput::TarokGame(game)];
+        handle_game(
+            round_players,
+            players,
+            radlci,
+            &mut round_player_attributes,
+            &round_game_attributes,
+            &TarokRules::new(),
+            &mut RoundBreakdown::new(1),
+        ).expect("Error playing contract").0
+    }
+
+    #[test]
+    fn open_beggar_colour_valat_and_valat_scores() {
+        let players = vec![user(CHAT_ID, "ANA"), user(CHAT_ID, "BOR"), user(CHAT_ID, "CENE")];
+        for (game, points) in [(TarokGame::OB, 90), (TarokGame::BV, 125), (TarokGame::VL, 500)] {
+            let changes = play(game, &players[..1], &players, &mut HashMap::new());
+            assert_eq!(changes.get(&players[0].id), Some(&points), "{}", game.code());
+            assert_eq!(changes.len(), 1, "{}", game.code());
+        }
+    }
+
+    #[test]
+    fn open_beggar_colour_valat_and_valat_are_doubled_by_radlc() {
+        let players = vec![user(CHAT_ID, "ANA"), user(CHAT_ID, "BOR"), user(CHAT_ID, "CENE")];
+        for (game, points) in [(TarokGame::OB, 180), (TarokGame::BV, 250), (TarokGame::VL, 1000)] {
+            let mut radlci = HashMap::from([(players[0].id.clone(), vec![Radlc::Avalible])]);
+            let changes = play(game, &players[..1], &players, &mut radlci);
+            assert_eq!(changes.get(&players[0].id), Some(&points), "{}", game.code());
+            assert!(matches!(radlci[&players[0].id][0], Radlc::Used), "{}", game.code());
+        }
+    }
+
+    #[test]
+    fn open_beggar_colour_valat_and_valat_add_radlci() {
+        let players = vec![user(CHAT_ID, "ANA"), user(CHAT_ID, "BOR"), user(CHAT_ID, "CENE")];
+        for game in [TarokGame::OB, TarokGame::BV, TarokGame::VL] {
+            let mut radlci: HashMap<String, Vec<Radlc>> = players.iter().map(|p| (p.id.clone(), vec![])).collect();
+            play(game, &players[..1], &players, &mut radlci);
+            for p in players.iter() {
+                assert!(matches!(radlci[&p.id].as_slice(), [Radlc::Avalible]), "{} {}", game.code(), p.name);
+            }
+        }
+    }
+
+    #[test]
+    fn renonce_costs_the_valat_values_of_the_rules() {
+        let players = vec![user(CHAT_ID, "ANA"), user(CHAT_ID, "BOR")];
+        let mut rules = TarokRules::new();
+        rules.colour_valat = 200;
+        rules.announced_valat = 800;
+        for mode in [RenonceMode::Offender, RenonceMode::Contract] {
+            rules.renonce_mode = mode;
+            for (game, worth) in [(TarokGame::BV, 200), (TarokGame::VL, 800)] {
+                let renonce = TarokPlayerInput::PlayerAttribute(TarokPlayerAttibute::R(None));
+                let mut round_player_attributes = HashMap::from([(players[0].id.clone(), vec![renonce])]);
+                let mut breakdown = RoundBreakdown::new(1);
+                handle_game(
+                    &players[..1],
+                    &players,
+                    &mut HashMap::new(),
+                    &mut round_player_attributes,
+                    &[TarokGameInput::TarokGame(game)],
+                    &rules,
+                    &mut breakdown,
+                ).expect("Error playing contract");
+                match mode {
+                    RenonceMode::Contract => assert_eq!(breakdown.game_points, -worth, "{}", game.code()),
+                    _ => assert_eq!(breakdown.personal, vec![(players[0].id.clone(), "Renonce".to_string(), -worth)], "{}", game.code()),
+                };
+            }
+        }
+    }
+
+    #[test]
+    fn contracts_reject_oversized_teams() {
+        let players = vec![user(CHAT_ID, "ANA"), user(CHAT_ID, "BOR"), user(CHAT_ID, "CENE"), user(CHAT_ID, "DAN")];
+        for game in [TarokGame::S1, TarokGame::B, TarokGame::BVS3, TarokGame::OB, TarokGame::BV, TarokGame::VL] {
+            assert!(contract_validity_check(game, &players[..1]).is_ok(), "{}", game.code());
+            assert!(contract_validity_check(game, &players[..2]).is_err(), "{}", game.code());
+        }
+        for game in [TarokGame::I3, TarokGame::BVI1] {
+            assert!(contract_validity_check(game, &players[..2]).is_ok(), "{}", game.code());
+            assert!(contract_validity_check(game, &players[..3]).is_err(), "{}", game.code());
+        }
+        assert!(contract_validity_check(TarokGame::KL, &players).is_ok());
+    }
+
+    #[test]
+    fn rejected_round_leaves_players_and_radlci_unchanged() {
+        setup_database();
+        let declarer = register(CHAT_ID, "REJANA");
+        register(CHAT_ID, "REJBOR");
+        register(CHAT_ID, "REJCENE");
+        let mut game = Tarok::new();
+        game.handle_round(message(CHAT_ID, "/round I3,10 REJANA REJBOR")).expect("Error playing round");
+        game.radlci.insert(declarer.id.clone(), vec![Radlc::Avalible]);
+
+        for round in [
+            "/round I3,10 REJANA,M:REJCENE REJBOR,M",
+            "/round I3,10 REJANA,M:REJANA REJBOR",
+            "/round I3,10 REJANA,R:REJANA REJBOR",
+            "/round I3,10 REJANA,R:REJANA REJCENE",
+            "/round S1,10 REJANA REJCENE",
+            "/round I3,10 REJANA REJBOR REJCENE",
+        ] {
+            assert!(game.handle_round(message(CHAT_ID, round)).is_err(), "{} was accepted", round);
+            assert_eq!(game.players.len(), 2);
+            assert_eq!(game.round, 1);
+            assert!(matches!(game.radlci.get(&declarer.id).map(|r| r.as_slice()), Some([Radlc::Avalible])));
+        }
+    }
+
+    fn assert_same_game(export: &GameExport, imported: &GameExport) {
+        assert_eq!(imported.rounds, export.rounds);
+        assert_eq!(imported.score, export.score);
+        assert_eq!(imported.totals, export.totals);
+        assert_eq!(imported.game_attributes, export.game_attributes);
+        // rounds a player sat out at the end of the game are only filled in on import
+        let padded = |export: &GameExport| -> HashMap<String, Vec<Option<Vec<String>>>> {
+            export.player_attributes
+                .iter()
+                .map(|(id, rounds)| {
+                    let mut rounds = rounds.clone();
+                    rounds.resize(export.rounds as usize, None);
+                    (id.clone(), rounds)
+                })
+                .collect()
+        };
+        assert_eq!(padded(imported), padded(export));
+        assert_eq!(imported.radlci, export.radlci);
+        assert_eq!(imported.dealers, export.dealers);
+    }
+
+    #[test]
+    fn export_and_import_keep_the_game() {
+        setup_database();
+        for name in ["RTANA", "RTBOR", "RTCENE", "RTDAN"] {
+            register(CHAT_ID, name);
+        }
+        let mut game = Tarok::new();
+        game.start_game(message(CHAT_ID, "/newgame RTANA RTBOR RTCENE RTDAN")).expect("Error starting game");
+        game.rules.mond_penalty = 30;
+        game.rules.radlc_contracts = vec![TarokGame::KL];
+        for round in [
+            "/round I3,15,T RTANA,M:RTCENE RTBOR",
+            "/round KL RTANA,-20 RTBOR,-10 RTCENE,-5 RTDAN,-35",
+            "/round S1,-10 RTCENE",
+        ] {
+            game.handle_round(message(CHAT_ID, round)).expect("Error playing round");
+        }
+        let export = game.export().expect("Error exporting game");
+        assert_eq!(export.seating.as_ref().map(|s| s.len()), Some(4));
+        assert_eq!(export.dealer, Some(game.dealer));
+
+        // through json, as the document is sent and read back
+        let json = export.to_json().expect("Error writing json");
+        let read: GameExport = serde_json::from_str(&json).expect("Error reading json");
+        let mut imported = Tarok::import(CHAT_ID.to_string(), &read, game.players.clone()).expect("Error importing game");
+        assert_same_game(&export, &imported.export().expect("Error exporting imported game"));
+        assert_eq!(imported.seating, game.seating);
+        assert_eq!(imported.dealer, game.dealer);
+        assert_eq!(imported.dealer, 3);
+        assert_eq!(imported.rules, game.rules);
+    }
+
+    #[test]
+    fn legend_shows_the_contract_values_of_the_rules() {
+        let mut game = Tarok::new();
+        game.rules.colour_valat = 175;
+        game.rules.announced_valat = 650;
+        let html = game.get_state(&Theme::Light).expect("Error rendering sheet");
+        assert!(html.contains("<td>BV</td><td>Colour valat</td><td>175</td>"), "{}", html);
+        assert!(html.contains("<td>VL</td><td>Valat</td><td>650</td>"), "{}", html);
+        assert!(html.contains("<td>I3</td><td>Three</td><td>10</td>"), "{}", html);
+    }
+
+    #[test]
+    fn import_without_seating_waits_for_seat() {
+        setup_database();
+        for name in ["NSANA", "NSBOR"] {
+            register(CHAT_ID, name);
+        }
+        let mut game = Tarok::new();
+        game.start_game(message(CHAT_ID, "/newgame NSANA NSBOR")).expect("Error starting game");
+        game.handle_round(message(CHAT_ID, "/round I3,10 NSANA NSBOR")).expect("Error playing round");
+        let mut export = game.export().expect("Error exporting game");
+        // exports written before the seating was exported
+        let json = export.to_json().expect("Error writing json")
+            .replace("\"seating\"", "\"old_seating\"")
+            .replace("\"dealer\"", "\"old_dealer\"")
+            .replace("\"rules\"", "\"old_rules\"");
+        let read: GameExport = serde_json::from_str(&json).expect("Error reading json");
+        assert!(read.seating.is_none() && read.dealer.is_none() && read.rules.is_none());
+        let imported = Tarok::import(CHAT_ID.to_string(), &read, game.players.clone()).expect("Error importing game");
+        assert!(imported.seating.is_empty());
+        assert_eq!(imported.dealer, 0);
+        assert_eq!(imported.rules, TarokRules::new());
+
+        // a seating of players that are not in the game is not restored
+        export.seating = Some(vec!["unknown".to_string()]);
+        let imported = Tarok::import(CHAT_ID.to_string(), &export, game.players.clone()).expect("Error importing game");
+        assert!(imported.seating.is_empty());
+    }
+
+    #[test]
+    fn round_rows_mark_who_played() {
+        setup_database();
+        let ana = register(CHAT_ID, "ROWANA");
+        let bor = register(CHAT_ID, "ROWBOR");
+        let cene = register(CHAT_ID, "ROWCENE");
+        let dan = register(CHAT_ID, "ROWDAN");
+        let mut game = Tarok::new();
+        game.handle_round(message(CHAT_ID, "/round I3,10 ROWANA ROWBOR,M:ROWCENE")).expect("Error playing round");
+        game.handle_round(message(CHAT_ID, "/round S1,10 ROWDAN")).expect("Error playing round");
+        let rows = game.take_round_rows();
+        assert!(game.take_round_rows().is_empty());
+        let played = |round: &str, player: &User| rows
+            .iter()
+            .find(|r| r.round_id == round && r.player_id == player.id)
+            .map(|r| r.tags.contains("played=1"));
+        assert_eq!(played("1", &ana), Some(true));
+        assert_eq!(played("1", &cene), Some(true));
+        assert_eq!(played("1", &dan), None);
+        // without a seating the defenders count as played
+        assert_eq!(played("2", &bor), Some(true));
+        assert_eq!(played("2", &dan), Some(true));
+
+        let end_rows = game.end_of_game_rows();
+        assert_eq!(end_rows.len(), 4);
+        assert!(end_rows.iter().all(|r| r.round_id == "end"));
+    }
+
+    #[test]
+    fn round_rows_follow_the_seating() {
+        setup_database();
+        let ana = register(CHAT_ID, "SROWANA");
+        let bor = register(CHAT_ID, "SROWBOR");
+        let cene = register(CHAT_ID, "SROWCENE");
+        let dan = register(CHAT_ID, "SROWDAN");
+        let eva = register(CHAT_ID, "SROWEVA");
+        let mut game = Tarok::new();
+        game.start_game(message(CHAT_ID, "/newgame SROWANA SROWBOR SROWCENE SROWDAN")).expect("Error starting game");
+        game.handle_round(message(CHAT_ID, "/round S1,10 SROWEVA")).expect("Error playing round");
+        game.handle_round(message(CHAT_ID, "/round I3,10 SROWANA SROWBOR")).expect("Error playing round");
+        let rows = game.take_round_rows();
+        let played = |round: &str, player: &User| rows
+            .iter()
+            .find(|r| r.round_id == round && r.player_id == player.id)
+            .map(|r| r.tags.contains("played=1"));
+        // named in the round without a seat still played
+        assert_eq!(played("1", &eva), Some(true));
+        assert_eq!(played("1", &dan), Some(true));
+        assert_eq!(played("2", &ana), Some(true));
+        assert_eq!(played("2", &cene), Some(true));
+        assert_eq!(played("2", &dan), Some(true));
+        assert_eq!(played("2", &eva), Some(false));
+        assert_eq!(played("2", &bor), Some(true));
+    }
+
+    #[test]
+    fn klop_is_not_a_declared_contract() {
+        setup_database();
+        for name in ["KLANA", "KLBOR", "KLCENE"] {
+            register(CHAT_ID, name);
+        }
+        let mut game = Tarok::new();
+        for round in [
+            "/round KL KLANA,-20 KLBOR,-10 KLCENE,-40",
+            "/round KL KLANA,-30 KLBOR,-20 KLCENE,-20",
+            "/round S3,10 KLBOR",
+        ] {
+            game.handle_round(message(CHAT_ID, round)).expect("Error playing round");
+        }
+        let sheet = game.score_sheet().expect("Error collecting sheet");
+        let declared: Vec<String> = game
+            .achievement_rules()
+            .iter()
+            .flat_map(|r| r.detect(&sheet))
+            .filter(|a| a.code == "most_declared")
+            .map(|a| format!("{} {}", a.player_id, a.description))
+            .collect();
+        let bor = game.players.iter().find(|p| p.name == "KLBOR").expect("Missing player");
+        assert_eq!(declared, vec![format!("{} Most contracts declared: 1", bor.id)]);
+    }
+
+    #[test]
+    fn rules_are_kept_for_the_next_game_of_the_chat() {
+        setup_database();
+        // a chat of its own, the changed rules would apply to the other tests
+        let chat_id = -10260;
+        let mut game = Tarok::new();
+        game.start_game(message(chat_id, "/newgame")).expect("Error starting game");
+        assert_eq!(game.rules, TarokRules::new());
+        game.handle_rules(message(chat_id, "/rules mond_penalty 35")).expect("Error setting rule");
+        game.handle_rules(message(chat_id, "/rules renonce_mode contract")).expect("Error setting rule");
+        assert!(game.handle_rules(message(chat_id, "/rules radlc_penalty lots")).is_err());
+
+        let mut next = Tarok::new();
+        next.start_game(message(chat_id, "/newgame")).expect("Error starting game");
+        assert_eq!(next.rules.mond_penalty, 35);
+        assert_eq!(next.rules.renonce_mode, RenonceMode::Contract);
+        assert_eq!(next.rules.radlc_penalty, TarokRules::new().radlc_penalty);
+
+        let mut other_chat = Tarok::new();
+        other_chat.start_game(message(chat_id - 1, "/newgame")).expect("Error starting game");
+        assert_eq!(other_chat.rules, TarokRules::new());
+    }
+}
@@ -1,22 +1,27 @@
 use std::collections::HashMap;
 
-use crate::models::user::User;
+use crate::{models::user::User, core::html_template::{Theme, Icon, icon, render_sheet, escape_html}};
 
-use super::enums::{Radlc, TarokGameInput, TarokPlayerInput};
+use super::{enums::{Radlc, TarokPlayerAttibute, TarokPlayerInput}, game::contract_values, rules::TarokRules};
 
-pub fn build_score_table_html(
-    players: &[User], 
-    score: &HashMap<String, Vec<Option<i32>>>, 
-    rounds: i32, 
-    sum_by_player: HashMap<String, (i32, i32, i32)>,
-    radlci: &HashMap<String, Vec<Radlc>>,
-    global_player_attributes: &mut HashMap<String, Vec<Option<Vec<TarokPlayerInput>>>>,
-    global_game_attributes: &mut Vec<Vec<TarokGameInput>>,
-) -> String {
+// the parts of a running game the score sheets are rendered from
+pub struct TarokSheet<'a> {
+    pub players: &'a [User],
+    pub score: &'a HashMap<String, Vec<Option<i32>>>,
+    pub rounds: i32,
+    pub sum_by_player: &'a HashMap<String, (i32, i32, i32)>,
+    pub radlci: &'a HashMap<String, Vec<Radlc>>,
+    pub dealers: &'a [Option<String>],
+    pub player_attributes: &'a HashMap<String, Vec<Option<Vec<TarokPlayerInput>>>>,
+    pub rules: &'a TarokRules,
+}
+
+pub fn build_score_table_html(sheet: &TarokSheet, theme: &Theme) -> String {
+    let TarokSheet { players, score, rounds, sum_by_player, radlci, dealers, player_attributes, rules } = *sheet;
     let mut table = String::from("");
     // generate table header
     for player in players.iter() {
-        let append = format!("<th>{}</th>", player.name);
+        let append = format!("<th>{}</th>", escape_html(&player.name));
         table = format!("{}{}", table,append)
     }
     table = format!("<tr>{}</tr>", table);
@@ -26,11 +31,11 @@ pub fn build_score_table_html(
     for player in players.iter() {
         // find field value for player's row
         let content = match radlci.get(&player.id) {
-            Some(radlci) => radlci_to_string(radlci),
+            Some(radlci) => radlci_to_string(radlci, rules.radlc_penalty),
             None => "".to_string(),
         };
         
-        let append = format!("<th>{}</th>", content);
+        let append = format!("<th class='radlci' title='Radlci'>{}</th>", content);
         line = format!("{}{}", line,append);
     }
     line = format!("<tr>{}</tr>", line);
@@ -40,15 +45,15 @@ pub fn build_score_table_html(
         let mut line = String::from("");
         for player in players.iter() {
             // find field value for player's row
-            
+            let dealer_marker = match dealers.get(index as usize) {
+                Some(Some(dealer)) if dealer == &player.id => icon(Icon::Dealer, "Dealer"),
+                _ => "".to_string(),
+            };
             let content = match score.get(&player.id) {
                 Some(score) => match score[index as usize] {
                     Some(val) => {
                         let mut class= "".to_string();
-                        let (_, min, max) = match sum_by_player.get(&player.id) {
-                            Some(data) => data,
-                            None => &(0, 0, 0),
-                        };
+                        let (_, min, max) = sum_by_player.get(&player.id).unwrap_or(&(0, 0, 0));
                         if val == *min {
                             class = "class='smallest'".to_string();
                         }
@@ -56,14 +61,15 @@ pub fn build_score_table_html(
                             class = "class='biggest'".to_string();
                         }
                         let aditional_markers = get_aditional_markers(
-                            global_player_attributes,
+                            players,
+                            player_attributes,
                             &player.id,
                             &index,
                         );
 
-                        format!("<td {}>{} {}</td>", class, val, aditional_markers)
+                        format!("<td {}>{} {}{}</td>", class, val, aditional_markers, dealer_marker)
                     },
-                    None => "<td></td>".to_string(),
+                    None => format!("<td>{}</td>", dealer_marker),
                 },
                 None => "<td>Missing</td>".to_string(),
             };
@@ -86,12 +92,13 @@ pub fn build_score_table_html(
     line = format!("<tr>{}</tr>", line);
     table = format!("{}{}", table, line);
 
-    format!("{}{}{}", get_html_head(), table, get_html_tail())
+    render_sheet("Tarok", players, &table, &generate_legend(rules), theme)
 
 }
 
 fn get_aditional_markers(
-    global_player_attributes: &mut HashMap<String, Vec<Option<Vec<TarokPlayerInput>>>>,
+    players: &[User],
+    global_player_attributes: &HashMap<String, Vec<Option<Vec<TarokPlayerInput>>>>,
     player_id: &String,
     round: &i32,
 ) -> String {
@@ -108,51 +115,89 @@ fn get_aditional_markers(
     };
     let markers = round_atrs
         .iter()
-        .map(|x| tarok_player_input_to_string(x))
+        .map(|x| tarok_player_input_to_string(players, x))
         .collect::<Vec<String>>()
         .join("");
-    format!("{}", markers)
+    markers.to_string()
 }
 
-fn tarok_player_input_to_string(x: &TarokPlayerInput) -> String {
+fn tarok_player_input_to_string(players: &[User], x: &TarokPlayerInput) -> String {
     match x {
         TarokPlayerInput::PlayerDiff(_) => "".to_string(),
         TarokPlayerInput::PlayerAttribute(a) => match a {
-            super::enums::TarokPlayerAttibute::M => "<i title='Mond snipe' class='fas fa-crosshairs'></i>".to_string(),
-            super::enums::TarokPlayerAttibute::R => "<i title='Renons' class='fas fa-hand-middle-finger'></i>".to_string(),
-            super::enums::TarokPlayerAttibute::T => "<i title='Renons' class='fas fa-users-slash'></i>".to_string(),
-            super::enums::TarokPlayerAttibute::Ig => "<i title='Renons' class='fas fa-dice'></i>".to_string(),
-            super::enums::TarokPlayerAttibute::Sl => "<i class='fal fa-truck-container'></i>".to_string(),
+            TarokPlayerAttibute::M(None) => icon(Icon::MondLost, "Mond lost"),
+            TarokPlayerAttibute::M(Some(captor)) => icon(
+                Icon::MondLost, 
+                &format!("Mond lost to {}", player_name(players, captor))
+            ),
+            TarokPlayerAttibute::Mc(loser) => icon(
+                Icon::MondCaptured, 
+                &format!("Mond captured from {}", player_name(players, loser))
+            ),
+            TarokPlayerAttibute::R(None) => icon(Icon::Renonce, "Renonce"),
+            TarokPlayerAttibute::R(Some(offender)) => icon(
+                Icon::RenonceBy, 
+                &format!("Renonce by {}", player_name(players, offender))
+            ),
+            TarokPlayerAttibute::T => icon(Icon::T, "T"),
+            TarokPlayerAttibute::Ig => icon(Icon::Declarer, "Declarer"),
+            TarokPlayerAttibute::Sl => icon(Icon::Supporting, "Supporting"),
         },
     }
 }
 
-fn radlci_to_string(radlci: &[Radlc]) -> String {
+fn player_name(players: &[User], id: &String) -> String {
+    match players.iter().find(|p| &p.id == id) {
+        Some(player) => player.name.clone(),
+        None => "".to_string(),
+    }
+}
+
+fn radlci_to_string(radlci: &[Radlc], radlc_penalty: i32) -> String {
     let mut out = "".to_string();
+    let mut unused = 0;
     for radl in radlci.iter() {
         if let Radlc::Avalible = radl {
             out = format!("{} O",out);
+            unused += 1;
         } else {
             out = format!("{} Ø",out);
         }
     }
+    // unused radlci are deducted from the final score
+    if unused > 0 {
+        out = format!("{} ({})", out, -unused * radlc_penalty);
+    }
     out
 }
 
-fn get_html_tail() -> String {
-    "</table>
-    <i title='Renons' class='fa-solid fa-truck-tow'></i>
-    <i class='fa-solid fa-truck-tow'></i>
-    <i class='fal fa-truck-container'></i>
-    </body>
-    </html>".to_string()
-}
-
-fn get_html_head() -> String {
-    "<!DOCTYPE html><html lang='en'><head><link rel='stylesheet' href='https://pro.fontawesome.com/releases/v5.10.0/css/all.css'
-    integrity='sha384-AYmEC3Yw5cVb3ZcuHtOA93w35dYTsvhLPVnYs9eStHfGJvOvKxVfELGroGkvsg+p' crossorigin='anonymous' />
-    <meta charset='UTF-8'><meta http-equiv='X-UA-Compatible' content='IE=edge'><meta name='viewport' content='width=device-width, initial-scale=1.0'>
-    <title>Document</title></head><body><style>
-    table{width: 100%;text-align: center;}tr:nth-child(2n+1) {background-color: rgb(229 228 228)}.biggest {color: 
-    green;}.smallest {color: red}</style><table>".to_string()
+fn generate_legend(rules: &TarokRules) -> String {
+    let icons = [
+        (Icon::Declarer, "Declarer"),
+        (Icon::Supporting, "Supporting"),
+        (Icon::MondLost, "Mond lost"),
+        (Icon::MondCaptured, "Mond captured"),
+        (Icon::Renonce, "Renonce"),
+        (Icon::RenonceBy, "Renonce by an opponent"),
+        (Icon::Dealer, "Dealer"),
+    ];
+    let mut markers = String::from("");
+    for (marker, title) in icons.into_iter() {
+        markers = format!("{}<tr><td>{}</td><td>{}</td></tr>", markers, icon(marker, title), title);
+    }
+    let mut rows = String::from("");
+    for (code, name, value) in contract_values(rules).into_iter() {
+        rows = format!(
+            "{}<tr><td>{}</td><td>{}</td><td>{}</td></tr>", 
+            rows, 
+            code, 
+            name, 
+            value
+        );
+    }
+    format!(
+        "<table class='legend'>{}</table><table class='legend'><tr><th>Code</th><th>Contract</th><th>Value</th></tr>{}</table>", 
+        markers, 
+        rows
+    )
 }
\ No newline at end of file
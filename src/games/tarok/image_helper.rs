@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use crate::core::score_image::{ScoreGrid, GridRow, RowStyle, CellTone};
+
+use super::{enums::{Radlc, TarokPlayerAttibute, TarokPlayerInput}, html_helper::TarokSheet};
+
+// same sheet as build_score_table_html, with letters in place of the icons
+pub fn build_score_grid(sheet: &TarokSheet) -> ScoreGrid {
+    let TarokSheet { players, score, rounds, sum_by_player, radlci, dealers, player_attributes, rules } = *sheet;
+    let mut rows = vec![];
+    rows.push(GridRow::new(RowStyle::Header, players.iter().map(|p| p.name.clone()).collect()));
+    rows.push(GridRow::new(
+        RowStyle::Radlc,
+        players
+            .iter()
+            .map(|p| match radlci.get(&p.id) {
+                Some(radlci) => radlci_to_string(radlci, rules.radlc_penalty),
+                None => "".to_string(),
+            })
+            .collect()
+    ));
+    for index in 0..rounds as usize {
+        let mut cells = vec![];
+        for player in players.iter() {
+            let dealer_marker = match dealers.get(index) {
+                Some(Some(dealer)) if dealer == &player.id => "D",
+                _ => "",
+            };
+            let cell = match score.get(&player.id).and_then(|s| s.get(index)) {
+                Some(Some(val)) => {
+                    let (_, min, max) = match sum_by_player.get(&player.id) {
+                        Some(data) => *data,
+                        None => (0, 0, 0),
+                    };
+                    let tone = if *val == max {
+                        CellTone::Best
+                    } else if *val == min {
+                        CellTone::Worst
+                    } else {
+                        CellTone::Normal
+                    };
+                    let markers = format!("{}{}", player_markers(player_attributes, &player.id, index), dealer_marker);
+                    (format!("{} {}", val, markers).trim().to_string(), tone)
+                },
+                _ => (dealer_marker.to_string(), CellTone::Normal),
+            };
+            cells.push(cell);
+        }
+        rows.push(GridRow { style: RowStyle::Round, cells });
+    }
+    rows.push(GridRow::new(
+        RowStyle::Total,
+        players
+            .iter()
+            .map(|p| match sum_by_player.get(&p.id) {
+                Some((sum, _, _)) => sum.to_string(),
+                None => "".to_string(),
+            })
+            .collect()
+    ));
+    rows.push(GridRow::new(
+        RowStyle::Note,
+        vec!["I declarer, S supporting, M mond lost, C mond captured, R renonce, D dealer".to_string()]
+    ));
+    rows.push(GridRow::new(RowStyle::Note, vec!["Radlci: O unused, X used".to_string()]));
+    ScoreGrid { rows }
+}
+
+fn player_markers(
+    global_player_attributes: &HashMap<String, Vec<Option<Vec<TarokPlayerInput>>>>,
+    player_id: &String,
+    round: usize,
+) -> String {
+    let round_atrs = match global_player_attributes.get(player_id).and_then(|a| a.get(round)) {
+        Some(Some(att)) => att,
+        _ => return "".to_string(),
+    };
+    round_atrs
+        .iter()
+        .map(|x| match x {
+            TarokPlayerInput::PlayerDiff(_) => "",
+            TarokPlayerInput::PlayerAttribute(a) => match a {
+                TarokPlayerAttibute::M(_) => "M",
+                TarokPlayerAttibute::Mc(_) => "C",
+                TarokPlayerAttibute::R(_) => "R",
+                TarokPlayerAttibute::T => "T",
+                TarokPlayerAttibute::Ig => "I",
+                TarokPlayerAttibute::Sl => "S",
+            },
+        })
+        .collect()
+}
+
+fn radlci_to_string(radlci: &[Radlc], radlc_penalty: i32) -> String {
+    let mut marks = vec![];
+    let mut unused = 0;
+    for radl in radlci.iter() {
+        if let Radlc::Avalible = radl {
+            marks.push("O");
+            unused += 1;
+        } else {
+            marks.push("X");
+        }
+    }
+    // unused radlci are deducted from the final score
+    match unused {
+        0 => marks.join(" "),
+        _ => format!("{} ({})", marks.join(" "), -unused * radlc_penalty),
+    }
+}
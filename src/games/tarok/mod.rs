@@ -1,3 +1,8 @@
+mod achievements;
+mod breakdown;
 mod enums;
 pub mod game;
-mod html_helper;
\ No newline at end of file
+mod html_helper;
+mod image_helper;
+pub mod rules;
+pub mod stats;
\ No newline at end of file
@@ -0,0 +1,196 @@
+use std::io::Error;
+
+use serde::{Serialize, Deserialize};
+
+use crate::{core::database::rules_operations::{get_chat_rules, save_chat_rules}, models::rules::ChatRules};
+
+use super::enums::TarokGame;
+
+// rules missing from stored json (added later) keep their defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TarokRules {
+    pub mond_penalty: i32,
+    pub mond_capture_credit: i32,
+    pub mond_waived_in_klop: bool,
+    pub mond_waived_in_colour_valat: bool,
+    pub renonce_mode: RenonceMode,
+    pub renonce_penalty: i32,
+    pub radlc_contracts: Vec<TarokGame>,
+    pub radlc_penalty: i32,
+    pub valat: i32,
+    pub announced_valat: i32,
+    pub colour_valat: i32,
+    pub announced_colour_valat: i32,
+    pub klop_dealer_repeats: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RenonceMode {
+    Off,
+    Fixed,      // offender pays renonce_penalty
+    Offender,   // offender pays the contract value (renonce_penalty in klop)
+    Contract,   // the contract is awarded to the offender's opponents
+}
+
+impl TarokRules {
+    pub fn new() -> Self {
+        Self {
+            mond_penalty: 20,
+            mond_capture_credit: 0,
+            mond_waived_in_klop: false,
+            mond_waived_in_colour_valat: false,
+            renonce_mode: RenonceMode::Offender,
+            renonce_penalty: 70,
+            radlc_contracts: vec![
+                TarokGame::SB, TarokGame::KL, TarokGame::B, TarokGame::P, TarokGame::BVI3, TarokGame::BVI2, 
+                TarokGame::BVI1, TarokGame::BVS3, TarokGame::BVS2, TarokGame::BVS1, TarokGame::BVSB, 
+                TarokGame::OB, TarokGame::BV, TarokGame::VL,
+            ],
+            radlc_penalty: 100,
+            valat: 250,
+            announced_valat: 500,
+            colour_valat: 125,
+            announced_colour_valat: 250,
+            klop_dealer_repeats: false,
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        match key.to_lowercase().as_str() {
+            "mond_penalty" => self.mond_penalty = parse_points(value)?,
+            "mond_capture_credit" => self.mond_capture_credit = parse_points(value)?,
+            "mond_waived_in_klop" => self.mond_waived_in_klop = parse_switch(value)?,
+            "mond_waived_in_colour_valat" => self.mond_waived_in_colour_valat = parse_switch(value)?,
+            "renonce_mode" => self.renonce_mode = parse_renonce_mode(value)?,
+            "renonce_penalty" => self.renonce_penalty = parse_points(value)?,
+            "radlc_contracts" => self.radlc_contracts = parse_contracts(value)?,
+            "radlc_penalty" => self.radlc_penalty = parse_points(value)?,
+            "valat" => self.valat = parse_points(value)?,
+            "announced_valat" => self.announced_valat = parse_points(value)?,
+            "colour_valat" => self.colour_valat = parse_points(value)?,
+            "announced_colour_valat" => self.announced_colour_valat = parse_points(value)?,
+            "klop_dealer_repeats" => self.klop_dealer_repeats = parse_switch(value)?,
+            _ => return Err(Error::other(format!("Unknown rule: {}", key))),
+        };
+        Ok(())
+    }
+
+    pub fn describe(&self) -> String {
+        [
+            format!("mond_penalty {}", self.mond_penalty),
+            format!("mond_capture_credit {}", self.mond_capture_credit),
+            format!("mond_waived_in_klop {}", switch_to_string(self.mond_waived_in_klop)),
+            format!("mond_waived_in_colour_valat {}", switch_to_string(self.mond_waived_in_colour_valat)),
+            format!("renonce_mode {} (off|fixed|offender|contract)", renonce_mode_to_string(self.renonce_mode)),
+            format!("renonce_penalty {}", self.renonce_penalty),
+            format!("radlc_contracts {}", contracts_to_string(&self.radlc_contracts)),
+            format!("radlc_penalty {}", self.radlc_penalty),
+            format!("valat {}", self.valat),
+            format!("announced_valat {}", self.announced_valat),
+            format!("colour_valat {}", self.colour_valat),
+            format!("announced_colour_valat {}", self.announced_colour_valat),
+            format!("klop_dealer_repeats {}", switch_to_string(self.klop_dealer_repeats)),
+        ].join("\n")
+    }
+}
+
+impl Default for TarokRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// rules the chat set with /rules, defaults until it changes any
+pub fn load_rules(chat_id: String) -> Result<TarokRules, Error> {
+    let stored = match get_chat_rules(chat_id) {
+        Ok(stored) => stored,
+        Err(e) => return Err(Error::other(format!("Error fetching rules from DB: {}", e))),
+    };
+    match stored {
+        Some(stored) => match serde_json::from_str(&stored.data) {
+            Ok(rules) => Ok(rules),
+            Err(e) => Err(Error::other(format!("Error reading stored rules: {}", e))),
+        },
+        None => Ok(TarokRules::new()),
+    }
+}
+
+pub fn save_rules(chat_id: String, rules: &TarokRules) -> Result<(), Error> {
+    let data = match serde_json::to_string(rules) {
+        Ok(data) => data,
+        Err(e) => return Err(Error::other(format!("Error writing rules: {}", e))),
+    };
+    match save_chat_rules(ChatRules::from(chat_id, data)) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::other(format!("Error saving rules to DB: {}", e))),
+    }
+}
+
+fn parse_points(value: &str) -> Result<i32, Error> {
+    match value.parse() {
+        Ok(val) => Ok(val),
+        Err(_) => Err(Error::other(format!("Expected a number of points, got: {}", value))),
+    }
+}
+
+fn parse_switch(value: &str) -> Result<bool, Error> {
+    match value.to_lowercase().as_str() {
+        "on" | "yes" | "true" => Ok(true),
+        "off" | "no" | "false" => Ok(false),
+        _ => Err(Error::other(format!("Expected on/off, got: {}", value))),
+    }
+}
+
+fn parse_contracts(value: &str) -> Result<Vec<TarokGame>, Error> {
+    // comma separated list of contract codes, or "none"
+    if value.to_lowercase() == "none" {
+        return Ok(vec![]);
+    }
+    let mut contracts = vec![];
+    for code in value.split(',') {
+        match TarokGame::from_code(code) {
+            Some(game) => contracts.push(game),
+            None => return Err(Error::other(format!("Unknown contract: {}", code))),
+        };
+    }
+    Ok(contracts)
+}
+
+fn contracts_to_string(contracts: &[TarokGame]) -> String {
+    if contracts.is_empty() {
+        return "none".to_string();
+    }
+    contracts
+        .iter()
+        .map(|g| g.code())
+        .collect::<Vec<&str>>()
+        .join(",")
+}
+
+fn parse_renonce_mode(value: &str) -> Result<RenonceMode, Error> {
+    match value.to_lowercase().as_str() {
+        "off" => Ok(RenonceMode::Off),
+        "fixed" => Ok(RenonceMode::Fixed),
+        "offender" => Ok(RenonceMode::Offender),
+        "contract" => Ok(RenonceMode::Contract),
+        _ => Err(Error::other(format!("Unknown renonce mode: {}", value))),
+    }
+}
+
+fn renonce_mode_to_string(mode: RenonceMode) -> &'static str {
+    match mode {
+        RenonceMode::Off => "off",
+        RenonceMode::Fixed => "fixed",
+        RenonceMode::Offender => "offender",
+        RenonceMode::Contract => "contract",
+    }
+}
+
+fn switch_to_string(value: bool) -> &'static str {
+    match value {
+        true => "on",
+        false => "off",
+    }
+}
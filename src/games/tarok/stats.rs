@@ -0,0 +1,374 @@
+use std::{collections::HashMap, io::Error};
+
+use crate::{
+    core::database::{round_operations::get_rounds_by_chat, user_operations::{get_user_by_name, get_users_by_chat}},
+    models::{round::Round, user::User}
+};
+
+struct PlayerStats {
+    rounds_played: i32,
+    declared: HashMap<String, (i32, i32)>, // contract code -> (declared, won)
+    diff_sum: i32,
+    diff_count: i32,
+    bonuses: HashMap<&'static str, i32>,
+    monds_lost: i32,
+    radlci_earned: i32,
+    radlci_used: i32,
+    radlci_forfeited: i32,
+    partner_rounds: i32,
+    partner_won: i32,
+}
+
+impl PlayerStats {
+    fn new() -> Self {
+        Self {
+            rounds_played: 0,
+            declared: HashMap::new(),
+            diff_sum: 0,
+            diff_count: 0,
+            bonuses: HashMap::new(),
+            monds_lost: 0,
+            radlci_earned: 0,
+            radlci_used: 0,
+            radlci_forfeited: 0,
+            partner_rounds: 0,
+            partner_won: 0,
+        }
+    }
+}
+
+pub fn tarok_stats(chat_id: String, player_name: Option<String>) -> Result<String, Error> {
+    let rounds = match get_rounds_by_chat(chat_id.clone()) {
+        Ok(rounds) => rounds,
+        Err(e) => return Err(Error::other(format!("Error fetching rounds from DB: {}", e))),
+    };
+    match player_name {
+        Some(name) => {
+            let user = match get_user_by_name(name.to_uppercase()) {
+                Ok(Some(user)) => user,
+                Ok(None) => return Err(Error::other(format!("Unknown player {}", name))),
+                Err(e) => return Err(Error::other(format!("Error fetching user from DB: {}", e))),
+            };
+            let stats = collect_player_stats(&rounds, &user);
+            Ok(describe_player_stats(&user, &stats))
+        },
+        None => {
+            let users = match get_users_by_chat(chat_id) {
+                Ok(users) => users,
+                Err(e) => return Err(Error::other(format!("Error fetching users from DB: {}", e))),
+            };
+            let mut lines = vec!["Tarok stats (use /stats <player> for details):".to_string()];
+            for user in users.iter() {
+                let stats = collect_player_stats(&rounds, user);
+                if stats.rounds_played == 0 {
+                    continue;
+                }
+                let (declared, won) = stats.declared
+                    .values()
+                    .fold((0, 0), |(d, w), (dd, ww)| (d + dd, w + ww));
+                lines.push(format!(
+                    "{}: {} rounds, {} declared, {} won",
+                    user.name,
+                    stats.rounds_played,
+                    declared,
+                    percentage(won, declared),
+                ));
+            }
+            Ok(lines.join("\n"))
+        },
+    }
+}
+
+pub(crate) fn parse_tags(tags: &str) -> HashMap<&str, &str> {
+    tags.split(';')
+        .filter_map(|tag| tag.split_once('='))
+        .collect()
+}
+
+pub(crate) fn split_tag_list(list: &str) -> Vec<&str> {
+    list.split(',').filter(|t| !t.is_empty()).collect()
+}
+
+fn collect_player_stats(rounds: &[Round], user: &User) -> PlayerStats {
+    let mut stats = PlayerStats::new();
+    for round in rounds.iter().filter(|r| r.player_id == user.id) {
+        let tags = parse_tags(&round.tags);
+        // end of game summary row
+        if round.round_id == "end" {
+            stats.radlci_forfeited += tag_number(&tags, "radlc_forfeited");
+            continue;
+        }
+        // everyone gets the radlc, the rest only counts for players at the table
+        // (rows written before the played tag count as played)
+        stats.radlci_earned += tag_number(&tags, "radlc_gained");
+        if tags.get("played") == Some(&"0") {
+            continue;
+        }
+        stats.rounds_played += 1;
+        stats.radlci_used += tag_number(&tags, "radlc_used");
+
+        let player_attributes = split_tag_list(tags.get("player").unwrap_or(&""));
+        let game_attributes = split_tag_list(tags.get("attrs").unwrap_or(&""));
+        let game = tags.get("game").unwrap_or(&"").to_string();
+        let won = tag_number(&tags, "won") == 1;
+
+        stats.monds_lost += player_attributes
+            .iter()
+            .filter(|a| **a == "M" || a.starts_with("M:"))
+            .count() as i32;
+
+        let declarer = player_attributes.contains(&"IG");
+        let supporter = player_attributes.contains(&"SL");
+        // klop is not declared by anyone
+        if declarer && game != "KL" {
+            let entry = stats.declared.entry(game).or_insert((0, 0));
+            entry.0 += 1;
+            if won { entry.1 += 1; }
+            if let Some(diff) = game_attributes.iter().find_map(|a| a.parse::<i32>().ok()) {
+                stats.diff_sum += diff;
+                stats.diff_count += 1;
+            }
+        }
+        if supporter {
+            stats.partner_rounds += 1;
+            if won { stats.partner_won += 1; }
+        }
+        if declarer || supporter {
+            for attr in game_attributes.iter() {
+                if let Some(bonus) = bonus_name(attr) {
+                    *stats.bonuses.entry(bonus).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    stats
+}
+
+fn describe_player_stats(user: &User, stats: &PlayerStats) -> String {
+    let mut lines = vec![format!("Tarok stats for {}", user.name)];
+    lines.push(format!("Rounds played: {}", stats.rounds_played));
+    lines.push("Contracts declared:".to_string());
+    let mut contracts: Vec<(&String, &(i32, i32))> = stats.declared.iter().collect();
+    contracts.sort_by(|a, b| b.1.0.cmp(&a.1.0).then(a.0.cmp(b.0)));
+    for (contract, (declared, won)) in contracts.iter() {
+        lines.push(format!("  {}: {} (won {})", contract, declared, percentage(*won, *declared)));
+    }
+    if stats.diff_count > 0 {
+        lines.push(format!("Average diff: {:.1}", stats.diff_sum as f32 / stats.diff_count as f32));
+    }
+    lines.push(format!(
+        "Bonuses: trula {}, kralji {}, pagat ultimo {}, king ultimo {}",
+        stats.bonuses.get("trula").unwrap_or(&0),
+        stats.bonuses.get("kralji").unwrap_or(&0),
+        stats.bonuses.get("pagat").unwrap_or(&0),
+        stats.bonuses.get("kralj").unwrap_or(&0),
+    ));
+    lines.push(format!("Monds lost: {}", stats.monds_lost));
+    lines.push(format!(
+        "Radlci: {} earned, {} used, {} forfeited",
+        stats.radlci_earned,
+        stats.radlci_used,
+        stats.radlci_forfeited,
+    ));
+    lines.push(format!(
+        "Partner success: {}/{} ({})",
+        stats.partner_won,
+        stats.partner_rounds,
+        percentage(stats.partner_won, stats.partner_rounds),
+    ));
+    lines.join("\n")
+}
+
+fn bonus_name(attr: &str) -> Option<&'static str> {
+    match attr {
+        "T" | "NT" => Some("trula"),
+        "K" | "NK" => Some("kralji"),
+        "ZP" | "NZP" => Some("pagat"),
+        "ZK" | "NZK" => Some("kralj"),
+        _ => None,
+    }
+}
+
+fn tag_number(tags: &HashMap<&str, &str>, key: &str) -> i32 {
+    match tags.get(key).map(|v| v.parse()) {
+        Some(Ok(val)) => val,
+        _ => 0,
+    }
+}
+
+fn percentage(part: i32, whole: i32) -> String {
+    if whole == 0 {
+        return "-".to_string();
+    }
+    format!("{}%", part * 100 / whole)
+}
+
+// rounds where both players played: as partners (both in the declaring team) or as opponents
+pub fn partnership_summary(chat_id: String, a: &User, b: &User) -> Result<Option<String>, Error> {
+    let rounds = match get_rounds_by_chat(chat_id) {
+        Ok(rounds) => rounds,
+        Err(e) => return Err(Error::other(format!("Error fetching rounds from DB: {}", e))),
+    };
+    let mut by_round: HashMap<(&String, &String), Vec<&Round>> = HashMap::new();
+    for round in rounds.iter().filter(|r| r.round_id != "end") {
+        by_round.entry((&round.game_id, &round.round_id)).or_default().push(round);
+    }
+    let (mut partner_rounds, mut partner_won) = (0, 0);
+    let (mut opponent_rounds, mut a_won, mut b_won) = (0, 0, 0);
+    for rows in by_round.values() {
+        let row_a = rows.iter().find(|r| r.player_id == a.id);
+        let row_b = rows.iter().find(|r| r.player_id == b.id);
+        let (tags_a, tags_b) = match (row_a, row_b) {
+            (Some(row_a), Some(row_b)) => (parse_tags(&row_a.tags), parse_tags(&row_b.tags)),
+            _ => continue,
+        };
+        // a player who sat the round out was neither partner nor opponent
+        if tags_a.get("played") == Some(&"0") || tags_b.get("played") == Some(&"0") {
+            continue;
+        }
+        // everyone plays for themselves in klop
+        if tags_a.get("game") == Some(&"KL") {
+            continue;
+        }
+        let won = tag_number(&tags_a, "won") == 1;
+        match (in_team(&tags_a), in_team(&tags_b)) {
+            (true, true) => {
+                partner_rounds += 1;
+                if won { partner_won += 1; }
+            },
+            (true, false) | (false, true) => {
+                opponent_rounds += 1;
+                if won == in_team(&tags_a) { a_won += 1; } else { b_won += 1; }
+            },
+            (false, false) => (),
+        };
+    }
+    if partner_rounds == 0 && opponent_rounds == 0 {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "Tarok as partners: {} rounds, won {} ({})\nTarok as opponents: {} rounds, {} won {}, {} won {}",
+        partner_rounds,
+        partner_won,
+        percentage(partner_won, partner_rounds),
+        opponent_rounds,
+        a.name,
+        a_won,
+        b.name,
+        b_won,
+    )))
+}
+
+fn in_team(tags: &HashMap<&str, &str>) -> bool {
+    let player_attributes = split_tag_list(tags.get("player").unwrap_or(&""));
+    player_attributes.contains(&"IG") || player_attributes.contains(&"SL")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        core::database::{round_operations::insert_rounds, test_database::{setup_database, user, register}},
+        models::{round::Round, user::User},
+    };
+
+    use super::{collect_player_stats, parse_tags, partnership_summary, split_tag_list};
+
+    const CHAT_ID: i64 = -1039;
+
+    fn row(round: &str, player: &User, tags: &str) -> Round {
+        Round::from(CHAT_ID.to_string(), round.to_string(), "game".to_string(), player.id.clone(), tags.to_string())
+    }
+
+    #[test]
+    fn tags_are_parsed() {
+        let tags = parse_tags("game=I3;attrs=I3,15,T;player=IG,M:ANA;score=;broken");
+        assert_eq!(tags.get("game"), Some(&"I3"));
+        assert_eq!(tags.get("attrs"), Some(&"I3,15,T"));
+        assert_eq!(tags.get("player"), Some(&"IG,M:ANA"));
+        assert_eq!(tags.get("score"), Some(&""));
+        assert_eq!(tags.get("broken"), None);
+        assert_eq!(split_tag_list("IG,M:ANA"), vec!["IG", "M:ANA"]);
+        assert!(split_tag_list("").is_empty());
+    }
+
+    #[test]
+    fn rounds_are_aggregated() {
+        let ana = user(CHAT_ID, "ANA");
+        let rounds = vec![
+            row("1", &ana, "game=I3;attrs=I3,15,T;player=IG,M;score=40;won=1;radlc_gained=0;radlc_used=0;played=1"),
+            row("2", &ana, "game=S1;attrs=S1,-5;player=IG;score=-130;won=0;radlc_gained=1;radlc_used=1;played=1"),
+            row("3", &ana, "game=I2;attrs=I2,25,K;player=SL;score=45;won=1;radlc_gained=0;radlc_used=0;played=1"),
+            row("4", &ana, "game=KL;attrs=KL;player=IG,-20;score=-20;won=1;radlc_gained=1;radlc_used=0;played=1"),
+            // sat out, only the radlc everyone gets counts
+            row("5", &ana, "game=B;attrs=B;player=;score=;won=1;radlc_gained=1;radlc_used=0;played=0"),
+            row("end", &ana, "total=-65;radlc_forfeited=2"),
+            row("1", &user(CHAT_ID, "BOR"), "game=I3;attrs=I3,15;player=SL;score=40;won=1;played=1"),
+        ];
+        let stats = collect_player_stats(&rounds, &ana);
+        assert_eq!(stats.rounds_played, 4);
+        assert_eq!(stats.declared.get("I3"), Some(&(1, 1)));
+        assert_eq!(stats.declared.get("S1"), Some(&(1, 0)));
+        assert_eq!(stats.declared.get("KL"), None);
+        assert_eq!((stats.diff_sum, stats.diff_count), (10, 2));
+        assert_eq!(stats.bonuses.get("trula"), Some(&1));
+        assert_eq!(stats.bonuses.get("kralji"), Some(&1));
+        assert_eq!(stats.monds_lost, 1);
+        assert_eq!((stats.radlci_earned, stats.radlci_used, stats.radlci_forfeited), (3, 1, 2));
+        assert_eq!((stats.partner_rounds, stats.partner_won), (1, 1));
+    }
+
+    #[test]
+    fn rows_without_played_tag_count_as_played() {
+        let ana = user(CHAT_ID, "ANA");
+        let rounds = vec![row("1", &ana, "game=I3;attrs=I3;player=;score=;won=1;radlc_gained=0;radlc_used=0")];
+        assert_eq!(collect_player_stats(&rounds, &ana).rounds_played, 1);
+    }
+
+    fn round_row(round: &str, player: &User, game: &str, player_tags: &str, won: bool) -> Round {
+        Round::from(
+            CHAT_ID.to_string(),
+            round.to_string(),
+            "partnership".to_string(),
+            player.id.clone(),
+            format!("game={};attrs={};player={};won={};played=1", game, game, player_tags, won as i32),
+        )
+    }
+
+    #[test]
+    fn partnerships_count_partners_and_opponents() {
+        setup_database();
+        let (ana, bor, cene, dan) = (register(CHAT_ID, "PARANA"), register(CHAT_ID, "PARBOR"), register(CHAT_ID, "PARCENE"), register(CHAT_ID, "PARDAN"));
+        insert_rounds(vec![
+            // partners, won
+            round_row("1", &ana, "I3", "IG", true),
+            round_row("1", &bor, "I3", "SL", true),
+            // ana declares and loses against bor
+            round_row("2", &ana, "S1", "IG", false),
+            round_row("2", &bor, "S1", "", false),
+            // klop has no partners or opponents
+            round_row("3", &ana, "KL", "IG,-10", true),
+            round_row("3", &bor, "KL", "-20", true),
+            // bor declares and wins against ana and cene
+            round_row("4", &ana, "S2", "", true),
+            round_row("4", &bor, "S2", "IG", true),
+            round_row("4", &cene, "S2", "", true),
+            // ana declares, cene sits out
+            round_row("5", &ana, "S1", "IG", true),
+            Round::from(CHAT_ID.to_string(), "5".to_string(), "partnership".to_string(), cene.id.clone(), "game=S1;attrs=S1;player=;won=0;played=0".to_string()),
+            Round::from(CHAT_ID.to_string(), "end".to_string(), "partnership".to_string(), ana.id.clone(), "total=10".to_string()),
+        ]).expect("Error saving rounds");
+
+        let summary = partnership_summary(CHAT_ID.to_string(), &ana, &bor).expect("Error reading rounds");
+        assert_eq!(
+            summary.as_deref(),
+            Some("Tarok as partners: 1 rounds, won 1 (100%)\nTarok as opponents: 2 rounds, PARANA won 0, PARBOR won 2")
+        );
+        // the order of the pair only swaps the names
+        let swapped = partnership_summary(CHAT_ID.to_string(), &bor, &ana).expect("Error reading rounds");
+        assert!(swapped.unwrap().ends_with("PARBOR won 2, PARANA won 0"));
+        // neither in the playing team, or sat out
+        assert_eq!(partnership_summary(CHAT_ID.to_string(), &ana, &cene).expect("Error reading rounds"), None);
+        // never met
+        assert_eq!(partnership_summary(CHAT_ID.to_string(), &ana, &dan).expect("Error reading rounds"), None);
+    }
+}
@@ -1,14 +1,29 @@
 
+use controllers::balance::balance;
+use controllers::chart::chart;
 use controllers::end_game::end_game;
-use controllers::game_state::game_state;
+use controllers::explain::explain;
+use controllers::export::export;
+use controllers::game_state::{game_state, ScoreOutput};
+use controllers::h2h::h2h;
+use controllers::import::import;
+use controllers::leaderboard::leaderboard;
+use controllers::me::me;
 use controllers::new_game::new_game;
+use controllers::radlc::radlc;
+use controllers::rating::rating;
 use controllers::register::register;
+use controllers::rules::rules;
 use controllers::score_round::score_round;
+use controllers::season::season;
+use controllers::seat::seat;
+use controllers::settle::settle;
+use controllers::stats::stats;
+use controllers::tournament::tournament;
 use teloxide::Bot;
-use teloxide::types::Message;
+use teloxide::types::{Message, ParseMode};
 use teloxide::utils::command::BotCommands;
 use teloxide::prelude::*;
-use std::result::Result;
 use std::env;
 use dotenv::dotenv;
 
@@ -30,7 +45,7 @@ async fn main() {
     pretty_env_logger::init();
     let bot = Bot::from_env();
     println!("Running telegram bot!");
-    teloxide::commands_repl(bot, answer, Command::ty()).await;
+    Command::repl(bot, answer).await;
 }
 
 #[derive(BotCommands, Clone)]
@@ -38,7 +53,7 @@ async fn main() {
 enum Command {
     #[command(description = "display this text.")]
     Help,
-    #[command(description = "Start new game")]
+    #[command(description = "Start new game, optionally with the seating order")]
     NewGame,
     #[command(description = "Unsubscribe from jobs")]
     EndGame,
@@ -46,8 +61,40 @@ enum Command {
     Register,
     #[command(description = "Submit a round of a game")]
     Round,
-    #[command(description = "Current score of game")]
+    #[command(description = "Current score of game: image, text or html. /score default <format> sets the chat default, /score theme light|dark|print the html look")]
     Score,
+    #[command(description = "Show or change rules of the game, kept for the next games")]
+    Rules,
+    #[command(description = "Seat the players in order to track the dealer: <players>")]
+    Seat,
+    #[command(description = "Show, add or remove radlci")]
+    Radlc,
+    #[command(description = "Explain how a round was scored")]
+    Explain,
+    #[command(description = "Tarok statistics of players")]
+    Stats,
+    #[command(description = "Leaderboard of finished games: [all|month|last N] [tarok|table] [image]")]
+    Leaderboard,
+    #[command(description = "Player ratings and rating history: [player] [tarok|table]")]
+    Rating,
+    #[command(description = "Show or change money settlement: [mode off|zerosum|winner] [rate cents]")]
+    Settle,
+    #[command(description = "Running money balance of the chat")]
+    Balance,
+    #[command(description = "Manage the league season: start [name] [sum|placement 3,2,1,0|best N], end, status")]
+    Season,
+    #[command(description = "Tarok tournament: join|leave <players>, next, round <table> <round>, score <table>, finish <table>, standings, end")]
+    Tournament,
+    #[command(description = "Head-to-head of two players: <player> <player>")]
+    H2h,
+    #[command(description = "Achievements of a player, yours by default")]
+    Me,
+    #[command(description = "Export the running or an archived game: csv|json|xlsx|ods|pdf [game id]")]
+    Export,
+    #[command(description = "Reply to an exported document to archive it or continue playing: archive|play [create]")]
+    Import,
+    #[command(description = "Running totals of the game as a chart: png or svg")]
+    Chart,
 }
 
 
@@ -64,6 +111,22 @@ async fn answer(
         Command::EndGame => end_game_handler(bot, message).await,
         Command::Score => game_state_handler(bot, message).await,
         Command::Round => { bot.send_message(message.chat.id, score_round(&bot, message).await).await?; },
+        Command::Rules => { bot.send_message(message.chat.id, rules(&bot, message).await).await?; },
+        Command::Seat => { bot.send_message(message.chat.id, seat(&bot, message).await).await?; },
+        Command::Radlc => { bot.send_message(message.chat.id, radlc(&bot, message).await).await?; },
+        Command::Explain => { bot.send_message(message.chat.id, explain(&bot, message).await).await?; },
+        Command::Stats => { bot.send_message(message.chat.id, stats(&bot, message)).await?; },
+        Command::Leaderboard => leaderboard_handler(bot, message).await,
+        Command::Rating => { bot.send_message(message.chat.id, rating(&bot, message)).await?; },
+        Command::Settle => { bot.send_message(message.chat.id, settle(&bot, message)).await?; },
+        Command::Balance => { bot.send_message(message.chat.id, balance(&bot, message)).await?; },
+        Command::Season => season_handler(bot, message).await,
+        Command::Tournament => tournament_handler(bot, message).await,
+        Command::H2h => { bot.send_message(message.chat.id, h2h(&bot, message)).await?; },
+        Command::Me => { bot.send_message(message.chat.id, me(&bot, message)).await?; },
+        Command::Export => export_handler(bot, message).await,
+        Command::Chart => chart_handler(bot, message).await,
+        Command::Import => { bot.send_message(message.chat.id, import(&bot, message).await.unwrap_or_else(|e| e.to_string())).await?; },
     };
     Ok(())
 }
@@ -71,7 +134,15 @@ async fn answer(
 async fn end_game_handler(bot: Bot, message: Message) {
     let id = message.chat.id;
     match end_game(&bot, message).await {
-        Ok(file) => { let _ = bot.send_document(id, file).await; },
+        Ok((image, file, summary)) => { 
+            if let Some(image) = image {
+                let _ = bot.send_photo(id, image).await;
+            }
+            let _ = bot.send_document(id, file).await; 
+            for text in summary.into_iter() {
+                let _ = bot.send_message(id, text).await;
+            }
+        },
         Err(e) => {let _ = bot.send_message(id, e.to_string()).await;},
     };
 }
@@ -80,7 +151,68 @@ async fn end_game_handler(bot: Bot, message: Message) {
 async fn game_state_handler(bot: Bot, message: Message) {
     let id = message.chat.id;
     match game_state(&bot, message).await {
+        Ok(ScoreOutput::Photo(image)) => { let _ = bot.send_photo(id, image).await; },
+        Ok(ScoreOutput::Document(file)) => { let _ = bot.send_document(id, file).await; },
+        Ok(ScoreOutput::Text(text)) => { let _ = bot.send_message(id, text).parse_mode(ParseMode::Html).await; },
+        Err(e) => {let _ = bot.send_message(id, e.to_string()).await;},
+    };
+}
+
+async fn leaderboard_handler(bot: Bot, message: Message) {
+    let id = message.chat.id;
+    match leaderboard(&bot, message) {
+        Ok((text, output)) => { 
+            let _ = bot.send_message(id, text).await;
+            match output {
+                ScoreOutput::Photo(image) => { let _ = bot.send_photo(id, image).await; },
+                ScoreOutput::Document(file) => { let _ = bot.send_document(id, file).await; },
+                ScoreOutput::Text(_) => (),
+            };
+        },
+        Err(e) => {let _ = bot.send_message(id, e.to_string()).await;},
+    };
+}
+
+async fn season_handler(bot: Bot, message: Message) {
+    let id = message.chat.id;
+    match season(&bot, message) {
+        Ok((text, file)) => { 
+            let _ = bot.send_message(id, text).await;
+            if let Some(file) = file {
+                let _ = bot.send_document(id, file).await; 
+            }
+        },
+        Err(e) => {let _ = bot.send_message(id, e.to_string()).await;},
+    };
+}
+
+async fn tournament_handler(bot: Bot, message: Message) {
+    let id = message.chat.id;
+    match tournament(&bot, message).await {
+        Ok((text, file)) => { 
+            let _ = bot.send_message(id, text).await;
+            if let Some(file) = file {
+                let _ = bot.send_document(id, file).await; 
+            }
+        },
+        Err(e) => {let _ = bot.send_message(id, e.to_string()).await;},
+    };
+}
+
+async fn export_handler(bot: Bot, message: Message) {
+    let id = message.chat.id;
+    match export(&bot, message).await {
         Ok(file) => { let _ = bot.send_document(id, file).await; },
         Err(e) => {let _ = bot.send_message(id, e.to_string()).await;},
     };
+}
+
+async fn chart_handler(bot: Bot, message: Message) {
+    let id = message.chat.id;
+    match chart(&bot, message).await {
+        Ok(ScoreOutput::Photo(image)) => { let _ = bot.send_photo(id, image).await; },
+        Ok(ScoreOutput::Document(file)) => { let _ = bot.send_document(id, file).await; },
+        Ok(ScoreOutput::Text(text)) => { let _ = bot.send_message(id, text).parse_mode(ParseMode::Html).await; },
+        Err(e) => {let _ = bot.send_message(id, e.to_string()).await;},
+    };
 }
\ No newline at end of file
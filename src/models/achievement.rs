@@ -0,0 +1,29 @@
+use chrono::Utc;
+use uuid::Uuid;
+use super::schema::achievements;
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "achievements"]
+pub struct PlayerAchievement {
+    pub id: String,
+    pub chat_id: String,
+    pub player_id: String,
+    pub game_id: String,
+    pub code: String,
+    pub description: String,
+    pub created_at: String,
+}
+
+impl PlayerAchievement {
+    pub fn from(chat_id: String, player_id: String, game_id: String, code: String, description: String) -> Self {
+        Self { 
+            id: Uuid::new_v4().to_string(), 
+            chat_id, 
+            player_id, 
+            game_id, 
+            code, 
+            description, 
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
\ No newline at end of file
@@ -1,4 +1,21 @@
+use super::schema::chats;
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "chats"]
 pub struct Chat {
-    telegram_id: String,
-    default_game: String,
+    pub telegram_id: String,
+    pub default_game: String,
+    pub score_format: String, // what a bare /score sends: image | text | html
+    pub theme: String,        // html sheets: light | dark | print
+}
+
+impl Chat {
+    pub fn from(telegram_id: String) -> Self {
+        Self { 
+            telegram_id, 
+            default_game: "tarok".to_string(), 
+            score_format: "image".to_string(),
+            theme: "light".to_string(),
+        }
+    }
 }
\ No newline at end of file
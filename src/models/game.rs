@@ -0,0 +1,41 @@
+use uuid::Uuid;
+use super::schema::{games, game_results};
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "games"]
+pub struct ArchivedGame {
+    pub id: String,
+    pub chat_id: String,
+    pub game_type: String,
+    pub finished_at: String,
+    pub season_id: Option<String>,
+    pub data: Option<String>,     // GameExport as json, missing for games archived before exports
+}
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "game_results"]
+pub struct GameResult {
+    pub id: String,
+    pub game_id: String,
+    pub player_id: String,
+    pub total: i32,
+    pub placement: i32,
+}
+
+impl ArchivedGame {
+    pub fn from(id: String, chat_id: String, game_type: String, finished_at: String, season_id: Option<String>, data: Option<String>) -> Self {
+        Self { id, chat_id, game_type, finished_at, season_id, data }
+    }
+}
+
+impl GameResult {
+    pub fn from(game_id: String, player_id: String, total: i32, placement: i32) -> Self {
+        Self { 
+            id: Uuid::new_v4().to_string(), 
+            game_id, 
+            player_id, 
+            total, 
+            placement 
+        }
+    }
+}
\ No newline at end of file
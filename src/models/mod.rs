@@ -1,5 +1,20 @@
+// diesel 1.4 `table!` and its Queryable/Insertable derives wrap their impls in
+// a local `const _` block, so non_local_definitions can only be silenced per module
+#[allow(non_local_definitions)]
+pub mod achievement;
+#[allow(non_local_definitions)]
 pub mod chat;
+#[allow(non_local_definitions)]
+pub mod game;
+#[allow(non_local_definitions)]
 pub mod round;
+#[allow(non_local_definitions)]
+pub mod rules;
+#[allow(non_local_definitions)]
 pub mod schema;
-pub mod state;
-pub mod user;
\ No newline at end of file
+#[allow(non_local_definitions)]
+pub mod season;
+#[allow(non_local_definitions)]
+pub mod settlement;
+#[allow(non_local_definitions)]
+pub mod user;
@@ -1,8 +1,26 @@
+use uuid::Uuid;
+use super::schema::rounds;
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "rounds"]
 pub struct Round {
-    id: String,
-    chat_id: String,
-    round_id: String,
-    game_id: String,
-    player_id: String,
-    tags: String,
+    pub id: String,
+    pub chat_id: String,
+    pub round_id: String,
+    pub game_id: String,
+    pub player_id: String,
+    pub tags: String,
+}
+
+impl Round {
+    pub fn from(chat_id: String, round_id: String, game_id: String, player_id: String, tags: String) -> Self {
+        Self { 
+            id: Uuid::new_v4().to_string(), 
+            chat_id, 
+            round_id, 
+            game_id, 
+            player_id, 
+            tags 
+        }
+    }
 }
\ No newline at end of file
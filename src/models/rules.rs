@@ -0,0 +1,14 @@
+use super::schema::chat_rules;
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "chat_rules"]
+pub struct ChatRules {
+    pub chat_id: String,
+    pub data: String, // TarokRules as json
+}
+
+impl ChatRules {
+    pub fn from(chat_id: String, data: String) -> Self {
+        Self { chat_id, data }
+    }
+}
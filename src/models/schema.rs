@@ -21,6 +21,78 @@ table! {
     chats (telegram_id) {
         telegram_id -> Text,
         default_game -> Text,
+        score_format -> Text,
+        theme -> Text,
+    }
+}
+
+table! {
+    games (id) {
+        id -> Text,
+        chat_id -> Text,
+        game_type -> Text,
+        finished_at -> Text,
+        season_id -> Nullable<Text>,
+        data -> Nullable<Text>,
+    }
+}
+
+table! {
+    game_results (id) {
+        id -> Text,
+        game_id -> Text,
+        player_id -> Text,
+        total -> Integer,
+        placement -> Integer,
+    }
+}
+
+table! {
+    settlements (chat_id) {
+        chat_id -> Text,
+        mode -> Text,
+        rate -> Integer,
+    }
+}
+
+table! {
+    ledger_entries (id) {
+        id -> Text,
+        chat_id -> Text,
+        game_id -> Text,
+        player_id -> Text,
+        amount -> Integer,
+        created_at -> Text,
+    }
+}
+
+table! {
+    seasons (id) {
+        id -> Text,
+        chat_id -> Text,
+        name -> Text,
+        scoring -> Text,
+        started_at -> Text,
+        ended_at -> Nullable<Text>,
+    }
+}
+
+table! {
+    achievements (id) {
+        id -> Text,
+        chat_id -> Text,
+        player_id -> Text,
+        game_id -> Text,
+        code -> Text,
+        description -> Text,
+        created_at -> Text,
+    }
+}
+
+table! {
+    chat_rules (chat_id) {
+        chat_id -> Text,
+        data -> Text,
     }
 }
 
@@ -28,4 +100,11 @@ allow_tables_to_appear_in_same_query!(
     rounds,
     chats,
     users,
+    games,
+    game_results,
+    settlements,
+    ledger_entries,
+    seasons,
+    achievements,
+    chat_rules,
 );
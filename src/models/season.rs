@@ -0,0 +1,27 @@
+use chrono::Utc;
+use uuid::Uuid;
+use super::schema::seasons;
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "seasons"]
+pub struct Season {
+    pub id: String,
+    pub chat_id: String,
+    pub name: String,
+    pub scoring: String,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+}
+
+impl Season {
+    pub fn from(chat_id: String, name: String, scoring: String) -> Self {
+        Self { 
+            id: Uuid::new_v4().to_string(), 
+            chat_id, 
+            name, 
+            scoring, 
+            started_at: Utc::now().to_rfc3339(), 
+            ended_at: None,
+        }
+    }
+}
\ No newline at end of file
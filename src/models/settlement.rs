@@ -0,0 +1,45 @@
+use chrono::Utc;
+use uuid::Uuid;
+use super::schema::{settlements, ledger_entries};
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "settlements"]
+pub struct SettlementConfig {
+    pub chat_id: String,
+    pub mode: String,
+    pub rate: i32, // cents per point
+}
+
+#[derive(Debug, Queryable, Insertable, Clone)]
+#[table_name = "ledger_entries"]
+pub struct LedgerEntry {
+    pub id: String,
+    pub chat_id: String,
+    pub game_id: String,
+    pub player_id: String,
+    pub amount: i32, // cents, positive when the player is owed money
+    pub created_at: String,
+}
+
+impl SettlementConfig {
+    pub fn from(chat_id: String) -> Self {
+        Self { 
+            chat_id, 
+            mode: "off".to_string(), 
+            rate: 0 
+        }
+    }
+}
+
+impl LedgerEntry {
+    pub fn from(chat_id: String, game_id: String, player_id: String, amount: i32) -> Self {
+        Self { 
+            id: Uuid::new_v4().to_string(), 
+            chat_id, 
+            game_id, 
+            player_id, 
+            amount,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
\ No newline at end of file
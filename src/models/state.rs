@@ -1,5 +0,0 @@
-pub enum ChatState {
-    Idle(String), 
-    Playing(String),
-    Ending,
-}
\ No newline at end of file
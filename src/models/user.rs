@@ -1,4 +1,4 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 use uuid::Uuid;
 use super::schema::users;
 
@@ -33,8 +33,8 @@ impl User {
                 name: new_user.name,
                 chat_id: new_user.chat_id
             }),
-            NameCheckedState::Invalid => Err(Error::new(ErrorKind::Other, "Invalid user name")),
-            NameCheckedState::Unchecked => Err(Error::new(ErrorKind::Other, "User name not yet checked")),
+            NameCheckedState::Invalid => Err(Error::other("Invalid user name")),
+            NameCheckedState::Unchecked => Err(Error::other("User name not yet checked")),
         }
     }
 }